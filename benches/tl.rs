@@ -24,12 +24,69 @@ const INPUT: &str = r#"
 </html>
 "#;
 
+const SVG_PATH: &str = r#"<path id="p" class="icon stroke" d="M10 10 L20 20 Z" fill="none" stroke="currentColor" stroke-width="2" stroke-linecap="round" stroke-linejoin="round" stroke-dasharray="4 2" stroke-dashoffset="1" transform="translate(0 0)" vector-effect="non-scaling-stroke" data-name="path" aria-hidden="true" />"#;
+
+const PARAGRAPH: &str = r#"<p>Lorem ipsum <b>dolor</b> sit amet, <a href="/x">consectetur</a> adipiscing elit, sed do <em>eiusmod <i>tempor</i></em> incididunt ut labore et dolore magna aliqua.</p>"#;
+
 pub fn criterion_benchmark(cr: &mut Criterion) {
     cr.bench_function("tl", |b| {
         b.iter(|| {
             let _ = tl::parse(black_box(INPUT), tl::ParserOptions::default());
         });
     });
+
+    let svg = format!("<svg>{}</svg>", SVG_PATH.repeat(200));
+    cr.bench_function("tl attributes", |b| {
+        b.iter(|| {
+            let _ = tl::parse(black_box(&svg), tl::ParserOptions::default());
+        });
+    });
+
+    let dom = tl::parse(INPUT, tl::ParserOptions::default()).unwrap();
+    cr.bench_function("tl outer_html", |b| {
+        b.iter(|| black_box(&dom).outer_html());
+    });
+
+    let article = format!("<article>{}</article>", PARAGRAPH.repeat(500));
+    let dom = tl::parse(&article, tl::ParserOptions::default()).unwrap();
+    let parser = dom.parser();
+    let root = dom.children()[0].get(parser).unwrap();
+    cr.bench_function("tl inner_text", |b| {
+        b.iter(|| black_box(root).inner_text(parser));
+    });
+
+    for (name, options) in [
+        ("tl query_selector", tl::ParserOptions::default()),
+        (
+            "tl query_selector interned",
+            tl::ParserOptions::default().intern_tag_names(),
+        ),
+    ] {
+        let dom = tl::parse(&article, options).unwrap();
+        cr.bench_function(name, |b| {
+            b.iter(|| {
+                black_box(&dom)
+                    .query_selector("p > a, em i")
+                    .unwrap()
+                    .count()
+            });
+        });
+    }
+
+    let dom = tl::parse(&article, tl::ParserOptions::default()).unwrap();
+    let parser = dom.parser();
+    let selector = tl::parse_query_selector("a[href], em, b").unwrap();
+    cr.bench_function("tl matches_selector", |b| {
+        b.iter(|| {
+            black_box(&dom)
+                .nodes()
+                .iter()
+                .enumerate()
+                .filter_map(|(id, node)| Some((tl::NodeHandle::new(id as u32), node.as_tag()?)))
+                .filter(|(handle, tag)| tag.matches_selector(parser, *handle, &selector))
+                .count()
+        });
+    });
 }
 
 criterion_group!(benches, criterion_benchmark);
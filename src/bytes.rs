@@ -4,6 +4,7 @@ use std::{
     hash::{Hash, Hasher},
     marker::PhantomData,
     mem::ManuallyDrop,
+    str::Utf8Error,
 };
 
 use crate::errors::SetBytesError;
@@ -40,6 +41,34 @@ impl<'a> PartialEq<str> for Bytes<'a> {
     }
 }
 
+impl<'a> PartialEq<&str> for Bytes<'a> {
+    #[inline]
+    fn eq(&self, other: &&str) -> bool {
+        self == other.as_bytes()
+    }
+}
+
+impl<'a> PartialEq<Bytes<'a>> for str {
+    #[inline]
+    fn eq(&self, other: &Bytes<'a>) -> bool {
+        other == self
+    }
+}
+
+impl<'a> PartialEq<Bytes<'a>> for &str {
+    #[inline]
+    fn eq(&self, other: &Bytes<'a>) -> bool {
+        other == *self
+    }
+}
+
+impl<'a> PartialEq<&[u8]> for Bytes<'a> {
+    #[inline]
+    fn eq(&self, other: &&[u8]) -> bool {
+        self.as_bytes() == *other
+    }
+}
+
 impl<'a> PartialEq<[u8]> for Bytes<'a> {
     #[inline]
     fn eq(&self, other: &[u8]) -> bool {
@@ -65,6 +94,13 @@ impl<'a> Hash for Bytes<'a> {
     }
 }
 
+impl<'a> AsRef<[u8]> for Bytes<'a> {
+    #[inline]
+    fn as_ref(&self) -> &[u8] {
+        self.as_bytes()
+    }
+}
+
 impl<'a> Clone for Bytes<'a> {
     fn clone(&self) -> Self {
         // It is important to manually implement Clone for Bytes,
@@ -115,6 +151,17 @@ impl TryFrom<String> for Bytes<'static> {
     }
 }
 
+impl TryFrom<Vec<u8>> for Bytes<'static> {
+    type Error = SetBytesError;
+
+    #[inline]
+    fn try_from(v: Vec<u8>) -> Result<Self, Self::Error> {
+        let mut bytes = Bytes::new();
+        bytes.set(v)?;
+        Ok(bytes)
+    }
+}
+
 /// Converts `Bytes` raw parts to a slice
 #[inline]
 unsafe fn compact_bytes_to_slice<'a>(ptr: *const u8, l: u32) -> &'a [u8] {
@@ -148,6 +195,13 @@ impl<'a> Debug for Bytes<'a> {
     }
 }
 
+impl<'a> Default for Bytes<'a> {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl<'a> Bytes<'a> {
     /// Creates an empty `Bytes`
     #[inline]
@@ -158,17 +212,55 @@ impl<'a> Bytes<'a> {
         }
     }
 
+    /// Creates a `Bytes` that borrows static data, in constant contexts
+    pub(crate) const fn from_static(s: &'static [u8]) -> Bytes<'static> {
+        Bytes {
+            data: BytesInner::Borrowed(s.as_ptr(), s.len() as u32),
+            _lt: PhantomData,
+        }
+    }
+
     /// Convenient method for lossy-encoding the data as UTF8
+    ///
+    /// Invalid UTF-8 sequences are replaced with U+FFFD, in which case a new string is allocated.
+    /// This is the same as [`Bytes::as_utf8_str_lossy`].
     #[inline]
     pub fn as_utf8_str(&self) -> Cow<'_, str> {
         String::from_utf8_lossy(self.as_bytes())
     }
 
+    /// Converts the data to a string, replacing invalid UTF-8 sequences with U+FFFD
+    ///
+    /// This never fails, and only allocates if the data is not valid UTF-8.
+    /// Use [`Bytes::to_str`] to detect invalid UTF-8 instead.
+    #[inline]
+    pub fn as_utf8_str_lossy(&self) -> Cow<'_, str> {
+        self.as_utf8_str()
+    }
+
+    /// Converts the data to a `&str` without allocating, or returns an error if it is not valid UTF-8
+    ///
+    /// # Example
+    /// ```
+    /// let bytes = tl::Bytes::from("hello");
+    /// assert_eq!(bytes.to_str(), Ok("hello"));
+    ///
+    /// let invalid = tl::Bytes::from(&b"\xff"[..]);
+    /// assert!(invalid.to_str().is_err());
+    /// assert_eq!(invalid.as_utf8_str_lossy(), "\u{fffd}");
+    /// ```
+    #[inline]
+    pub fn to_str(&self) -> Result<&str, Utf8Error> {
+        std::str::from_utf8(self.as_bytes())
+    }
+
     /// Tries to convert the inner data to a `&str`, without allocating in the case
     /// that the inner data is not valid UTF8
+    ///
+    /// This is [`Bytes::to_str`], with the error discarded.
     #[inline]
     pub fn try_as_utf8_str(&self) -> Option<&str> {
-        std::str::from_utf8(self.as_bytes()).ok()
+        self.to_str().ok()
     }
 
     /// Returns the raw data wrapped by this struct
@@ -201,6 +293,77 @@ impl<'a> Bytes<'a> {
         }
     }
 
+    /// Copies the data into a new, owned `Bytes` that is not tied to the lifetime of the source string
+    pub(crate) fn to_static(&self) -> Bytes<'static> {
+        let mut bytes = Bytes::new();
+        // SAFETY: the length of `self` already fits in a u32
+        unsafe { bytes.set_unchecked(self.as_bytes()) };
+        bytes
+    }
+
+    /// Returns the data with leading and trailing ASCII whitespace removed
+    ///
+    /// If the data is borrowed, this returns a view into the same source string without allocating.
+    /// Owned data is copied, because a borrowed view into it could outlive `self`.
+    ///
+    /// # Example
+    /// ```
+    /// let bytes = tl::Bytes::from("  hello \n");
+    /// assert_eq!(bytes.trim(), "hello");
+    /// ```
+    pub fn trim(&self) -> Bytes<'a> {
+        match self.as_bytes_borrowed() {
+            Some(bytes) => Bytes::from(bytes.trim_ascii()),
+            None => {
+                let mut bytes = Bytes::new();
+                // SAFETY: the trimmed data is not longer than `self`, whose length already fits in a u32
+                unsafe { bytes.set_unchecked(self.as_bytes().trim_ascii()) };
+                bytes
+            }
+        }
+    }
+
+    /// Checks whether the data is equal to `other`, ignoring ASCII case
+    ///
+    /// # Example
+    /// ```
+    /// let bytes = tl::Bytes::from("Text/HTML");
+    /// assert!(bytes.eq_ignore_ascii_case(b"text/html"));
+    /// ```
+    #[inline]
+    pub fn eq_ignore_ascii_case(&self, other: &[u8]) -> bool {
+        self.as_bytes().eq_ignore_ascii_case(other)
+    }
+
+    /// Converts this `Bytes` into one that is not tied to the lifetime of the source string
+    ///
+    /// Borrowed data is copied into a new allocation. If the data is already owned, ownership is transferred
+    /// without copying.
+    ///
+    /// # Example
+    /// ```
+    /// let id = {
+    ///     let input = String::from("<div id=\"foo\"></div>");
+    ///     let dom = tl::parse(&input, Default::default()).unwrap();
+    ///     let tag = dom.nodes()[0].as_tag().unwrap();
+    ///     tag.attributes().id().unwrap().clone().into_owned()
+    /// };
+    /// assert_eq!(id, "foo");
+    /// ```
+    pub fn into_owned(self) -> Bytes<'static> {
+        match self.data {
+            BytesInner::Borrowed(..) => self.to_static(),
+            BytesInner::Owned(ptr, len) => {
+                // the allocation is moved into the new `Bytes`, so `self` must not free it
+                let _ = ManuallyDrop::new(self);
+                Bytes {
+                    data: BytesInner::Owned(ptr, len),
+                    _lt: PhantomData,
+                }
+            }
+        }
+    }
+
     /// Sets the inner data to the given data and returns the old bytes
     pub fn set<B: IntoOwnedBytes>(&mut self, data: B) -> Result<Option<Box<[u8]>>, SetBytesError> {
         const MAX: usize = u32::MAX as usize;
@@ -284,6 +447,52 @@ impl IntoOwnedBytes for String {
     }
 }
 
+/// Bytes are serialized as a string. Invalid UTF-8 is replaced with U+FFFD.
+#[cfg(feature = "serde")]
+impl serde::Serialize for Bytes<'_> {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.as_utf8_str())
+    }
+}
+
+/// Bytes are deserialized from a string or a byte array, and always own their data.
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for Bytes<'static> {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct BytesVisitor;
+
+        impl<'de> serde::de::Visitor<'de> for BytesVisitor {
+            type Value = Bytes<'static>;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.write_str("a string or bytes")
+            }
+
+            fn visit_str<E: serde::de::Error>(self, v: &str) -> Result<Self::Value, E> {
+                self.visit_bytes(v.as_bytes())
+            }
+
+            fn visit_string<E: serde::de::Error>(self, v: String) -> Result<Self::Value, E> {
+                self.visit_byte_buf(v.into_bytes())
+            }
+
+            fn visit_bytes<E: serde::de::Error>(self, v: &[u8]) -> Result<Self::Value, E> {
+                let mut bytes = Bytes::new();
+                bytes.set(v).map_err(E::custom)?;
+                Ok(bytes)
+            }
+
+            fn visit_byte_buf<E: serde::de::Error>(self, v: Vec<u8>) -> Result<Self::Value, E> {
+                let mut bytes = Bytes::new();
+                bytes.set(v).map_err(E::custom)?;
+                Ok(bytes)
+            }
+        }
+
+        deserializer.deserialize_string(BytesVisitor)
+    }
+}
+
 impl Drop for BytesInner {
     fn drop(&mut self) {
         // we only need to deallocate if we own the data
@@ -0,0 +1,185 @@
+use std::{borrow::Cow, fmt};
+
+/// Named character references that are decoded, as `(name, value, legacy)`
+///
+/// Legacy references are also recognized without a trailing semicolon, like browsers do.
+/// In text, this includes legacy references that are a prefix of a longer name: `&copyright` decodes to `©right`.
+const NAMED_ENTITIES: &[(&str, &str, bool)] = &[
+    ("amp", "&", true),
+    ("lt", "<", true),
+    ("gt", ">", true),
+    ("quot", "\"", true),
+    ("apos", "'", false),
+    ("nbsp", "\u{a0}", true),
+    ("copy", "\u{a9}", true),
+    ("reg", "\u{ae}", true),
+    ("deg", "\u{b0}", true),
+    ("times", "\u{d7}", true),
+    ("divide", "\u{f7}", true),
+    ("cent", "\u{a2}", true),
+    ("pound", "\u{a3}", true),
+    ("yen", "\u{a5}", true),
+    ("sect", "\u{a7}", true),
+    ("para", "\u{b6}", true),
+    ("middot", "\u{b7}", true),
+    ("laquo", "\u{ab}", true),
+    ("raquo", "\u{bb}", true),
+    ("shy", "\u{ad}", true),
+    ("euro", "\u{20ac}", false),
+    ("trade", "\u{2122}", false),
+    ("hellip", "\u{2026}", false),
+    ("ndash", "\u{2013}", false),
+    ("mdash", "\u{2014}", false),
+    ("lsquo", "\u{2018}", false),
+    ("rsquo", "\u{2019}", false),
+    ("ldquo", "\u{201c}", false),
+    ("rdquo", "\u{201d}", false),
+    ("bull", "\u{2022}", false),
+];
+
+/// Longest numeric reference we bother parsing. Anything longer is out of range anyway.
+const MAX_NUMERIC_DIGITS: usize = 8;
+
+/// Tries to decode a single character reference at the start of `input`, which must begin right after the `&`.
+///
+/// Returns the decoded value and the number of bytes consumed after the `&`.
+/// In attribute values, legacy references without a semicolon are not decoded if they are followed by
+/// an alphanumeric character or `=`, so that URLs like `?a=1&copy=2` are preserved.
+fn decode_reference(input: &str, in_attribute: bool) -> Option<(Cow<'static, str>, usize)> {
+    let bytes = input.as_bytes();
+
+    if let Some(rest) = input.strip_prefix('#') {
+        let (radix, digits_start) = match rest.as_bytes().first() {
+            Some(b'x' | b'X') => (16, 2),
+            _ => (10, 1),
+        };
+
+        let digits = bytes[digits_start..]
+            .iter()
+            .take_while(|c| c.is_ascii_digit() || (radix == 16 && c.is_ascii_hexdigit()))
+            .count();
+
+        if digits == 0 || digits > MAX_NUMERIC_DIGITS {
+            return None;
+        }
+
+        let end = digits_start + digits;
+        let code = u32::from_str_radix(&input[digits_start..end], radix).ok()?;
+
+        // Null, surrogates and out of range code points are replaced, like browsers do
+        let decoded = match code {
+            0 => char::REPLACEMENT_CHARACTER,
+            _ => char::from_u32(code).unwrap_or(char::REPLACEMENT_CHARACTER),
+        };
+
+        // The semicolon is optional for numeric references
+        let consumed = end + (bytes.get(end) == Some(&b';')) as usize;
+        return Some((Cow::Owned(decoded.to_string()), consumed));
+    }
+
+    let len = bytes
+        .iter()
+        .take_while(|c| c.is_ascii_alphanumeric())
+        .count();
+    let name = &input[..len];
+
+    if bytes.get(len) == Some(&b';') {
+        if let Some(&(_, value, _)) = NAMED_ENTITIES.iter().find(|(entity, ..)| *entity == name) {
+            return Some((Cow::Borrowed(value), len + 1));
+        }
+    }
+
+    let &(entity, value, _) = NAMED_ENTITIES
+        .iter()
+        .filter(|(entity, _, legacy)| *legacy && name.starts_with(entity))
+        .max_by_key(|(entity, ..)| entity.len())?;
+
+    let next = bytes.get(entity.len());
+    if in_attribute && next.is_some_and(|&c| c.is_ascii_alphanumeric() || c == b'=') {
+        return None;
+    }
+
+    Some((Cow::Borrowed(value), entity.len()))
+}
+
+/// Decodes HTML character references (`&amp;`, `&#169;`, `&#x2764;`) in the given string
+///
+/// Malformed or unknown references are left untouched.
+/// If the input does not contain any references, no memory is allocated.
+pub fn decode(input: &str) -> Cow<'_, str> {
+    decode_with(input, false)
+}
+
+/// Like [`decode`], but follows the rules for attribute values
+pub fn decode_attribute(input: &str) -> Cow<'_, str> {
+    decode_with(input, true)
+}
+
+fn decode_with(input: &str, in_attribute: bool) -> Cow<'_, str> {
+    let mut output = String::new();
+    // Start of the part of `input` that has not been copied to `output` yet
+    let mut last = 0;
+    let mut search = 0;
+
+    while let Some(offset) = input[search..].find('&') {
+        let amp = search + offset;
+        search = amp + 1;
+
+        if let Some((value, consumed)) = decode_reference(&input[amp + 1..], in_attribute) {
+            output.push_str(&input[last..amp]);
+            output.push_str(&value);
+            last = amp + 1 + consumed;
+            search = last;
+        }
+    }
+
+    if last == 0 {
+        return Cow::Borrowed(input);
+    }
+
+    output.push_str(&input[last..]);
+    Cow::Owned(output)
+}
+
+/// Like [`decode`], but reuses the allocation of an already owned string if it contains no references
+pub fn decode_cow(input: Cow<'_, str>) -> Cow<'_, str> {
+    match input {
+        Cow::Borrowed(input) => decode(input),
+        Cow::Owned(input) => match decode(&input) {
+            Cow::Borrowed(_) => Cow::Owned(input),
+            Cow::Owned(decoded) => Cow::Owned(decoded),
+        },
+    }
+}
+
+/// Characters that are escaped in text
+pub const TEXT: &[u8] = b"&<>";
+/// Characters that are escaped in double quoted attribute values
+pub const ATTRIBUTE: &[u8] = b"&\"";
+/// Characters that always need to be escaped in double quoted attribute values, even if the value is not decoded
+pub const QUOTE: &[u8] = b"\"";
+
+/// Writes the input to the given writer, replacing each of the given characters with its character reference
+pub fn escape_to<W: fmt::Write>(out: &mut W, input: &str, chars: &[u8]) -> fmt::Result {
+    let mut last = 0;
+
+    for (index, byte) in input.bytes().enumerate() {
+        if !chars.contains(&byte) {
+            continue;
+        }
+
+        let reference = match byte {
+            b'&' => "&amp;",
+            b'<' => "&lt;",
+            b'>' => "&gt;",
+            b'"' => "&quot;",
+            _ => unreachable!("no character reference for {}", byte as char),
+        };
+
+        out.write_str(&input[last..index])?;
+        out.write_str(reference)?;
+        last = index + 1;
+    }
+
+    out.write_str(&input[last..])
+}
@@ -6,6 +6,26 @@ use std::error::Error;
 pub enum ParseError {
     /// The input string length was too large to fit in a `u32`
     InvalidLength,
+    /// The input ended in the middle of a tag
+    UnexpectedEof {
+        /// The byte offset of the `<` that starts the incomplete tag
+        offset: usize,
+    },
+    /// A tag was not terminated properly, e.g. `<div "x">`
+    MalformedTag {
+        /// The byte offset of the unexpected character
+        offset: usize,
+    },
+    /// A tag was never closed (only reported in strict mode)
+    UnclosedTag {
+        /// The byte offset of the start tag
+        offset: usize,
+    },
+    /// An end tag did not match the last open tag (only reported in strict mode)
+    MismatchedEndTag {
+        /// The byte offset of the `<` that starts the end tag
+        offset: usize,
+    },
 }
 
 impl fmt::Display for ParseError {
@@ -14,6 +34,24 @@ impl fmt::Display for ParseError {
             ParseError::InvalidLength => {
                 write!(f, "The input string length is too large to fit in a `u32`")
             }
+            ParseError::UnexpectedEof { offset } => {
+                write!(
+                    f,
+                    "Unexpected end of input in tag starting at byte {offset}"
+                )
+            }
+            ParseError::MalformedTag { offset } => {
+                write!(f, "Malformed tag: unexpected character at byte {offset}")
+            }
+            ParseError::UnclosedTag { offset } => {
+                write!(f, "The tag starting at byte {offset} is never closed")
+            }
+            ParseError::MismatchedEndTag { offset } => {
+                write!(
+                    f,
+                    "The end tag at byte {offset} does not match the last open tag"
+                )
+            }
         }
     }
 }
@@ -38,3 +76,52 @@ impl fmt::Display for SetBytesError {
 }
 
 impl Error for SetBytesError {}
+
+/// An error that occurred while parsing a query selector
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct SelectorParseError {
+    /// The byte offset in the selector at which the error was detected
+    pub offset: usize,
+    /// What went wrong
+    pub kind: SelectorParseErrorKind,
+}
+
+/// The reason why a query selector could not be parsed
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum SelectorParseErrorKind {
+    /// The selector ended where more input was expected, e.g. `div >`
+    UnexpectedEof,
+    /// A specific character was expected, e.g. the closing `]` in `[href`
+    Expected(char),
+    /// A character that cannot appear at this position, e.g. the `)` in `div)`
+    UnexpectedCharacter(char),
+    /// The pseudo-class is not supported, e.g. `:hover`
+    UnknownPseudoClass,
+    /// The argument of `:nth-child()` or `:nth-of-type()` is not a valid `An+B` expression
+    InvalidNth,
+}
+
+impl fmt::Display for SelectorParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        let offset = self.offset;
+        match self.kind {
+            SelectorParseErrorKind::UnexpectedEof => {
+                write!(f, "Unexpected end of selector at byte {offset}")
+            }
+            SelectorParseErrorKind::Expected(c) => {
+                write!(f, "Expected `{c}` at byte {offset}")
+            }
+            SelectorParseErrorKind::UnexpectedCharacter(c) => {
+                write!(f, "Unexpected character `{c}` at byte {offset}")
+            }
+            SelectorParseErrorKind::UnknownPseudoClass => {
+                write!(f, "Unknown pseudo-class at byte {offset}")
+            }
+            SelectorParseErrorKind::InvalidNth => {
+                write!(f, "Invalid `An+B` expression at byte {offset}")
+            }
+        }
+    }
+}
+
+impl Error for SelectorParseError {}
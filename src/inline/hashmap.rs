@@ -9,6 +9,9 @@ use std::{collections::HashMap, mem::MaybeUninit};
 ///
 /// Hashing can be slower than just iterating through an array
 /// if the array is small, which is where it makes most sense
+///
+/// Unlike `HashMap`, this map remembers the order in which keys were inserted,
+/// and iterating over it yields the elements in that order.
 #[derive(Debug, Clone)]
 pub struct InlineHashMap<K, V, const N: usize>(InlineHashMapInner<K, V, N>);
 
@@ -27,20 +30,35 @@ where
         self.0.len()
     }
 
-    /// Returns an iterator over the elements of this map
+    /// Checks whether the map is empty
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns an iterator over the elements of this map, in insertion order
     ///
     /// This function boxes the returned iterator because it can be either of two:
-    /// - The iterator returned by `HashMap::iter()`
+    /// - The iterator over the heap-allocated map
     /// - The iterator over a stack-allocated array
     #[inline]
     pub fn iter(&self) -> Box<dyn Iterator<Item = (&K, &V)> + '_> {
         self.0.iter()
     }
 
+    /// Returns an iterator over the elements of this map, with mutable references to the values
+    ///
+    /// Like [`InlineHashMap::iter`], the returned iterator is boxed.
+    #[inline]
+    pub fn iter_mut(&mut self) -> Box<dyn Iterator<Item = (&K, &mut V)> + '_> {
+        self.0.iter_mut()
+    }
+
     /// If `self` is inlined, this returns the underlying raw parts that make up this `InlineHashMap`.
     ///
     /// Only the first `.1` elements are initialized.
     #[inline]
+    #[allow(clippy::type_complexity)]
     pub fn inline_parts_mut(&mut self) -> Option<(&mut [MaybeUninit<(K, V)>; N], usize)> {
         self.0.inline_parts_mut()
     }
@@ -62,8 +80,13 @@ where
     }
 
     /// Inserts a new element into the map
+    ///
+    /// If the map already contains the key, its value is replaced and the key keeps its position.
     #[inline]
-    pub fn insert(&mut self, key: K, value: V) {
+    pub fn insert(&mut self, key: K, value: V)
+    where
+        K: Clone,
+    {
         self.0.insert(key, value)
     }
 
@@ -97,7 +120,87 @@ enum InlineHashMapInner<K, V, const N: usize> {
         len: usize,
         data: [MaybeUninit<(K, V)>; N],
     },
-    Heap(HashMap<K, V>),
+    Heap(OrderedHashMap<K, V>),
+}
+
+/// A `HashMap` that remembers the insertion order of its keys
+///
+/// The elements are stored in a vector in insertion order, so iterating over them does not allocate.
+/// A side index maps every key to its position in that vector, which is why inserting clones the key.
+#[derive(Clone)]
+struct OrderedHashMap<K, V> {
+    entries: Vec<(K, V)>,
+    /// Maps every key to the index of its element in `entries`
+    index: HashMap<K, usize>,
+}
+
+impl<K, V> OrderedHashMap<K, V> {
+    fn iter(&self) -> impl Iterator<Item = (&K, &V)> {
+        self.entries.iter().map(|(k, v)| (k, v))
+    }
+
+    fn iter_mut(&mut self) -> impl Iterator<Item = (&K, &mut V)> {
+        self.entries.iter_mut().map(|(k, v)| (&*k, v))
+    }
+
+    fn len(&self) -> usize {
+        self.entries.len()
+    }
+}
+
+impl<K: Eq + Hash, V> OrderedHashMap<K, V> {
+    fn with_capacity(capacity: usize) -> Self {
+        Self {
+            entries: Vec::with_capacity(capacity),
+            index: HashMap::with_capacity(capacity),
+        }
+    }
+
+    fn get(&self, k: &K) -> Option<&V> {
+        self.index.get(k).map(|&idx| &self.entries[idx].1)
+    }
+
+    fn get_mut(&mut self, k: &K) -> Option<&mut V> {
+        self.index.get(k).map(|&idx| &mut self.entries[idx].1)
+    }
+
+    /// Removes an element. The elements after it are shifted to keep them in insertion order.
+    fn remove(&mut self, k: &K) -> Option<V> {
+        let removed = self.index.remove(k)?;
+        for idx in self.index.values_mut() {
+            if *idx > removed {
+                *idx -= 1;
+            }
+        }
+
+        Some(self.entries.remove(removed).1)
+    }
+
+    /// Inserts an element. If the key already exists, the value is replaced but the key keeps its position.
+    fn insert(&mut self, k: K, v: V)
+    where
+        K: Clone,
+    {
+        match self.index.get(&k) {
+            Some(&idx) => self.entries[idx].1 = v,
+            None => {
+                self.index.insert(k.clone(), self.entries.len());
+                self.entries.push((k, v));
+            }
+        }
+    }
+
+    fn contains_key(&self, k: &K) -> bool {
+        self.index.contains_key(k)
+    }
+
+    fn to_map(&self) -> HashMap<K, V>
+    where
+        K: Clone,
+        V: Clone,
+    {
+        self.entries.iter().cloned().collect()
+    }
 }
 
 impl<K, V, const N: usize> Debug for InlineHashMapInner<K, V, N>
@@ -168,6 +271,17 @@ impl<K, V, const N: usize> InlineHashMapInner<K, V, N> {
     }
 
     #[inline]
+    pub fn iter_mut(&mut self) -> Box<dyn Iterator<Item = (&K, &mut V)> + '_> {
+        match self {
+            Self::Inline { len, data } => Box::new(
+                unsafe { InlineHashMapIteratorMut::new(data, *len) }.map(|(k, v)| (&*k, v)),
+            ),
+            Self::Heap(h) => Box::new(h.iter_mut()),
+        }
+    }
+
+    #[inline]
+    #[allow(clippy::type_complexity)]
     pub fn inline_parts_mut(&mut self) -> Option<(&mut [MaybeUninit<(K, V)>; N], usize)> {
         match self {
             Self::Heap(_) => None,
@@ -182,11 +296,11 @@ impl<K, V, const N: usize> InlineHashMapInner<K, V, N> {
         V: Clone,
     {
         match &self {
-            InlineHashMapInner::Heap(m) => m.clone(),
+            InlineHashMapInner::Heap(m) => m.to_map(),
             InlineHashMapInner::Inline { len, data } => {
                 let mut new_data = HashMap::with_capacity(*len);
 
-                let iter = data.into_iter().take(*len);
+                let iter = data.iter().take(*len);
 
                 for element in iter {
                     let element = unsafe { &*element.as_ptr() };
@@ -249,9 +363,8 @@ impl<K: Eq + Hash, V, const N: usize> InlineHashMapInner<K, V, N> {
                     std::mem::replace(data.get_unchecked_mut(idx), MaybeUninit::uninit())
                 };
 
-                // HashMap order is not guaranteed, so instead of swapping every item like we do with InlineVec,
-                // we can simply swap the last item with the one we want to remove.
-                data.swap(idx, *len - 1);
+                // shift the remaining items to keep them in insertion order
+                data[idx..*len].rotate_left(1);
                 *len -= 1;
 
                 Some(unsafe { element.assume_init().1 })
@@ -260,7 +373,15 @@ impl<K: Eq + Hash, V, const N: usize> InlineHashMapInner<K, V, N> {
         }
     }
 
-    pub fn insert(&mut self, k: K, v: V) {
+    pub fn insert(&mut self, k: K, v: V)
+    where
+        K: Clone,
+    {
+        if let Some(value) = self.get_mut(&k) {
+            *value = v;
+            return;
+        }
+
         let (array, len) = match self {
             Self::Inline { data, len } => (data, len),
             Self::Heap(map) => {
@@ -270,7 +391,7 @@ impl<K: Eq + Hash, V, const N: usize> InlineHashMapInner<K, V, N> {
         };
 
         if *len >= N {
-            let mut map = HashMap::with_capacity(*len);
+            let mut map = OrderedHashMap::with_capacity(*len + 1);
 
             // move old elements to heap
             for element in array.iter_mut().take(*len) {
@@ -286,7 +407,6 @@ impl<K: Eq + Hash, V, const N: usize> InlineHashMapInner<K, V, N> {
 
             // do not call the destructor!
             unsafe { ptr::write(self, new_heap) };
-            return;
         } else {
             array[*len].write((k, v));
             *len += 1;
@@ -373,9 +493,7 @@ mod tests {
 
         let mut iter = x.iter();
 
-        // order is guaranteed as long as:
-        // - `InlineHashMap` is a stack-allocated array
-        // - `x.remove()` is never called
+        // elements are iterated in insertion order
 
         assert_eq!(iter.next(), Some((&"foo".into(), &3usize)));
         assert_eq!(iter.next(), Some((&"bar".into(), &6usize)));
@@ -383,6 +501,68 @@ mod tests {
         assert_eq!(iter.next(), Some((&"qux".into(), &9usize)));
     }
 
+    #[test]
+    fn inlinehashmap_iter_order() {
+        let mut x = InlineHashMap::<usize, usize, 4>::new();
+        for i in [5, 3, 9, 1] {
+            x.insert(i, i);
+        }
+
+        x.remove(&3);
+        assert!(x.iter().map(|(k, _)| *k).eq([5, 9, 1]));
+
+        for i in [0, 8, 2] {
+            x.insert(i, i);
+        }
+        assert!(x.is_heap_allocated());
+
+        x.remove(&8);
+        x.insert(9, 10);
+        assert!(x.iter().map(|(k, _)| *k).eq([5, 9, 1, 0, 2]));
+        assert!(x.iter_mut().map(|(k, _)| *k).eq([5, 9, 1, 0, 2]));
+        assert_eq!(x.get(&9), Some(&10));
+
+        // the index of the elements after a removed one is updated
+        x.remove(&5);
+        assert!(x.iter().map(|(k, _)| *k).eq([9, 1, 0, 2]));
+        assert_eq!(x.get(&2), Some(&2));
+        assert!(x.contains_key(&0));
+        assert!(!x.contains_key(&5));
+    }
+
+    #[test]
+    fn inlinehashmap_insert_existing() {
+        let mut x = InlineHashMap::<usize, usize, 2>::new();
+        x.insert(1, 1);
+        x.insert(2, 2);
+        x.insert(1, 3);
+        assert_eq!(x.len(), 2);
+        assert!(!x.is_heap_allocated());
+        assert!(x.iter().eq([(&1, &3), (&2, &2)]));
+    }
+
+    #[test]
+    fn inlinehashmap_iter_mut() {
+        let mut x = InlineHashMap::<usize, usize, 2>::new();
+        x.insert(1, 10);
+        x.insert(2, 20);
+
+        for (k, v) in x.iter_mut() {
+            *v += k;
+        }
+        assert_eq!(x.get(&1), Some(&11));
+        assert_eq!(x.get(&2), Some(&22));
+
+        x.insert(3, 30);
+        assert!(x.is_heap_allocated());
+
+        for (_, v) in x.iter_mut() {
+            *v *= 2;
+        }
+        assert_eq!(x.get(&1), Some(&22));
+        assert_eq!(x.get(&3), Some(&60));
+    }
+
     #[test]
     fn inlinehashmap_remove() {
         let mut x = InlineHashMap::<usize, usize, 4>::new();
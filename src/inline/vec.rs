@@ -1,5 +1,5 @@
 use std::fmt::{Debug, Formatter};
-use std::mem::MaybeUninit;
+use std::mem::{ManuallyDrop, MaybeUninit};
 use std::ops::Index;
 use std::ptr;
 
@@ -20,6 +20,12 @@ impl<T, const N: usize> InlineVec<T, N> {
         self.0.len()
     }
 
+    /// Checks whether the vector is empty
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
     /// Checks whether this vector is allocated on the heap
     #[inline]
     pub fn is_heap_allocated(&self) -> bool {
@@ -61,6 +67,15 @@ impl<T, const N: usize> InlineVec<T, N> {
         self.0.get_mut(index)
     }
 
+    /// Inserts an element at a given index, shifting all elements after it to the right
+    ///
+    /// # Panics
+    /// Just like `Vec::insert`, this method will panic if the index is greater than the length.
+    #[inline]
+    pub fn insert(&mut self, index: usize, value: T) {
+        self.0.insert(index, value)
+    }
+
     /// Removes an element at a given index
     ///
     /// # Panics
@@ -70,17 +85,86 @@ impl<T, const N: usize> InlineVec<T, N> {
         self.0.remove(index)
     }
 
+    /// Retains only the elements for which `f` returns `true`, keeping their order
+    ///
+    /// A heap allocated vector stays on the heap, even if fewer than `N` elements are left.
+    #[inline]
+    pub fn retain<F: FnMut(&T) -> bool>(&mut self, f: F) {
+        self.0.retain(f)
+    }
+
+    /// Removes all elements from the vector
+    ///
+    /// A heap allocated vector keeps its allocation.
+    #[inline]
+    pub fn clear(&mut self) {
+        self.0.clear()
+    }
+
     /// Returns an iterator over the elements of this vector
     #[inline]
     pub fn iter(&self) -> InlineVecIter<'_, T, N> {
         self.0.iter()
     }
 
+    /// Returns an iterator that allows modifying each element of this vector
+    #[inline]
+    pub fn iter_mut(&mut self) -> std::slice::IterMut<'_, T> {
+        self.0.as_mut_slice().iter_mut()
+    }
+
     /// Returns a slice to the contents of this vector
     #[inline]
     pub fn as_slice(&self) -> &[T] {
         self.0.as_slice()
     }
+
+    /// Returns a mutable slice to the contents of this vector
+    #[inline]
+    pub fn as_mut_slice(&mut self) -> &mut [T] {
+        self.0.as_mut_slice()
+    }
+}
+
+impl<T, const N: usize> IntoIterator for InlineVec<T, N> {
+    type Item = T;
+    type IntoIter = InlineVecIntoIter<T, N>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        // the elements are moved into the iterator, so the destructor of the vector must not run
+        let inner = ManuallyDrop::new(self.0);
+
+        let inner = match &*inner {
+            InlineVecInner::Inline { len, data } => IntoIterInner::Inline {
+                idx: 0,
+                len: *len,
+                data: unsafe { ptr::read(data) },
+            },
+            InlineVecInner::Heap(vec) => IntoIterInner::Heap(unsafe { ptr::read(vec) }.into_iter()),
+        };
+
+        InlineVecIntoIter(inner)
+    }
+}
+
+impl<'a, T, const N: usize> IntoIterator for &'a InlineVec<T, N> {
+    type Item = &'a T;
+    type IntoIter = InlineVecIter<'a, T, N>;
+
+    #[inline]
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<'a, T, const N: usize> IntoIterator for &'a mut InlineVec<T, N> {
+    type Item = &'a mut T;
+    type IntoIter = std::slice::IterMut<'a, T>;
+
+    #[inline]
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter_mut()
+    }
 }
 
 enum InlineVecInner<T, const N: usize> {
@@ -135,6 +219,15 @@ impl<T, const N: usize> InlineVecInner<T, N> {
         }
     }
 
+    pub fn as_mut_slice(&mut self) -> &mut [T] {
+        match self {
+            Self::Heap(v) => v.as_mut_slice(),
+            Self::Inline { len, data } => unsafe {
+                std::slice::from_raw_parts_mut(data.as_mut_ptr() as *mut T, *len)
+            },
+        }
+    }
+
     pub fn as_slice(&self) -> &[T] {
         match self {
             Self::Heap(v) => v.as_slice(),
@@ -161,7 +254,7 @@ impl<T, const N: usize> InlineVecInner<T, N> {
             InlineVecInner::Inline { len, data } => {
                 let mut new_data = Vec::with_capacity(*len);
 
-                let iter = data.into_iter().take(*len);
+                let iter = data.iter().take(*len);
 
                 for element in iter {
                     new_data.push(unsafe { T::clone(&*element.as_ptr()) });
@@ -236,6 +329,69 @@ impl<T, const N: usize> InlineVecInner<T, N> {
         }
     }
 
+    pub fn retain<F: FnMut(&T) -> bool>(&mut self, mut f: F) {
+        match self {
+            Self::Inline { data, len } => {
+                let old_len = *len;
+                // if `f` panics, the remaining elements are leaked instead of being dropped twice
+                *len = 0;
+
+                let mut kept = 0;
+                for i in 0..old_len {
+                    let element = unsafe { &mut *data[i].as_mut_ptr() };
+
+                    if f(element) {
+                        data.swap(kept, i);
+                        kept += 1;
+                    } else {
+                        unsafe { ptr::drop_in_place(element) };
+                    }
+                }
+
+                *len = kept;
+            }
+            Self::Heap(vec) => vec.retain(f),
+        }
+    }
+
+    pub fn clear(&mut self) {
+        match self {
+            Self::Inline { data, len } => {
+                let old_len = std::mem::replace(len, 0);
+
+                for element in data.iter_mut().take(old_len) {
+                    unsafe { ptr::drop_in_place(element.as_mut_ptr()) };
+                }
+            }
+            Self::Heap(vec) => vec.clear(),
+        }
+    }
+
+    pub fn insert(&mut self, idx: usize, value: T) {
+        assert!(idx <= self.len());
+
+        match self {
+            Self::Inline { data, len } if *len < N => {
+                // shift the elements after idx to the right, the slot at `len` is uninitialized
+                for i in (idx..*len).rev() {
+                    data.swap(i, i + 1);
+                }
+
+                data[idx].write(value);
+                *len += 1;
+            }
+            Self::Inline { .. } => {
+                // no space left, so this moves the elements to the heap
+                self.push(value);
+
+                if let Self::Heap(vec) = self {
+                    vec[idx..].rotate_right(1);
+                }
+            }
+            Self::Heap(vec) => vec.insert(idx, value),
+        }
+    }
+
     pub fn push(&mut self, value: T) {
         let (array, len) = match self {
             Self::Inline { data, len } => (data, len),
@@ -296,6 +452,58 @@ impl<'a, T, const N: usize> Iterator for InlineVecIter<'a, T, N> {
     }
 }
 
+/// An iterator that moves the elements out of an [`InlineVec`]
+pub struct InlineVecIntoIter<T, const N: usize>(IntoIterInner<T, N>);
+
+enum IntoIterInner<T, const N: usize> {
+    /// The elements in `idx..len` are initialized and have not been yielded yet
+    Inline {
+        idx: usize,
+        len: usize,
+        data: [MaybeUninit<T>; N],
+    },
+    Heap(std::vec::IntoIter<T>),
+}
+
+impl<T, const N: usize> Iterator for InlineVecIntoIter<T, N> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match &mut self.0 {
+            IntoIterInner::Inline { idx, len, data } => {
+                if *idx < *len {
+                    *idx += 1;
+                    // the element is initialized and is never read again, because `idx` moved past it
+                    Some(unsafe { data.get_unchecked(*idx - 1).assume_init_read() })
+                } else {
+                    None
+                }
+            }
+            IntoIterInner::Heap(iter) => iter.next(),
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        match &self.0 {
+            IntoIterInner::Inline { idx, len, .. } => (len - idx, Some(len - idx)),
+            IntoIterInner::Heap(iter) => iter.size_hint(),
+        }
+    }
+}
+
+impl<T, const N: usize> ExactSizeIterator for InlineVecIntoIter<T, N> {}
+
+impl<T, const N: usize> Drop for IntoIterInner<T, N> {
+    fn drop(&mut self) {
+        // drop the elements that have not been yielded
+        if let Self::Inline { idx, len, data } = self {
+            for element in &mut data[*idx..*len] {
+                unsafe { ptr::drop_in_place(element.as_mut_ptr()) };
+            }
+        }
+    }
+}
+
 impl<T, const N: usize> Drop for InlineVecInner<T, N> {
     fn drop(&mut self) {
         if let Some((data, len)) = self.inline_parts_mut() {
@@ -310,6 +518,32 @@ impl<T, const N: usize> Drop for InlineVecInner<T, N> {
 mod tests {
     use super::*;
 
+    #[test]
+    fn inlinevec_insert() {
+        let mut x = InlineVec::<usize, 4>::new();
+
+        x.insert(0, 1);
+        x.insert(0, 0);
+        x.insert(2, 3);
+        x.insert(2, 2);
+        assert!(!x.is_heap_allocated());
+        assert_eq!(x.as_slice(), &[0, 1, 2, 3]);
+
+        x.insert(1, 42);
+        assert!(x.is_heap_allocated());
+        assert_eq!(x.as_slice(), &[0, 42, 1, 2, 3]);
+
+        x.insert(5, 5);
+        assert_eq!(x.as_slice(), &[0, 42, 1, 2, 3, 5]);
+    }
+
+    #[test]
+    #[should_panic]
+    fn inlinevec_insert_out_of_bounds() {
+        let mut x = InlineVec::<usize, 4>::new();
+        x.insert(1, 0);
+    }
+
     #[test]
     fn inlinevec_to_vec_stack() {
         let mut x = InlineVec::<usize, 4>::new();
@@ -395,6 +629,97 @@ mod tests {
         assert_eq!(iter.next(), None);
     }
 
+    #[test]
+    fn inlinevec_iter_mut() {
+        let mut x = InlineVec::<String, 4>::new();
+        x.push("a".into());
+        x.push("b".into());
+
+        for element in x.iter_mut() {
+            element.push('!');
+        }
+        assert!(!x.is_heap_allocated());
+        assert_eq!(x.as_slice(), &["a!", "b!"]);
+
+        for i in 0..4 {
+            x.push(i.to_string());
+        }
+        for element in &mut x {
+            element.push('?');
+        }
+        assert!(x.is_heap_allocated());
+        assert_eq!(x.as_slice(), &["a!?", "b!?", "0?", "1?", "2?", "3?"]);
+    }
+
+    #[test]
+    fn inlinevec_into_iter() {
+        let mut x = InlineVec::<String, 4>::new();
+        for i in 0..3 {
+            x.push(i.to_string());
+        }
+
+        let mut iter = x.clone().into_iter();
+        assert_eq!(iter.len(), 3);
+        assert_eq!(iter.next().as_deref(), Some("0"));
+        // the remaining elements are dropped with the iterator
+        drop(iter);
+
+        assert_eq!(x.clone().into_iter().collect::<Vec<_>>(), ["0", "1", "2"]);
+
+        x.push("3".into());
+        x.push("4".into());
+        assert!(x.is_heap_allocated());
+        assert_eq!(x.into_iter().collect::<Vec<_>>(), ["0", "1", "2", "3", "4"]);
+    }
+
+    #[test]
+    fn inlinevec_retain() {
+        let mut x = InlineVec::<String, 4>::new();
+        for i in 0..4 {
+            x.push(i.to_string());
+        }
+
+        x.retain(|s| s != "1");
+        assert!(!x.is_heap_allocated());
+        assert_eq!(x.as_slice(), &["0", "2", "3"]);
+
+        x.retain(|_| false);
+        assert!(x.is_empty());
+
+        for i in 0..8 {
+            x.push(i.to_string());
+        }
+        assert!(x.is_heap_allocated());
+
+        // fewer than N elements are left, but the vector stays on the heap
+        x.retain(|s| s == "2" || s == "7");
+        assert!(x.is_heap_allocated());
+        assert_eq!(x.as_slice(), &["2", "7"]);
+        x.push("8".into());
+        assert_eq!(x.as_slice(), &["2", "7", "8"]);
+    }
+
+    #[test]
+    fn inlinevec_clear() {
+        let mut x = InlineVec::<String, 4>::new();
+        x.push("a".into());
+        x.push("b".into());
+
+        x.clear();
+        assert!(x.is_empty());
+        assert!(!x.is_heap_allocated());
+        x.push("c".into());
+        assert_eq!(x.as_slice(), &["c"]);
+
+        for i in 0..4 {
+            x.push(i.to_string());
+        }
+        assert!(x.is_heap_allocated());
+        x.clear();
+        assert!(x.is_empty());
+        assert!(x.is_heap_allocated());
+    }
+
     #[test]
     fn inlinevec_remove() {
         let mut x = InlineVecInner::<usize, 4>::new();
@@ -427,7 +752,7 @@ mod tests {
         assert_eq!(x.as_slice(), &[0]);
 
         assert_eq!(x.remove(0), 0);
-        assert_eq!(x.as_slice(), &[]);
+        assert!(x.as_slice().is_empty());
         assert!(!x.is_heap_allocated());
 
         // trigger heap allocation
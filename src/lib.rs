@@ -3,6 +3,7 @@
 #![deny(missing_docs)]
 
 mod bytes;
+mod entities;
 /// Errors that occur throughout the crate
 pub mod errors;
 /// Inline data structures
@@ -10,11 +11,14 @@ pub mod inline;
 mod parser;
 /// Query selector API
 pub mod queryselector;
+#[cfg(feature = "serde")]
+mod serialize;
 mod stream;
 #[cfg(test)]
 mod tests;
 mod util;
 mod vdom;
+mod visitor;
 
 #[doc(hidden)]
 #[cfg(feature = "__INTERNALS_DO_NOT_USE")]
@@ -23,10 +27,14 @@ pub mod simd;
 mod simd;
 
 pub use bytes::Bytes;
-pub use errors::ParseError;
+pub use errors::{ParseError, SelectorParseError};
 pub use parser::*;
 use queryselector::Selector;
-pub use vdom::{VDom, VDomGuard};
+#[cfg(feature = "serde")]
+pub use serialize::{SerializableNode, SerializableTag};
+use std::sync::Arc;
+pub use vdom::{OwnedVDom, VDom, VDomGuard};
+pub use visitor::{Visitor, VisitorMut};
 
 /// Parses the given input string
 ///
@@ -47,11 +55,103 @@ pub use vdom::{VDom, VDomGuard};
 /// assert_eq!(dom.query_selector("div").unwrap().count(), 1);
 /// ```
 pub fn parse(input: &str, options: ParserOptions) -> Result<VDom<'_>, ParseError> {
+    parse_bytes(input.as_bytes(), options)
+}
+
+/// Parses the given input bytes
+///
+/// This is like [`parse`], but the input is not required to be valid UTF-8,
+/// which is useful for raw bodies of HTTP responses.
+/// Methods that return strings, such as [`Bytes::as_utf8_str`], replace invalid UTF-8 sequences with U+FFFD.
+///
+/// # Errors
+/// See [parse]
+///
+/// # Example
+/// ```
+/// # use tl::*;
+/// let dom = parse_bytes(b"<p>caf\xe9</p>", ParserOptions::default()).unwrap();
+/// let p = dom.query_selector_first("p").unwrap().get(dom.parser()).unwrap();
+/// assert_eq!(p.inner_text(dom.parser()), "caf\u{fffd}");
+/// ```
+pub fn parse_bytes(input: &[u8], options: ParserOptions) -> Result<VDom<'_>, ParseError> {
     let mut parser = Parser::new(input, options);
     parser.parse()?;
     Ok(VDom::from(parser))
 }
 
+/// Parses the given input string as the contents of an element with the given tag name
+///
+/// This is similar to setting `innerHTML` on an element in browsers: the context element is not part of the result,
+/// but it decides how the input is tokenized. The contents of elements like `<title>`, `<textarea>`, `<script>` and `<style>`
+/// are text rather than markup, so in such a context the whole input becomes a single text node.
+/// Any other context parses the input like [`parse`], since the parser does not apply context-dependent tree construction rules
+/// (e.g. a `<td>` is accepted anywhere).
+///
+/// # Errors
+/// See [parse]
+///
+/// # Example
+/// ```
+/// # use tl::*;
+/// let dom = parse_fragment("<b>not</b> bold", "title", ParserOptions::default()).unwrap();
+/// assert_eq!(dom.children().len(), 1);
+/// assert_eq!(dom.outer_html(), "<b>not</b> bold");
+/// assert!(dom.query_selector_first("b").is_none());
+///
+/// let dom = parse_fragment("<td>x</td>", "tr", ParserOptions::default()).unwrap();
+/// assert!(dom.query_selector_first("td").is_some());
+/// ```
+pub fn parse_fragment<'a>(
+    input: &'a str,
+    context_tag: &str,
+    options: ParserOptions,
+) -> Result<VDom<'a>, ParseError> {
+    let mut parser = Parser::new(input.as_bytes(), options);
+    parser.parse_fragment(context_tag.as_bytes())?;
+    Ok(VDom::from(parser))
+}
+
+/// Parses the given input string and reports every tag, text and comment to the handler as it is encountered
+///
+/// Unlike [`parse`], no DOM is built, so memory usage does not grow with the size of the input.
+/// This is useful for extracting a few values out of large documents.
+/// Parsing can be stopped early by returning [`ControlFlow::Break`](std::ops::ControlFlow::Break) from any of the callbacks.
+///
+/// The handler can be passed by value or by mutable reference.
+///
+/// # Errors
+/// See [parse]
+///
+/// # Example
+/// ```
+/// use std::ops::ControlFlow;
+/// use tl::{HTMLTag, TagHandler};
+///
+/// #[derive(Default)]
+/// struct Links(Vec<String>);
+///
+/// impl<'a> TagHandler<'a> for Links {
+///     fn on_open_tag(&mut self, tag: &HTMLTag<'a>) -> ControlFlow<()> {
+///         if let Some(Some(href)) = tag.attributes().get("href") {
+///             self.0.push(href.as_utf8_str().into_owned());
+///         }
+///         ControlFlow::Continue(())
+///     }
+/// }
+///
+/// let mut links = Links::default();
+/// tl::parse_streaming(r#"<a href="/a">a</a><p><a href="/b">b</a></p>"#, Default::default(), &mut links).unwrap();
+/// assert_eq!(links.0, ["/a", "/b"]);
+/// ```
+pub fn parse_streaming<'a, H: TagHandler<'a>>(
+    input: &'a str,
+    options: ParserOptions,
+    handler: H,
+) -> Result<(), ParseError> {
+    Parser::new(input.as_bytes(), options).parse_streaming(handler)
+}
+
 /// Parses a query selector
 ///
 /// # Example
@@ -68,8 +168,24 @@ pub fn parse(input: &str, options: ParserOptions) -> Result<VDom<'_>, ParseError
 /// }
 /// ```
 pub fn parse_query_selector(input: &str) -> Option<Selector<'_>> {
-    let selector = queryselector::Parser::new(input.as_bytes()).selector()?;
-    Some(selector)
+    parse_query_selector_checked(input).ok()
+}
+
+/// Parses a query selector, returning an error that describes why it is invalid
+///
+/// This is the same as [`parse_query_selector`], except that the error carries
+/// the byte offset at which parsing failed and the reason.
+///
+/// # Example
+/// ```
+/// use tl::errors::{SelectorParseError, SelectorParseErrorKind};
+///
+/// let error = tl::parse_query_selector_checked("a[href").unwrap_err();
+/// assert_eq!(error, SelectorParseError { offset: 6, kind: SelectorParseErrorKind::Expected(']') });
+/// assert_eq!(error.to_string(), "Expected `]` at byte 6");
+/// ```
+pub fn parse_query_selector_checked(input: &str) -> Result<Selector<'_>, SelectorParseError> {
+    queryselector::Parser::new(input.as_bytes()).selector_checked()
 }
 
 /// Parses the given input string and returns an owned, RAII guarded DOM
@@ -85,3 +201,29 @@ pub fn parse_query_selector(input: &str) -> Option<Selector<'_>> {
 pub unsafe fn parse_owned(input: String, options: ParserOptions) -> Result<VDomGuard, ParseError> {
     VDomGuard::parse(input, options)
 }
+
+/// Parses the given input string and returns an owned DOM that can be cloned and shared between threads
+///
+/// The input is stored in an [`Arc`], so cloning the returned [`OwnedVDom`] does not copy it.
+/// The parsed nodes are cloned, so each clone can be modified independently.
+///
+/// # Errors
+/// See [parse]
+///
+/// # Example
+/// ```
+/// let dom = tl::parse_shared("<p>Hello</p>", Default::default()).unwrap();
+/// let cached = dom.clone();
+///
+/// std::thread::spawn(move || {
+///     assert_eq!(cached.get_ref().outer_html(), "<p>Hello</p>");
+/// })
+/// .join()
+/// .unwrap();
+/// ```
+pub fn parse_shared(
+    input: impl Into<Arc<str>>,
+    options: ParserOptions,
+) -> Result<OwnedVDom, ParseError> {
+    OwnedVDom::parse(input.into(), options)
+}
@@ -4,9 +4,10 @@ use super::{
     tag::{Attributes, HTMLTag, Node},
 };
 use crate::InnerNodeHandle;
-use crate::{bytes::Bytes, inline::vec::InlineVec, simd, ParseError};
+use crate::{bytes::Bytes, inline::vec::InlineVec, simd, util, ParseError};
 use crate::{stream::Stream, ParserOptions};
 use std::collections::HashMap;
+use std::num::NonZeroU32;
 
 /// A list of HTML nodes
 pub type Tree<'a> = Vec<Node<'a>>;
@@ -27,11 +28,82 @@ pub enum HTMLVersion {
     /// Frameset HTML 4.01:
     FramesetHTML401,
 }
+
+impl HTMLVersion {
+    /// Determines the HTML version from the contents of a `<!DOCTYPE ...>` declaration, following the `DOCTYPE` keyword
+    ///
+    /// HTML 4.01 doctypes are recognized by their public identifier, or by their DTD URL if the public identifier is unknown.
+    fn from_doctype(doctype: &[u8]) -> Option<Self> {
+        let mut parts = doctype.trim_ascii().splitn(2, u8::is_ascii_whitespace);
+
+        if !parts.next()?.eq_ignore_ascii_case(b"html") {
+            return None;
+        }
+
+        let external_id = parts.next().unwrap_or_default().trim_ascii();
+        let Some(identifiers) = strip_prefix_ignore_case(external_id, b"public") else {
+            // `<!DOCTYPE html>`, or the `SYSTEM "about:legacy-compat"` variant of it
+            return Some(HTMLVersion::HTML5);
+        };
+
+        let mut identifiers = quoted_strings(identifiers);
+        let public = identifiers.next().unwrap_or_default();
+        let system = identifiers.next().unwrap_or_default();
+
+        const VERSIONS: [(&[u8], &[u8], HTMLVersion); 3] = [
+            (
+                b"-//W3C//DTD HTML 4.01//EN",
+                b"http://www.w3.org/TR/html4/strict.dtd",
+                HTMLVersion::StrictHTML401,
+            ),
+            (
+                b"-//W3C//DTD HTML 4.01 Transitional//EN",
+                b"http://www.w3.org/TR/html4/loose.dtd",
+                HTMLVersion::TransitionalHTML401,
+            ),
+            (
+                b"-//W3C//DTD HTML 4.01 Frameset//EN",
+                b"http://www.w3.org/TR/html4/frameset.dtd",
+                HTMLVersion::FramesetHTML401,
+            ),
+        ];
+
+        VERSIONS
+            .iter()
+            .find(|(id, _, _)| public.eq_ignore_ascii_case(id))
+            .or_else(|| {
+                VERSIONS
+                    .iter()
+                    .find(|(_, url, _)| system.eq_ignore_ascii_case(url))
+            })
+            .map(|&(_, _, version)| version)
+    }
+}
+
+/// Strips the given prefix from `bytes`, ignoring ASCII case
+fn strip_prefix_ignore_case<'b>(bytes: &'b [u8], prefix: &[u8]) -> Option<&'b [u8]> {
+    let (head, tail) = bytes.split_at_checked(prefix.len())?;
+    head.eq_ignore_ascii_case(prefix).then_some(tail)
+}
+
+/// Returns an iterator over the strings enclosed in double or single quotes
+fn quoted_strings(mut bytes: &[u8]) -> impl Iterator<Item = &[u8]> {
+    std::iter::from_fn(move || {
+        let start = bytes.iter().position(|&c| c == b'"' || c == b'\'')?;
+        let quote = bytes[start];
+        let rest = &bytes[start + 1..];
+        let end = rest.iter().position(|&c| c == quote).unwrap_or(rest.len());
+
+        bytes = rest.get(end + 1..).unwrap_or_default();
+        Some(&rest[..end])
+    })
+}
+
 /// The main HTML parser
 ///
 /// Users of this library are not supposed to directly construct this struct.
 /// Instead, users must call `tl::parse()` and use the returned `VDom`.
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct Parser<'a> {
     /// The inner stream that is used to iterate through the HTML source
     pub(crate) stream: Stream<'a, u8>,
@@ -48,21 +120,51 @@ pub struct Parser<'a> {
     pub(crate) ids: HashMap<Bytes<'a>, NodeHandle>,
     /// A HashMap that maps Tag Class to a Node ID
     pub(crate) classes: HashMap<Bytes<'a>, ClassVec>,
+    /// A HashMap that maps lowercased Tag names to Node IDs
+    pub(crate) tag_names: HashMap<Bytes<'a>, Vec<NodeHandle>>,
+    /// Interned tag names, if tag name interning is enabled. The id of a name is its index plus one
+    pub(crate) interned_tag_names: Vec<Bytes<'a>>,
     /// The current HTML version, if set
     pub(crate) version: Option<HTMLVersion>,
+    /// The number of open tags on the stack in which whitespace is significant
+    pub(crate) preserving_whitespace: usize,
+    /// The number of start tags that were kept as text because of the depth limit and have not been closed yet
+    pub(crate) depth_overflow: usize,
+    /// Whether the depth limit was exceeded at any point
+    pub(crate) depth_limit_exceeded: bool,
+}
+
+/// Checks whether the end tag of an open `open` tag is implied by a following `next` start tag
+fn is_end_tag_implied(open: &[u8], next: &[u8]) -> bool {
+    match open {
+        b"li" => next == b"li",
+        b"dt" | b"dd" => matches!(next, b"dt" | b"dd"),
+        b"p" => constants::CLOSES_P_TAGS.contains(&next) || matches!(next, b"li" | b"dt" | b"dd"),
+        b"option" => matches!(next, b"option" | b"optgroup"),
+        b"optgroup" => next == b"optgroup",
+        b"td" | b"th" => matches!(next, b"td" | b"th" | b"tr" | b"thead" | b"tbody" | b"tfoot"),
+        b"tr" => matches!(next, b"tr" | b"thead" | b"tbody" | b"tfoot"),
+        b"thead" | b"tbody" | b"tfoot" => matches!(next, b"thead" | b"tbody" | b"tfoot"),
+        _ => false,
+    }
 }
 
 impl<'a> Parser<'a> {
-    pub(crate) fn new(input: &str, options: ParserOptions) -> Parser {
+    pub(crate) fn new(input: &[u8], options: ParserOptions) -> Parser<'_> {
         Parser {
             stack: Vec::with_capacity(4),
             options,
             tags: Vec::new(),
-            stream: Stream::new(input.as_bytes()),
+            stream: Stream::new(input),
             ast: Vec::new(),
             ids: HashMap::new(),
             classes: HashMap::new(),
+            tag_names: HashMap::new(),
+            interned_tag_names: Vec::new(),
             version: None,
+            preserving_whitespace: 0,
+            depth_overflow: 0,
+            depth_limit_exceeded: false,
         }
     }
 
@@ -72,12 +174,42 @@ impl<'a> Parser<'a> {
         NodeHandle::new((self.tags.len() - 1) as u32)
     }
 
+    /// Returns the id of the given tag name, assigning the next free one if the name has not been seen yet
+    ///
+    /// Documents only use a handful of distinct tag names, so a linear scan is faster than hashing every name.
+    /// To bound the cost for adversarial input, names are no longer interned once the table is full.
+    /// Tags without an id are compared by name.
+    fn intern_tag_name(&mut self, name: Bytes<'a>) -> Option<NonZeroU32> {
+        let index = match self.interned_tag_names.iter().position(|n| *n == name) {
+            Some(index) => index,
+            None if self.interned_tag_names.len() < constants::MAX_INTERNED_TAG_NAMES => {
+                self.interned_tag_names.push(name);
+                self.interned_tag_names.len() - 1
+            }
+            None => return None,
+        };
+
+        NonZeroU32::new(index as u32 + 1)
+    }
+
+    /// Returns the ids of the interned tag names for which the predicate returns true
+    pub(crate) fn interned_tag_name_ids<'s>(
+        &'s self,
+        mut predicate: impl FnMut(&[u8]) -> bool + 's,
+    ) -> impl Iterator<Item = NonZeroU32> + 's {
+        self.interned_tag_names
+            .iter()
+            .zip(1..)
+            .filter(move |(name, _)| predicate(name.as_bytes()))
+            .filter_map(|(_, id)| NonZeroU32::new(id))
+    }
+
     #[inline(always)]
-    fn skip_whitespaces(&mut self) {
+    pub(super) fn skip_whitespaces(&mut self) {
         self.read_while2(b' ', b'\n');
     }
 
-    fn read_to(&mut self, needle: u8) -> &'a [u8] {
+    pub(super) fn read_to(&mut self, needle: u8) -> &'a [u8] {
         let start = self.stream.idx;
         let bytes = &self.stream.data()[start..];
 
@@ -87,11 +219,20 @@ impl<'a> Parser<'a> {
         self.stream.slice(start, start + end)
     }
 
-    fn read_to4(&mut self, needle: [u8; 4]) -> &'a [u8] {
+    /// Reads an unquoted attribute value, which ends at whitespace or at the end of the tag
+    ///
+    /// Slashes are part of the value (`href=/about`), unless the value is directly followed by `>`,
+    /// in which case the slash makes the tag self-closing: `<img src=a.png/>`.
+    fn read_unquoted_attribute_value(&mut self) -> &'a [u8] {
         let start = self.stream.idx;
         let bytes = &self.stream.data()[start..];
 
-        let end = simd::find4(bytes, needle).unwrap_or_else(|| self.stream.len() - start);
+        let mut end =
+            simd::find_any(bytes, constants::UNQUOTED_ATTRIBUTE_VALUE_END).unwrap_or(bytes.len());
+
+        if bytes.get(end) == Some(&b'>') && end > 0 && bytes[end - 1] == b'/' {
+            end -= 1;
+        }
 
         self.stream.idx += end;
         self.stream.slice(start, start + end)
@@ -112,20 +253,23 @@ impl<'a> Parser<'a> {
         }
     }
 
-    fn read_ident(&mut self) -> Option<&'a [u8]> {
+    pub(super) fn read_ident(&mut self) -> Option<&'a [u8]> {
         let start = self.stream.idx;
         let bytes = &self.stream.data()[start..];
 
         // If we do not find any characters that are not identifiers
         // then we are probably at the end of the stream
-        let end = simd::search_non_ident(bytes)
-            .unwrap_or_else(|| self.stream.len() - start);
+        let end = simd::search_non_ident(bytes).unwrap_or_else(|| self.stream.len() - start);
+
+        // `/` is an identifier character, but in tag and attribute names it starts a self-closing `/>`,
+        // as in `<div/>` or `<input disabled/>`
+        let end = bytes[..end].iter().position(|&c| c == b'/').unwrap_or(end);
 
         self.stream.idx += end;
         Some(self.stream.slice(start, start + end))
     }
 
-    fn skip_comment_with_start(&mut self, start: usize) -> &'a [u8] {
+    pub(super) fn skip_comment_with_start(&mut self, start: usize) -> &'a [u8] {
         while !self.stream.is_eof() {
             let idx = self.stream.idx;
 
@@ -141,6 +285,10 @@ impl<'a> Parser<'a> {
                 if is_end_of_comment {
                     return self.stream.slice(start, self.stream.idx);
                 }
+
+                // The second dash may be the start of the closing `-->`, as in `<!-- a --->`
+                self.stream.idx = idx + 1;
+                continue;
             }
 
             self.stream.advance();
@@ -149,8 +297,46 @@ impl<'a> Parser<'a> {
         &[]
     }
 
+    /// Reads a CDATA section, starting right after the `<![CDATA[`, and returns its contents
+    ///
+    /// An unterminated CDATA section extends to the end of the input.
+    pub(super) fn read_cdata(&mut self) -> &'a [u8] {
+        let start = self.stream.idx;
+        let rest = self.stream.slice(start, self.stream.len());
+
+        // Search for the full terminator at every position, so that `]]]>` ends the section after the first `]`
+        match rest
+            .windows(constants::CDATA_END.len())
+            .position(|window| window == constants::CDATA_END)
+        {
+            Some(end) => {
+                self.stream.idx = start + end + constants::CDATA_END.len();
+                self.stream.slice(start, start + end)
+            }
+            None => {
+                self.stream.idx = self.stream.len();
+                rest
+            }
+        }
+    }
+
+    /// Reads an attribute name, which extends to the next whitespace, `=`, `/` or `>`
+    fn read_attribute_name(&mut self) -> &'a [u8] {
+        let start = self.stream.idx;
+        let bytes = &self.stream.data()[start..];
+
+        let end = simd::find_any(bytes, constants::ATTRIBUTE_NAME_END)
+            .unwrap_or_else(|| self.stream.len() - start);
+
+        self.stream.idx += end;
+        self.stream.slice(start, start + end)
+    }
+
     fn parse_attribute(&mut self) -> Option<(&'a [u8], Option<&'a [u8]>)> {
-        let name = self.read_ident()?;
+        let name = self.read_attribute_name();
+        if name.is_empty() {
+            return None;
+        }
         self.skip_whitespaces();
 
         let has_value = self.stream.expect_and_skip_cond(b'=');
@@ -160,16 +346,18 @@ impl<'a> Parser<'a> {
 
         self.skip_whitespaces();
 
-        let value = if let Some(quote) = self.stream.expect_oneof_and_skip(&[b'"', b'\'']) {
-            self.read_to(quote)
+        let value = if let Some(quote) = self.stream.expect_oneof_and_skip(b"\"'") {
+            let value = self.read_to(quote);
+            self.stream.expect_and_skip_cond(quote);
+            value
         } else {
-            self.read_to4([b' ', b'\n', b'/', b'>'])
+            self.read_unquoted_attribute_value()
         };
 
         Some((name, Some(value)))
     }
 
-    fn parse_attributes(&mut self) -> Option<Attributes<'a>> {
+    pub(super) fn parse_attributes(&mut self) -> Option<Attributes<'a>> {
         let mut attributes = Attributes::new();
 
         loop {
@@ -177,21 +365,36 @@ impl<'a> Parser<'a> {
 
             let cur = self.stream.current_cpy()?;
 
-            if simd::is_closing(cur) {
+            // like browsers, stray slashes that are not followed by `>` are skipped: `<a / href="x">`.
+            // In strict mode, they end the tag so that it is reported as malformed
+            let next = self.stream.data().get(self.stream.idx + 1).copied();
+            let ends_tag =
+                cur == b'>' || self.options.is_strict() || next.is_none_or(|c| c == b'>');
+            if simd::is_closing(cur) && ends_tag {
                 break;
             }
 
+            let start = self.stream.idx;
+
             if let Some((key, value)) = self.parse_attribute() {
                 let value: Option<Bytes<'a>> = value.map(Into::into);
-
-                match key {
-                    b"id" => attributes.id = value,
-                    b"class" => attributes.class = value,
-                    _ => attributes.raw.insert(key.into(), value),
+                let key = util::to_lower_bytes(key);
+
+                // like browsers, only the first occurrence of an attribute is kept
+                match key.as_bytes() {
+                    b"id" if attributes.id.is_none() => attributes.set_id(value),
+                    b"class" if attributes.class.is_none() => attributes.set_class(value),
+                    b"id" | b"class" => {}
+                    _ => {
+                        if !attributes.raw.contains_key(&key) {
+                            attributes.raw.insert(key, value);
+                        }
+                    }
                 };
             }
 
-            if !simd::is_closing(self.stream.current_cpy()?) {
+            // skip characters that cannot start an attribute
+            if self.stream.idx == start {
                 self.stream.advance();
             }
         }
@@ -215,60 +418,121 @@ impl<'a> Parser<'a> {
         }
     }
 
-    fn read_end(&mut self) {
+    /// Parses an end tag, starting at the `/` of the end tag whose `<` is at `start`
+    ///
+    /// An end tag closes the innermost open tag with the same name, along with all tags that were opened after it.
+    /// End tags that do not match any open tag are ignored. In strict mode, an end tag that does not match
+    /// the last open tag is an error.
+    fn read_end(&mut self, start: usize) -> Result<(), ParseError> {
         self.stream.advance();
 
         let closing_tag_name = self.read_to(b'>');
-        
-        self.stream.expect_and_skip_cond(b'>');
 
-        let closing_tag_matches_parent = self.stack.last()
-            .and_then(|last_handle| last_handle.get(self))
-            .and_then(|last_item| last_item.as_tag())
-            .map_or(false, |last_tag| last_tag.name() == closing_tag_name);
+        if !self.stream.expect_and_skip_cond(b'>') && self.options.is_strict() {
+            return Err(ParseError::UnexpectedEof { offset: start });
+        }
 
-        if !closing_tag_matches_parent {
-            return;
+        let position = self.stack.iter().rposition(|handle| {
+            handle
+                .get(self)
+                .and_then(Node::as_tag)
+                .is_some_and(|tag| tag.name() == closing_tag_name)
+        });
+
+        let Some(position) = position else {
+            if self.options.is_strict() {
+                return Err(ParseError::MismatchedEndTag { offset: start });
+            }
+
+            return Ok(());
+        };
+
+        if position != self.stack.len() - 1 && self.options.is_strict() {
+            return Err(ParseError::MismatchedEndTag { offset: start });
         }
 
-        if let Some(handle) = self.stack.pop() {
-            let tag = self
-                .tags
-                .get_mut(handle.get_inner() as usize)
-                .unwrap()
-                .as_tag_mut()
-                .unwrap();
+        // tags that were opened after the matching tag are implicitly closed right before this end tag
+        while self.stack.len() > position + 1 {
+            let handle = self.pop_open_tag().unwrap();
+            self.close_tag(handle, start);
+        }
+
+        let handle = self.pop_open_tag().unwrap();
+        self.close_tag(handle, self.stream.idx);
+
+        Ok(())
+    }
+
+    /// Closes open tags whose end tag can be omitted when a `name` start tag at `start` follows them,
+    /// e.g. an open `<li>` when another `<li>` starts
+    fn close_implied_tags(&mut self, name: &[u8], start: usize) {
+        while let Some(&handle) = self.stack.last() {
+            let is_implied = handle
+                .get(self)
+                .and_then(Node::as_tag)
+                .is_some_and(|open| is_end_tag_implied(open.name().as_bytes(), name));
+
+            if !is_implied {
+                break;
+            }
 
-            let ptr = self.stream.data().as_ptr() as usize;
-            let offset = tag._raw.as_ptr() as usize;
-            let offset = offset - ptr;
-
-            tag._raw = self.stream.slice(offset, self.stream.idx).into();
-
-            let (track_classes, track_ids) = (
-                self.options.is_tracking_classes(),
-                self.options.is_tracking_ids(),
-            );
-
-            if let (true, Some(bytes)) = (track_classes, &tag._attributes.class) {
-                let s = bytes
-                    .as_bytes_borrowed()
-                    .and_then(|x| std::str::from_utf8(x).ok())
-                    .map(|x| x.split_ascii_whitespace());
-
-                if let Some(s) = s {
-                    for class in s {
-                        self.classes
-                            .entry(class.into())
-                            .or_insert_with(InlineVec::new)
-                            .push(handle);
+            self.pop_open_tag();
+            self.close_tag(handle, start);
+        }
+    }
+
+    /// Finishes a tag that was popped off the stack, whose source ends at `end`
+    fn close_tag(&mut self, handle: NodeHandle, end: usize) {
+        let tag = self
+            .tags
+            .get_mut(handle.get_inner() as usize)
+            .unwrap()
+            .as_tag_mut()
+            .unwrap();
+
+        let ptr = self.stream.data().as_ptr() as usize;
+        let offset = tag._raw.as_ptr() as usize;
+        let offset = offset - ptr;
+
+        tag._raw = self.stream.slice(offset, end).into();
+    }
+
+    /// Registers the ID and classes of a newly opened tag in the lookup tables, if tracking is enabled
+    ///
+    /// Tags are registered as soon as they are opened, so the tables are in document order and include
+    /// void, self-closing and unclosed tags. Like a linear scan, the first tag with a given ID wins.
+    fn track_attributes(&mut self, handle: NodeHandle) {
+        let Some(tag) = self
+            .tags
+            .get(handle.get_inner() as usize)
+            .and_then(Node::as_tag)
+        else {
+            return;
+        };
+
+        if let (true, Some(bytes)) = (self.options.is_tracking_classes(), &tag._attributes.class) {
+            let s = bytes
+                .as_bytes_borrowed()
+                .and_then(|x| std::str::from_utf8(x).ok())
+                .map(|x| x.split_ascii_whitespace());
+
+            if let Some(s) = s {
+                for class in s {
+                    let handles = self
+                        .classes
+                        .entry(class.into())
+                        .or_insert_with(InlineVec::new);
+
+                    // a class that is listed twice (`class="a a"`) only registers the tag once
+                    if handles.as_slice().last() != Some(&handle) {
+                        handles.push(handle);
                     }
                 }
             }
+        }
 
-            if let (true, Some(bytes)) = (track_ids, &tag._attributes.id) {
-                self.ids.insert(bytes.clone(), handle);
-            }
+        if let (true, Some(bytes)) = (self.options.is_tracking_ids(), &tag._attributes.id) {
+            self.ids.entry(bytes.clone()).or_insert(handle);
         }
     }
 
@@ -284,88 +548,206 @@ impl<'a> Parser<'a> {
             .slice_len(self.stream.idx, 2)
             .eq(constants::COMMENT);
 
+        let is_cdata = self
+            .stream
+            .slice_len(self.stream.idx, constants::CDATA_START.len())
+            .eq(constants::CDATA_START);
+
         if is_comment {
             let comment = self.skip_comment_with_start(start);
-            let comment = self.register_tag(Node::Comment(comment.into()));
-            self.add_to_parent(comment);
+
+            if self.options.is_preserving_comments() {
+                let comment = self.register_tag(Node::Comment(comment.into()));
+                self.add_to_parent(comment);
+            }
+        } else if is_cdata {
+            self.stream.advance_by(constants::CDATA_START.len());
+            let cdata = self.read_cdata();
+            let cdata = self.register_tag(Node::CData(cdata.into()));
+            self.add_to_parent(cdata);
         } else {
             let tag = self.read_ident()?;
 
             self.skip_whitespaces();
 
             if simd::matches_case_insensitive(tag, *b"doctype") {
-                let doctype = self.read_ident()?;
-
-                let html5 = simd::matches_case_insensitive(doctype, *b"html");
+                let doctype = self.read_to(b'>');
+                self.stream.advance(); // skip >
 
-                if html5 {
-                    self.version = Some(HTMLVersion::HTML5);
+                if let Some(version) = HTMLVersion::from_doctype(doctype) {
+                    self.version = Some(version);
                 }
-
-                self.skip_whitespaces();
-                self.stream.advance(); // skip >
             }
         }
 
         Some(())
     }
 
-    fn parse_tag(&mut self) -> Option<()> {
+    /// Returns the error for a tag starting at `start` that could not be parsed at the current position
+    fn tag_error(&self, start: usize) -> ParseError {
+        if self.stream.is_eof() {
+            ParseError::UnexpectedEof { offset: start }
+        } else {
+            ParseError::MalformedTag {
+                offset: self.stream.idx,
+            }
+        }
+    }
+
+    /// Parses a tag, comment or declaration starting at the current `<`
+    ///
+    /// Errors are only reported back to the caller and do not abort parsing.
+    /// The source of a tag that failed to parse is skipped.
+    fn parse_tag(&mut self) -> Result<(), ParseError> {
         let start = self.stream.idx;
 
         self.stream.advance();
         self.skip_whitespaces();
-        let cur = self.stream.current_cpy()?;
+        let cur = self
+            .stream
+            .current_cpy()
+            .ok_or(ParseError::UnexpectedEof { offset: start })?;
 
         match cur {
-            b'/' => self.read_end(),
+            b'/' if self.depth_overflow > 0 => {
+                // the end tag of a start tag that was kept as text
+                self.read_to(b'>');
+                self.stream.expect_and_skip_cond(b'>');
+                self.depth_overflow -= 1;
+                self.add_text(start);
+            }
+            b'/' => self.read_end(start)?,
             b'!' => {
                 self.read_markdown();
             }
             _ => {
-                let name = self.read_ident()?;
+                let name = self
+                    .read_ident()
+                    .ok_or(ParseError::UnexpectedEof { offset: start })?;
                 self.skip_whitespaces();
 
-                let attr = self.parse_attributes()?;
+                let attr = self
+                    .parse_attributes()
+                    .ok_or(ParseError::UnexpectedEof { offset: start })?;
 
                 let is_self_closing = self.stream.expect_and_skip_cond(b'/');
 
-                self.stream.expect_and_skip(b'>')?;
+                if self.stream.expect_and_skip(b'>').is_none() {
+                    return Err(self.tag_error(start));
+                }
 
-                let this = self.register_tag(Node::Tag(HTMLTag::new(
+                let is_void = is_self_closing || self.options.is_void_tag(name);
+
+                // inside of tags that were kept as text, the open tags on the stack are not the parent
+                if self.depth_overflow == 0 {
+                    self.close_implied_tags(name, start);
+                }
+
+                if self
+                    .options
+                    .get_max_depth()
+                    .is_some_and(|max| self.stack.len() >= max as usize)
+                {
+                    self.depth_limit_exceeded = true;
+                    if !is_void {
+                        self.depth_overflow += 1;
+                    }
+                    self.add_text(start);
+                    return Ok(());
+                }
+
+                let mut tag = HTMLTag::new(
                     name.into(),
                     attr,
                     InlineVec::new(),
                     self.stream.slice(start, self.stream.idx).into(),
-                )));
+                );
+
+                if self.options.is_interning_tag_names() {
+                    tag._name_id = self.intern_tag_name(name.into());
+                }
+
+                let this = self.register_tag(Node::Tag(tag));
 
                 self.add_to_parent(this);
+                self.track_attributes(this);
+
+                if self.options.is_tracking_tags() {
+                    self.tag_names
+                        .entry(util::to_lower_bytes(name))
+                        .or_default()
+                        .push(this);
+                }
 
                 // some tags are self closing, so even though there might not be a /,
                 // we don't always want to push them to the stack
                 // e.g. <br><p>Hello</p>
                 // <p> should not be a subtag of <br>
-                if !is_self_closing && !constants::VOID_TAGS.contains(&name) {
-                    self.stack.push(this);
+                if !is_void {
+                    self.push_open_tag(this);
                 }
             }
         };
 
-        Some(())
+        Ok(())
     }
 
-    pub(crate) fn parse_single(&mut self) -> Option<()> {
-        loop {
-            let cur = self.stream.current()?;
+    /// Adds the source from `start` to the current position as a text node
+    fn add_text(&mut self, start: usize) {
+        let text = self.stream.slice(start, self.stream.idx);
+        let handle = self.register_tag(Node::Raw(text.into()));
+        self.add_to_parent(handle);
+    }
 
-            if *cur == b'<' {
-                self.parse_tag();
+    /// Pushes an open tag onto the stack
+    fn push_open_tag(&mut self, handle: NodeHandle) {
+        if self.preserves_whitespace(handle) {
+            self.preserving_whitespace += 1;
+        }
+        self.stack.push(handle);
+    }
+
+    /// Pops the innermost open tag off the stack
+    fn pop_open_tag(&mut self) -> Option<NodeHandle> {
+        let handle = self.stack.pop()?;
+        if self.preserves_whitespace(handle) {
+            self.preserving_whitespace -= 1;
+        }
+        Some(handle)
+    }
+
+    fn preserves_whitespace(&self, handle: NodeHandle) -> bool {
+        handle
+            .get(self)
+            .and_then(Node::as_tag)
+            .is_some_and(HTMLTag::preserves_whitespace)
+    }
+
+    pub(crate) fn parse_single(&mut self) -> Result<(), ParseError> {
+        while let Some(cur) = self.stream.current_cpy() {
+            if cur == b'<' {
+                // unless in strict mode, the parser is lenient and keeps going after malformed tags
+                if let Err(err) = self.parse_tag() {
+                    if self.options.is_strict() {
+                        return Err(err);
+                    }
+                }
             } else {
-                let raw = Node::Raw(self.read_to(b'<').into());
-                let handle = self.register_tag(raw);
+                let raw = self.read_to(b'<');
+
+                if self.options.is_skipping_whitespace_text_nodes()
+                    && raw.iter().all(u8::is_ascii_whitespace)
+                    && self.preserving_whitespace == 0
+                {
+                    continue;
+                }
+
+                let handle = self.register_tag(Node::Raw(raw.into()));
                 self.add_to_parent(handle);
             }
         }
+
+        Ok(())
     }
 
     /// Resolves an internal Node ID obtained from a NodeHandle to a Node
@@ -380,13 +762,89 @@ impl<'a> Parser<'a> {
         self.tags.get_mut(id as usize)
     }
 
+    /// Adds a node to the nodes table and returns a handle to it
+    ///
+    /// The node is not attached to any parent.
+    /// To insert it into the tree, append the returned handle to the children of a tag using [`ChildrenMut::append`](crate::ChildrenMut::append).
+    ///
+    /// Nodes that are added after parsing are not registered in the ID, class or tag name lookup tables.
+    ///
+    /// # Example
+    /// ```
+    /// let mut dom = tl::parse("<div></div>", Default::default()).unwrap();
+    /// let parser = dom.parser_mut();
+    /// let text = parser.push_node(tl::Node::Raw("hello".into()));
+    ///
+    /// let div = tl::NodeHandle::new(0).get_mut(parser).unwrap().as_tag_mut().unwrap();
+    /// div.children_mut().append(text);
+    ///
+    /// assert_eq!(dom.outer_html(), "<div>hello</div>");
+    /// ```
+    pub fn push_node(&mut self, mut node: Node<'a>) -> NodeHandle {
+        // the node may come from another parser, so its tag name id is not necessarily valid for this one
+        if let Node::Tag(tag) = &mut node {
+            tag._name_id = None;
+            if self.options.is_interning_tag_names() {
+                tag._name_id = self.intern_tag_name(tag._name.clone());
+            }
+        }
+
+        self.register_tag(node)
+    }
+
+    /// Parses another input, appending its top level nodes to the existing ones
+    ///
+    /// Tags that were left open by the previous input are not continued.
+    pub(crate) fn parse_next(&mut self, input: &'a [u8]) -> Result<(), ParseError> {
+        self.stream = Stream::new(input);
+        self.stack.clear();
+        self.preserving_whitespace = 0;
+        self.depth_overflow = 0;
+        self.parse()
+    }
+
+    /// Parses the input as the contents of an element with the given name
+    ///
+    /// If the element contains text rather than markup, e.g. `<title>` or `<script>`, the whole input is a single text node.
+    pub(crate) fn parse_fragment(&mut self, context: &[u8]) -> Result<(), ParseError> {
+        let is_raw_text = util::is_tag_in(context, constants::RAW_TEXT_TAGS)
+            || util::is_tag_in(context, constants::RCDATA_TAGS);
+
+        if !is_raw_text {
+            return self.parse();
+        }
+
+        if self.stream.len() > u32::MAX as usize {
+            return Err(ParseError::InvalidLength);
+        }
+
+        if !self.stream.is_eof() {
+            self.stream.idx = self.stream.len();
+            self.add_text(0);
+        }
+
+        Ok(())
+    }
+
     pub(crate) fn parse(&mut self) -> Result<(), ParseError> {
         if self.stream.len() > u32::MAX as usize {
             return Err(ParseError::InvalidLength);
         }
 
         while !self.stream.is_eof() {
-            self.parse_single();
+            self.parse_single()?;
+        }
+
+        if self.options.is_strict() {
+            if let Some(tag) = self
+                .stack
+                .last()
+                .and_then(|handle| handle.get(self))
+                .and_then(Node::as_tag)
+            {
+                let offset = tag._raw.as_ptr() as usize - self.stream.data().as_ptr() as usize;
+                return Err(ParseError::UnclosedTag { offset });
+            }
         }
 
         Ok(())
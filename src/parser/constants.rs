@@ -1,5 +1,126 @@
 pub const COMMENT: &[u8; 2] = b"--";
-pub const VOID_TAGS: &[&[u8]; 15] = &[
-    b"area", b"base", b"br", b"col", b"embed", b"hr", b"img", b"input", b"keygen", b"link",
-    b"meta", b"param", b"source", b"track", b"wbr",
+pub const CDATA_START: &[u8; 7] = b"[CDATA[";
+pub const CDATA_END: &[u8; 3] = b"]]>";
+/// Bytes that end an attribute name
+pub const ATTRIBUTE_NAME_END: [u8; 7] = [b' ', b'\n', b'\t', b'\r', b'=', b'/', b'>'];
+/// Bytes that end an unquoted attribute value
+pub const UNQUOTED_ATTRIBUTE_VALUE_END: [u8; 6] = [b' ', b'\n', b'\t', b'\r', b'\x0c', b'>'];
+/// Maximum number of distinct tag names that are interned with `ParserOptions::intern_tag_names`
+pub const MAX_INTERNED_TAG_NAMES: usize = 256;
+/// Void elements, which have neither content nor an end tag
+pub const VOID_TAGS: &[&[u8]; 16] = &[
+    b"area", b"base", b"br", b"col", b"command", b"embed", b"hr", b"img", b"input", b"keygen",
+    b"link", b"meta", b"param", b"source", b"track", b"wbr",
+];
+
+/// Raw text elements, whose contents are text rather than markup, and in which character references are not decoded
+pub const RAW_TEXT_TAGS: &[&[u8]; 7] = &[
+    b"iframe",
+    b"noembed",
+    b"noframes",
+    b"plaintext",
+    b"script",
+    b"style",
+    b"xmp",
+];
+
+/// Escapable raw text elements, whose contents are text rather than markup, but in which character references are decoded
+pub const RCDATA_TAGS: &[&[u8]; 2] = &[b"textarea", b"title"];
+
+/// Elements in which whitespace is significant, in addition to the raw text elements
+pub const PREFORMATTED_TAGS: &[&[u8]; 3] = &[b"listing", b"pre", b"textarea"];
+
+/// Elements that are not rendered inline, so that whitespace next to their start and end tags is insignificant
+/// and their text is separated from the surrounding text
+pub const BLOCK_TAGS: &[&[u8]; 58] = &[
+    b"address",
+    b"article",
+    b"aside",
+    b"blockquote",
+    b"body",
+    b"br",
+    b"caption",
+    b"col",
+    b"colgroup",
+    b"dd",
+    b"details",
+    b"dialog",
+    b"div",
+    b"dl",
+    b"dt",
+    b"fieldset",
+    b"figcaption",
+    b"figure",
+    b"footer",
+    b"form",
+    b"h1",
+    b"h2",
+    b"h3",
+    b"h4",
+    b"h5",
+    b"h6",
+    b"head",
+    b"header",
+    b"hgroup",
+    b"hr",
+    b"html",
+    b"legend",
+    b"li",
+    b"link",
+    b"main",
+    b"menu",
+    b"meta",
+    b"nav",
+    b"noscript",
+    b"ol",
+    b"optgroup",
+    b"option",
+    b"p",
+    b"pre",
+    b"script",
+    b"section",
+    b"style",
+    b"summary",
+    b"table",
+    b"tbody",
+    b"td",
+    b"template",
+    b"tfoot",
+    b"th",
+    b"thead",
+    b"title",
+    b"tr",
+    b"ul",
+];
+
+/// Tags that implicitly close an open `<p>` tag
+pub const CLOSES_P_TAGS: &[&[u8]; 28] = &[
+    b"address",
+    b"article",
+    b"aside",
+    b"blockquote",
+    b"details",
+    b"div",
+    b"dl",
+    b"fieldset",
+    b"figure",
+    b"footer",
+    b"form",
+    b"h1",
+    b"h2",
+    b"h3",
+    b"h4",
+    b"h5",
+    b"h6",
+    b"header",
+    b"hr",
+    b"main",
+    b"menu",
+    b"nav",
+    b"ol",
+    b"p",
+    b"pre",
+    b"section",
+    b"table",
+    b"ul",
 ];
@@ -1,4 +1,4 @@
-use crate::Node;
+use crate::{inline::vec::InlineVec, queryselector::context::MatchContext, Node, Selector};
 
 use super::Parser;
 
@@ -44,9 +44,268 @@ impl NodeHandle {
         parser.resolve_node_id_mut(self.0)
     }
 
+    /// Returns the parent of the node associated to this handle
+    ///
+    /// Returns `None` for top level nodes and for handles that point outside of the nodes table.
+    ///
+    /// Nodes do not store a reference to their parent, so this is a linear scan over the nodes table.
+    /// Parents usually precede their children, so nodes before this one are checked first.
+    ///
+    /// # Example
+    /// ```
+    /// let dom = tl::parse("<div><p>hi</p></div>", Default::default()).unwrap();
+    /// let parser = dom.parser();
+    /// let div = dom.query_selector_first("div").unwrap();
+    /// let p = dom.query_selector_first("p").unwrap();
+    /// assert_eq!(p.parent(parser), Some(div));
+    /// assert_eq!(div.parent(parser), None);
+    /// ```
+    pub fn parent(&self, parser: &Parser<'_>) -> Option<NodeHandle> {
+        let tags = &parser.tags;
+        let index = (self.0 as usize).min(tags.len());
+        let is_parent = |&(_, node): &(usize, &Node<'_>)| {
+            node.as_tag()
+                .is_some_and(|tag| tag._children.as_slice().contains(self))
+        };
+
+        let before = tags[..index].iter().enumerate().rev();
+        let after = tags.iter().enumerate().skip(index + 1);
+
+        before
+            .chain(after)
+            .find(is_parent)
+            .map(|(index, _)| NodeHandle::new(index as InnerNodeHandle))
+    }
+
+    /// Returns an iterator over the ancestors of this node: its parent, grandparent and so on, up to a top level node.
+    ///
+    /// The node itself is not included.
+    /// Every step is a call to [`NodeHandle::parent`], so this is a linear scan per ancestor.
+    ///
+    /// # Example
+    /// ```
+    /// let dom = tl::parse("<nav><ul><li>a</li></ul></nav>", Default::default()).unwrap();
+    /// let parser = dom.parser();
+    /// let li = dom.query_selector_first("li").unwrap();
+    /// let breadcrumbs = li
+    ///     .ancestors(parser)
+    ///     .map(|handle| handle.get(parser).unwrap().as_tag().unwrap().name().as_utf8_str())
+    ///     .collect::<Vec<_>>();
+    /// assert_eq!(breadcrumbs, ["ul", "nav"]);
+    /// ```
+    pub fn ancestors<'p>(&self, parser: &'p Parser<'_>) -> impl Iterator<Item = NodeHandle> + 'p {
+        // A node cannot have more ancestors than there are nodes, so this bound
+        // only matters for malformed trees that contain a cycle
+        std::iter::successors(self.parent(parser), move |handle| handle.parent(parser))
+            .take(parser.tags.len())
+    }
+
+    /// Returns the list of nodes that share a parent with this node, and the position of this node in it
+    pub(crate) fn siblings<'p>(&self, parser: &'p Parser<'_>) -> Option<(&'p [NodeHandle], usize)> {
+        let siblings = match self.parent(parser) {
+            Some(parent) => parent.get(parser)?.as_tag()?._children.as_slice(),
+            None => &parser.ast,
+        };
+
+        let position = siblings.iter().position(|handle| handle == self)?;
+        Some((siblings, position))
+    }
+
+    /// Returns the node that directly follows this node in its parent's children, including text and comment nodes
+    ///
+    /// Returns `None` if this is the last child.
+    ///
+    /// # Example
+    /// ```
+    /// let dom = tl::parse("<p>a</p> text <p>b</p>", Default::default()).unwrap();
+    /// let parser = dom.parser();
+    /// let first = dom.query_selector_first("p").unwrap();
+    /// let text = first.next_sibling(parser).unwrap();
+    /// assert_eq!(text.get(parser).unwrap().inner_text(parser), " text ");
+    ///
+    /// let second = first.next_element_sibling(parser).unwrap();
+    /// assert_eq!(second.get(parser).unwrap().inner_text(parser), "b");
+    /// ```
+    pub fn next_sibling(&self, parser: &Parser<'_>) -> Option<NodeHandle> {
+        let (siblings, position) = self.siblings(parser)?;
+        siblings.get(position + 1).copied()
+    }
+
+    /// Returns the node that directly precedes this node in its parent's children, including text and comment nodes
+    ///
+    /// Returns `None` if this is the first child.
+    pub fn previous_sibling(&self, parser: &Parser<'_>) -> Option<NodeHandle> {
+        let (siblings, position) = self.siblings(parser)?;
+        siblings.get(position.checked_sub(1)?).copied()
+    }
+
+    /// Returns the next sibling that is an HTML tag, skipping text and comment nodes
+    pub fn next_element_sibling(&self, parser: &Parser<'_>) -> Option<NodeHandle> {
+        let (siblings, position) = self.siblings(parser)?;
+        siblings[position + 1..]
+            .iter()
+            .copied()
+            .find(|handle| is_element(parser, *handle))
+    }
+
+    /// Returns the previous sibling that is an HTML tag, skipping text and comment nodes
+    pub fn previous_element_sibling(&self, parser: &Parser<'_>) -> Option<NodeHandle> {
+        let (siblings, position) = self.siblings(parser)?;
+        siblings[..position]
+            .iter()
+            .rev()
+            .copied()
+            .find(|handle| is_element(parser, *handle))
+    }
+
+    /// Returns the position of this node among the children of its parent, counting text and comment nodes
+    ///
+    /// For top level nodes, this is the position in [`VDom::children`](crate::VDom::children).
+    /// Returns `None` if the node is not part of the tree.
+    ///
+    /// # Example
+    /// ```
+    /// let dom = tl::parse("<ul><li>a</li> <li>b</li></ul>", Default::default()).unwrap();
+    /// let parser = dom.parser();
+    /// let second = dom.query_selector("li").unwrap().nth(1).unwrap();
+    ///
+    /// assert_eq!(second.index_in_parent(parser), Some(2));
+    /// assert_eq!(second.element_index_in_parent(parser), Some(1));
+    /// ```
+    pub fn index_in_parent(&self, parser: &Parser<'_>) -> Option<usize> {
+        self.siblings(parser).map(|(_, position)| position)
+    }
+
+    /// Returns the position of this element among the element children of its parent, skipping text and comment nodes
+    ///
+    /// This is the index used by `:nth-child()`, minus one.
+    /// Returns `None` if this node is not an element or not part of the tree.
+    pub fn element_index_in_parent(&self, parser: &Parser<'_>) -> Option<usize> {
+        if !is_element(parser, *self) {
+            return None;
+        }
+
+        let (siblings, position) = self.siblings(parser)?;
+        Some(
+            siblings[..position]
+                .iter()
+                .filter(|handle| is_element(parser, **handle))
+                .count(),
+        )
+    }
+
+    /// Returns the number of ancestors of this node
+    ///
+    /// Top level nodes have a depth of 0, their children a depth of 1, and so on.
+    /// Like [`NodeHandle::ancestors`], this is a linear scan per ancestor.
+    ///
+    /// # Example
+    /// ```
+    /// let dom = tl::parse("<nav><ul><li>a</li></ul></nav>", Default::default()).unwrap();
+    /// let parser = dom.parser();
+    /// assert_eq!(dom.query_selector_first("nav").unwrap().depth(parser), 0);
+    /// assert_eq!(dom.query_selector_first("li").unwrap().depth(parser), 2);
+    /// ```
+    pub fn depth(&self, parser: &Parser<'_>) -> usize {
+        self.ancestors(parser).count()
+    }
+
+    /// Parses the given HTML fragment and replaces the children of the tag associated to this handle with it
+    ///
+    /// The fragment is parsed with the same options as the document.
+    /// The nodes of the fragment are copied into the nodes table of the parser,
+    /// so the fragment string does not need to outlive the DOM.
+    /// The previous children are detached, but remain in the nodes table.
+    /// Just like nodes added with [`Parser::push_node`], the new nodes are not registered in the ID, class or tag name lookup tables.
+    ///
+    /// Returns `false` and does nothing if this handle does not refer to an HTML tag.
+    ///
+    /// # Example
+    /// ```
+    /// let mut dom = tl::parse("<div><p>old</p></div>", Default::default()).unwrap();
+    /// let div = dom.query_selector_first("div").unwrap();
+    ///
+    /// let fragment = String::from("<span>new</span>!");
+    /// assert!(div.set_inner_html(dom.parser_mut(), &fragment));
+    /// drop(fragment);
+    ///
+    /// assert_eq!(dom.outer_html(), "<div><span>new</span>!</div>");
+    /// ```
+    pub fn set_inner_html(&self, parser: &mut Parser<'_>, html: &str) -> bool {
+        if self.get(parser).and_then(Node::as_tag).is_none() {
+            return false;
+        }
+
+        let fragment = match crate::parse(html, parser.options) {
+            Ok(fragment) => fragment,
+            Err(_) => return false,
+        };
+
+        // Handles in the fragment are shifted by the number of nodes that already exist
+        let offset = parser.tags.len() as InnerNodeHandle;
+        let shift = |handle: &NodeHandle| NodeHandle::new(handle.0 + offset);
+
+        for node in fragment.nodes() {
+            let mut node = node.to_static();
+
+            if let Node::Tag(tag) = &mut node {
+                let mut children = InlineVec::new();
+                for child in tag._children.iter() {
+                    children.push(shift(child));
+                }
+                tag._children = children;
+            }
+
+            parser.push_node(node);
+        }
+
+        let mut children = InlineVec::new();
+        for child in fragment.children() {
+            children.push(shift(child));
+        }
+
+        let tag = self.get_mut(parser).and_then(Node::as_tag_mut).unwrap();
+        tag._children = children;
+        tag._modified = true;
+        true
+    }
+
+    /// Checks whether the node associated to this handle matches the given query selector
+    ///
+    /// Returns `false` if the selector is invalid or if this handle points outside of the nodes table.
+    /// If the same selector is used repeatedly, consider parsing it once with [`crate::parse_query_selector`]
+    /// and using [`NodeHandle::matches_selector`] instead.
+    ///
+    /// # Example
+    /// ```
+    /// let dom = tl::parse(r#"<div><p class="foo" data-x>bar</p></div>"#, Default::default()).unwrap();
+    /// let handle = dom.query_selector_first("p").unwrap();
+    /// assert!(handle.matches(dom.parser(), ".foo[data-x]"));
+    /// assert!(handle.matches(dom.parser(), "div > p"));
+    /// assert!(!handle.matches(dom.parser(), "span"));
+    /// ```
+    pub fn matches(&self, parser: &Parser<'_>, selector: &str) -> bool {
+        crate::parse_query_selector(selector)
+            .is_some_and(|selector| self.matches_selector(parser, &selector))
+    }
+
+    /// Checks whether the node associated to this handle matches the given, already parsed query selector
+    ///
+    /// Returns `false` if this handle points outside of the nodes table.
+    pub fn matches_selector(&self, parser: &Parser<'_>, selector: &Selector<'_>) -> bool {
+        selector.matches_in(&MatchContext::new(parser), *self)
+    }
+
     /// Returns the internal unique Node ID that maps to a specific node in the node table
     #[inline]
     pub fn get_inner(&self) -> InnerNodeHandle {
         self.0
     }
 }
+
+/// Checks whether the given handle refers to an HTML tag
+fn is_element(parser: &Parser<'_>, handle: NodeHandle) -> bool {
+    handle
+        .get(parser)
+        .is_some_and(|node| node.as_tag().is_some())
+}
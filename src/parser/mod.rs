@@ -2,9 +2,11 @@ mod base;
 pub(crate) mod constants;
 mod handle;
 mod options;
+mod streaming;
 mod tag;
 
 pub use base::*;
 pub use handle::*;
 pub use options::*;
+pub use streaming::*;
 pub use tag::*;
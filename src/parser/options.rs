@@ -1,24 +1,29 @@
+use super::constants;
+
 mod flags {
     pub const TRACK_IDS: u8 = 1 << 0;
     pub const TRACK_CLASSES: u8 = 1 << 1;
-    pub const HIGHEST: u8 = TRACK_CLASSES;
+    pub const SKIP_WHITESPACE_TEXT_NODES: u8 = 1 << 2;
+    pub const TRACK_TAGS: u8 = 1 << 3;
+    pub const STRICT: u8 = 1 << 4;
+    pub const DROP_COMMENTS: u8 = 1 << 5;
+    pub const INTERN_TAG_NAMES: u8 = 1 << 6;
+    pub const HIGHEST: u8 = INTERN_TAG_NAMES;
+
+    pub const TRACKING: u8 = TRACK_IDS | TRACK_CLASSES | TRACK_TAGS;
 }
 
 /// Options for the HTML Parser
 ///
 /// This allows users of this library to configure the parser.
 /// The default options (`ParserOptions::default()`) are optimized for raw parsing.
-/// If you need to do HTML tag lookups by ID or class names, you can enable tracking.
+/// If you need to do HTML tag lookups by ID, class or tag names, you can enable tracking.
 /// This will cache HTML nodes as they appear in the source code on the fly.
-#[derive(Debug, Copy, Clone, PartialEq)]
+#[derive(Debug, Copy, Clone, PartialEq, Default)]
 pub struct ParserOptions {
     flags: u8,
-}
-
-impl Default for ParserOptions {
-    fn default() -> Self {
-        Self { flags: 0 }
-    }
+    max_depth: Option<u32>,
+    extra_void_tags: &'static [&'static str],
 }
 
 impl ParserOptions {
@@ -34,7 +39,7 @@ impl ParserOptions {
         } else {
             Some(Self {
                 flags,
-                ..Default::default()
+                ..Self::default()
             })
         }
     }
@@ -48,6 +53,10 @@ impl ParserOptions {
         self.flags |= flag;
     }
 
+    fn clear_flag(&mut self, flag: u8) {
+        self.flags &= !flag;
+    }
+
     #[inline]
     fn has_flag(&self, flag: u8) -> bool {
         self.flags & flag != 0
@@ -69,6 +78,140 @@ impl ParserOptions {
         self
     }
 
+    /// Enables tracking of HTML Tag names and stores them in a lookup table.
+    ///
+    /// This makes `get_elements_by_tag_name()` lookups ~O(1)
+    pub fn track_tags(mut self) -> Self {
+        self.set_flag(flags::TRACK_TAGS);
+        self
+    }
+
+    /// Assigns every distinct tag name a small integer id while parsing.
+    ///
+    /// Tag name selectors (e.g. `div`) and `get_elements_by_tag_name()` then compare these ids
+    /// instead of the names, which speeds up queries on large documents at a small cost during parsing.
+    ///
+    /// # Example
+    /// ```
+    /// let options = tl::ParserOptions::default().intern_tag_names();
+    /// let dom = tl::parse("<div><p>a</p><p>b</p></div>", options).unwrap();
+    /// assert_eq!(dom.query_selector("div > p").unwrap().count(), 2);
+    /// ```
+    pub fn intern_tag_names(mut self) -> Self {
+        self.set_flag(flags::INTERN_TAG_NAMES);
+        self
+    }
+
+    /// Skips text nodes that only consist of ASCII whitespace, such as the indentation between tags.
+    ///
+    /// Whitespace inside of `<pre>` and `<textarea>` tags is significant and is always kept.
+    pub fn skip_whitespace_text_nodes(mut self) -> Self {
+        self.set_flag(flags::SKIP_WHITESPACE_TEXT_NODES);
+        self
+    }
+
+    /// Sets whether comments are kept in the tree.
+    ///
+    /// By default, comments are kept as [`Node::Comment`](crate::Node::Comment) nodes.
+    /// If disabled, comments are skipped and do not appear in the tree.
+    ///
+    /// # Example
+    /// ```
+    /// let options = tl::ParserOptions::default().preserve_comments(false);
+    /// let dom = tl::parse("<p><!-- hi -->Hello</p>", options).unwrap();
+    /// assert_eq!(dom.outer_html(), "<p>Hello</p>");
+    /// ```
+    pub fn preserve_comments(mut self, preserve: bool) -> Self {
+        if preserve {
+            self.clear_flag(flags::DROP_COMMENTS);
+        } else {
+            self.set_flag(flags::DROP_COMMENTS);
+        }
+        self
+    }
+
+    /// Makes parsing fail on malformed HTML instead of recovering from it.
+    ///
+    /// In strict mode, [`parse`](crate::parse) returns an error if a tag is never closed,
+    /// an end tag does not match the last open tag, or the input ends in the middle of a tag.
+    /// Void elements like `<br>` and self-closing tags do not need to be closed.
+    ///
+    /// # Example
+    /// ```
+    /// let options = tl::ParserOptions::default().strict();
+    /// assert!(tl::parse("<div><p>Hello</p></div>", options).is_ok());
+    /// assert_eq!(
+    ///     tl::parse("<div><p>Hello</div>", options).unwrap_err(),
+    ///     tl::ParseError::MismatchedEndTag { offset: 13 }
+    /// );
+    /// ```
+    pub fn strict(mut self) -> Self {
+        self.set_flag(flags::STRICT);
+        self
+    }
+
+    /// Limits how deeply tags can be nested.
+    ///
+    /// Start tags that would be nested deeper than `depth` are not parsed as elements, but kept as text,
+    /// along with the matching end tags. This bounds the depth of the tree for adversarial input,
+    /// which protects code that walks the tree recursively.
+    /// Use [`VDom::depth_limit_exceeded`](crate::VDom::depth_limit_exceeded) to check whether this happened.
+    ///
+    /// By default, there is no limit.
+    ///
+    /// # Example
+    /// ```
+    /// let options = tl::ParserOptions::default().max_depth(2);
+    /// let dom = tl::parse("<div><div><div>x</div></div></div>", options).unwrap();
+    /// assert!(dom.depth_limit_exceeded());
+    /// assert_eq!(dom.nodes()[1].inner_text(dom.parser()), "<div>x</div>");
+    /// ```
+    pub fn max_depth(mut self, depth: u32) -> Self {
+        self.max_depth = Some(depth);
+        self
+    }
+
+    /// Treats the given tags as void elements, in addition to the standard ones like `<br>` and `<img>`.
+    ///
+    /// Void elements have neither content nor an end tag, so the parser does not nest the following nodes inside of them,
+    /// and serializing them does not write an end tag.
+    /// This is useful for custom elements that are used without an end tag.
+    ///
+    /// # Example
+    /// ```
+    /// let options = tl::ParserOptions::default().extra_void_tags(&["my-icon"]);
+    /// let dom = tl::parse("<p><my-icon name=\"x\">Text</p>", options).unwrap();
+    /// assert_eq!(dom.outer_html(), "<p><my-icon name=\"x\">Text</p>");
+    /// ```
+    pub fn extra_void_tags(mut self, tags: &'static [&'static str]) -> Self {
+        self.extra_void_tags = tags;
+        self
+    }
+
+    /// Returns the tags that were configured as void with [`ParserOptions::extra_void_tags`].
+    #[inline]
+    pub fn get_extra_void_tags(&self) -> &'static [&'static str] {
+        self.extra_void_tags
+    }
+
+    /// Returns whether a tag with the given name is a void element, which has neither content nor an end tag.
+    ///
+    /// This includes the standard void elements and the ones configured with [`ParserOptions::extra_void_tags`].
+    #[inline]
+    pub fn is_void_tag(&self, name: &[u8]) -> bool {
+        constants::VOID_TAGS.contains(&name)
+            || self
+                .extra_void_tags
+                .iter()
+                .any(|tag| tag.as_bytes() == name)
+    }
+
+    /// Returns the maximum nesting depth of tags, if set with [`ParserOptions::max_depth`].
+    #[inline]
+    pub fn get_max_depth(&self) -> Option<u32> {
+        self.max_depth
+    }
+
     /// Returns whether the parser is tracking HTML Tag IDs.
     #[inline]
     pub fn is_tracking_ids(&self) -> bool {
@@ -81,12 +224,39 @@ impl ParserOptions {
         self.has_flag(flags::TRACK_CLASSES)
     }
 
-    /// Returns whether the parser is tracking HTML Tag IDs or classes (previously enabled by a call to `track_ids()` or `track_classes()`).
+    /// Returns whether the parser is tracking HTML Tag names.
+    #[inline]
+    pub fn is_tracking_tags(&self) -> bool {
+        self.has_flag(flags::TRACK_TAGS)
+    }
+
+    /// Returns whether tag names are interned.
+    #[inline]
+    pub fn is_interning_tag_names(&self) -> bool {
+        self.has_flag(flags::INTERN_TAG_NAMES)
+    }
+
+    /// Returns whether whitespace-only text nodes are skipped.
+    #[inline]
+    pub fn is_skipping_whitespace_text_nodes(&self) -> bool {
+        self.has_flag(flags::SKIP_WHITESPACE_TEXT_NODES)
+    }
+
+    /// Returns whether comments are kept in the tree.
+    #[inline]
+    pub fn is_preserving_comments(&self) -> bool {
+        !self.has_flag(flags::DROP_COMMENTS)
+    }
+
+    /// Returns whether strict parsing is enabled.
+    #[inline]
+    pub fn is_strict(&self) -> bool {
+        self.has_flag(flags::STRICT)
+    }
+
+    /// Returns whether the parser is tracking HTML Tag IDs, classes or names (previously enabled by a call to `track_ids()`, `track_classes()` or `track_tags()`).
     #[inline]
     pub fn is_tracking(&self) -> bool {
-        // for now we can just check if any bit is set, may or may not lead to better codegen than two cmps
-        // this must be changed in some way if we ever add more flags
-        // self.is_tracking_ids() || self.is_tracking_classes()
-        self.flags != 0
+        self.has_flag(flags::TRACKING)
     }
 }
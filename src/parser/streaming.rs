@@ -0,0 +1,315 @@
+use std::ops::ControlFlow;
+
+use super::{constants, tag::HTMLTag, Parser};
+use crate::{inline::vec::InlineVec, Bytes, ParseError, ParserOptions};
+
+/// Callbacks that are invoked by [`parse_streaming`](crate::parse_streaming) as the input is parsed
+///
+/// Every method has a default implementation that does nothing, so only the events of interest need to be implemented.
+/// Returning [`ControlFlow::Break`] from any of them stops parsing.
+pub trait TagHandler<'a> {
+    /// Called for every start tag, such as `<a href="/">`
+    ///
+    /// The tag has no children and its [raw](HTMLTag::raw) source only covers the start tag.
+    /// Self-closing tags and void elements like `<br>` are immediately followed by a call to [`TagHandler::on_close_tag`].
+    fn on_open_tag(&mut self, tag: &HTMLTag<'a>) -> ControlFlow<()> {
+        let _ = tag;
+        ControlFlow::Continue(())
+    }
+
+    /// Called for every end tag, such as `</a>`, with the name of the tag
+    ///
+    /// End tags are reported as they appear in the source, even if they do not match the last start tag.
+    fn on_close_tag(&mut self, name: &Bytes<'a>) -> ControlFlow<()> {
+        let _ = name;
+        ControlFlow::Continue(())
+    }
+
+    /// Called for text between tags, and for the contents of CDATA sections
+    fn on_text(&mut self, text: &Bytes<'a>) -> ControlFlow<()> {
+        let _ = text;
+        ControlFlow::Continue(())
+    }
+
+    /// Called for every comment, including the `<!--` and `-->`
+    ///
+    /// This is not called if comments are disabled with [`ParserOptions::preserve_comments`](crate::ParserOptions::preserve_comments).
+    fn on_comment(&mut self, comment: &Bytes<'a>) -> ControlFlow<()> {
+        let _ = comment;
+        ControlFlow::Continue(())
+    }
+}
+
+impl<'a, H: TagHandler<'a> + ?Sized> TagHandler<'a> for &mut H {
+    fn on_open_tag(&mut self, tag: &HTMLTag<'a>) -> ControlFlow<()> {
+        (**self).on_open_tag(tag)
+    }
+
+    fn on_close_tag(&mut self, name: &Bytes<'a>) -> ControlFlow<()> {
+        (**self).on_close_tag(name)
+    }
+
+    fn on_text(&mut self, text: &Bytes<'a>) -> ControlFlow<()> {
+        (**self).on_text(text)
+    }
+
+    fn on_comment(&mut self, comment: &Bytes<'a>) -> ControlFlow<()> {
+        (**self).on_comment(comment)
+    }
+}
+
+/// Returns early if the handler asked to stop parsing
+macro_rules! emit {
+    ($event:expr) => {
+        if $event.is_break() {
+            return Ok(ControlFlow::Break(()));
+        }
+    };
+}
+
+/// Rewinds to the start of the current token and returns early if it reached the end of the input,
+/// and more input may follow
+///
+/// A token that ends exactly at the end of the input is also retried, because the tokenizer cannot tell
+/// whether it would have continued.
+macro_rules! defer_incomplete {
+    ($self:ident, $start:expr, $is_last:expr) => {
+        if !$is_last && $self.stream.is_eof() {
+            $self.stream.idx = $start;
+            return Ok(ControlFlow::Continue(()));
+        }
+    };
+}
+
+impl<'a> Parser<'a> {
+    /// Parses the input like [`Parser::parse`], but reports every token to the handler instead of building a tree
+    pub(crate) fn parse_streaming<H: TagHandler<'a>>(
+        &mut self,
+        handler: H,
+    ) -> Result<(), ParseError> {
+        let mut preserve_whitespace = 0;
+        self.stream_tokens(handler, &mut preserve_whitespace, true)
+            .map(|_| ())
+    }
+
+    /// Reports the tokens of the input to the handler
+    ///
+    /// If `is_last` is false, the input is a prefix of the document, and parsing stops before the first token
+    /// that may be incomplete. The position of the stream is then the start of that token.
+    /// `preserve_whitespace` is the number of open tags in which whitespace is significant, and is kept between calls.
+    fn stream_tokens<H: TagHandler<'a>>(
+        &mut self,
+        mut handler: H,
+        preserve_whitespace: &mut usize,
+        is_last: bool,
+    ) -> Result<ControlFlow<()>, ParseError> {
+        if self.stream.len() > u32::MAX as usize {
+            return Err(ParseError::InvalidLength);
+        }
+
+        while let Some(cur) = self.stream.current_cpy() {
+            let start = self.stream.idx;
+
+            if cur != b'<' {
+                let raw = self.read_to(b'<');
+                defer_incomplete!(self, start, is_last);
+
+                if self.options.is_skipping_whitespace_text_nodes()
+                    && raw.iter().all(u8::is_ascii_whitespace)
+                    && *preserve_whitespace == 0
+                {
+                    continue;
+                }
+
+                emit!(handler.on_text(&raw.into()));
+                continue;
+            }
+
+            self.stream.advance();
+            self.skip_whitespaces();
+
+            match self.stream.current_cpy() {
+                None => {
+                    defer_incomplete!(self, start, is_last);
+                    break;
+                }
+                Some(b'/') => {
+                    self.stream.advance();
+                    let name = self.read_to(b'>').trim_ascii_end();
+                    self.stream.expect_and_skip_cond(b'>');
+                    defer_incomplete!(self, start, is_last);
+
+                    if matches!(name, b"pre" | b"textarea") {
+                        *preserve_whitespace = preserve_whitespace.saturating_sub(1);
+                    }
+
+                    emit!(handler.on_close_tag(&name.into()));
+                }
+                Some(b'!') => {
+                    self.stream.advance();
+
+                    if self.stream.slice_len(self.stream.idx, 2) == constants::COMMENT {
+                        let comment = self.skip_comment_with_start(start);
+                        defer_incomplete!(self, start, is_last);
+
+                        if self.options.is_preserving_comments() {
+                            emit!(handler.on_comment(&comment.into()));
+                        }
+                    } else if self
+                        .stream
+                        .slice_len(self.stream.idx, constants::CDATA_START.len())
+                        == constants::CDATA_START
+                    {
+                        self.stream.advance_by(constants::CDATA_START.len());
+                        let cdata = self.read_cdata();
+                        defer_incomplete!(self, start, is_last);
+
+                        emit!(handler.on_text(&cdata.into()));
+                    } else {
+                        // doctype and other declarations are not reported
+                        self.read_to(b'>');
+                        self.stream.expect_and_skip_cond(b'>');
+                        defer_incomplete!(self, start, is_last);
+                    }
+                }
+                Some(_) => {
+                    let name = self.read_ident();
+                    self.skip_whitespaces();
+                    let attributes = name.and_then(|_| self.parse_attributes());
+                    defer_incomplete!(self, start, is_last);
+
+                    let (Some(name), Some(attributes)) = (name, attributes) else {
+                        break;
+                    };
+
+                    let is_self_closing = self.stream.expect_and_skip_cond(b'/');
+                    let is_closed = self.stream.expect_and_skip_cond(b'>');
+                    defer_incomplete!(self, start, is_last);
+
+                    if !is_closed {
+                        continue;
+                    }
+
+                    let tag = HTMLTag::new(
+                        name.into(),
+                        attributes,
+                        InlineVec::new(),
+                        self.stream.slice(start, self.stream.idx).into(),
+                    );
+
+                    emit!(handler.on_open_tag(&tag));
+
+                    if is_self_closing || self.options.is_void_tag(name) {
+                        emit!(handler.on_close_tag(tag.name()));
+                    } else if matches!(name, b"pre" | b"textarea") {
+                        *preserve_whitespace += 1;
+                    }
+                }
+            }
+        }
+
+        Ok(ControlFlow::Continue(()))
+    }
+}
+
+/// A parser that is fed the input in chunks, such as when reading a response from a network connection
+///
+/// Tokens are reported to the [`TagHandler`] as soon as they are complete, like with [`parse_streaming`](crate::parse_streaming).
+/// The unparsed end of the input, such as half of a tag, is buffered until the next chunk arrives.
+/// Since the buffered input does not outlive a call to [`ChunkedParser::feed`], the handler must accept tokens of any lifetime,
+/// which is the case if it implements `TagHandler<'a>` for every `'a`.
+///
+/// A token that spans many chunks is parsed again for every chunk, so feeding very small chunks can be slow.
+///
+/// # Example
+/// ```
+/// use std::ops::ControlFlow;
+/// use tl::{Bytes, ChunkedParser, TagHandler};
+///
+/// #[derive(Default)]
+/// struct Text(String);
+///
+/// impl<'a> TagHandler<'a> for Text {
+///     fn on_text(&mut self, text: &Bytes<'a>) -> ControlFlow<()> {
+///         self.0.push_str(&text.as_utf8_str());
+///         ControlFlow::Continue(())
+///     }
+/// }
+///
+/// let mut parser = ChunkedParser::new(Default::default(), Text::default());
+/// parser.feed(b"<p>Hel").unwrap();
+/// parser.feed(b"lo <b>wor").unwrap();
+/// parser.feed(b"ld</b></p>").unwrap();
+/// assert_eq!(parser.finish().unwrap().0, "Hello world");
+/// ```
+#[derive(Debug)]
+pub struct ChunkedParser<H> {
+    /// The input that has not been parsed yet
+    buffer: Vec<u8>,
+    options: ParserOptions,
+    handler: H,
+    /// The number of open tags in which whitespace is significant
+    preserve_whitespace: usize,
+    /// Whether the handler asked to stop parsing
+    stopped: bool,
+}
+
+impl<H: for<'b> TagHandler<'b>> ChunkedParser<H> {
+    /// Creates a new parser that reports tokens to the given handler
+    pub fn new(options: ParserOptions, handler: H) -> Self {
+        Self {
+            buffer: Vec::new(),
+            options,
+            handler,
+            preserve_whitespace: 0,
+            stopped: false,
+        }
+    }
+
+    /// Parses the next chunk of the input
+    ///
+    /// Chunks may be split anywhere, even in the middle of a tag or a UTF-8 sequence.
+    /// After the handler has stopped parsing by returning [`ControlFlow::Break`], further chunks are ignored.
+    pub fn feed(&mut self, chunk: &[u8]) -> Result<(), ParseError> {
+        self.parse(chunk, false)
+    }
+
+    /// Parses the rest of the buffered input, and returns the handler
+    pub fn finish(mut self) -> Result<H, ParseError> {
+        self.parse(&[], true)?;
+        Ok(self.handler)
+    }
+
+    /// Returns a reference to the handler
+    pub fn handler(&self) -> &H {
+        &self.handler
+    }
+
+    /// Returns a mutable reference to the handler
+    pub fn handler_mut(&mut self) -> &mut H {
+        &mut self.handler
+    }
+
+    fn parse(&mut self, chunk: &[u8], is_last: bool) -> Result<(), ParseError> {
+        if self.stopped {
+            return Ok(());
+        }
+
+        self.buffer.extend_from_slice(chunk);
+
+        let mut parser = Parser::new(&self.buffer, self.options);
+        let flow =
+            parser.stream_tokens(&mut self.handler, &mut self.preserve_whitespace, is_last)?;
+        let consumed = parser.stream.idx;
+        drop(parser);
+
+        if flow.is_break() {
+            self.stopped = true;
+            self.buffer = Vec::new();
+        } else {
+            self.buffer.drain(..consumed);
+        }
+
+        Ok(())
+    }
+}
@@ -1,18 +1,75 @@
 use crate::{
+    entities,
     inline::{hashmap::InlineHashMap, vec::InlineVec},
-    queryselector::{self, QuerySelectorIterator},
-    Bytes, InnerNodeHandle,
+    queryselector::{self, QuerySelectorIterator, Selector},
+    util, Bytes, InnerNodeHandle,
 };
-use std::{borrow::Cow, mem};
+use std::{borrow::Cow, fmt, mem, num::NonZeroU32};
 
-use super::{handle::NodeHandle, Parser};
+use super::{constants, handle::NodeHandle, Parser};
 
 const INLINED_ATTRIBUTES: usize = 2;
 const INLINED_SUBNODES: usize = 2;
-const HTML_VOID_ELEMENTS: [&str; 16] = [
-    "area", "base", "br", "col", "command", "embed", "hr", "img", "input", "keygen", "link",
-    "meta", "param", "source", "track", "wbr",
-];
+
+/// Returns the text of a raw text node, optionally decoding character references
+fn raw_text<'s>(raw: &'s Bytes<'_>, decode: bool) -> Cow<'s, str> {
+    let text = raw.as_utf8_str();
+
+    if decode {
+        entities::decode_cow(text)
+    } else {
+        text
+    }
+}
+
+/// Strips the `<!--` and `-->` delimiters of a comment
+///
+/// Comments that were not closed before the end of the input only have the opening delimiter removed.
+pub(crate) fn comment_content(comment: &[u8]) -> &[u8] {
+    let content = comment.strip_prefix(b"<!--").unwrap_or(comment);
+    content.strip_suffix(b"-->").unwrap_or(content)
+}
+
+/// Strips the `data-` prefix of a custom data attribute key, ignoring the case of the prefix
+fn strip_data_prefix(key: &[u8]) -> Option<&[u8]> {
+    const PREFIX: &[u8] = b"data-";
+
+    key.get(..PREFIX.len())
+        .is_some_and(|prefix| prefix.eq_ignore_ascii_case(PREFIX))
+        .then(|| &key[PREFIX.len()..])
+}
+
+/// A pending step of serializing a tree in [`HTMLTag::write_inner_html`]
+enum SerializeStep<'p, 'a> {
+    /// Write a node and its children, with the given escaping
+    Node(&'p Node<'a>, bool),
+    /// Write the end tag of an element whose children have been written
+    EndTag(&'p HTMLTag<'a>),
+}
+
+/// Pushes the children of a tag onto the serialization stack, so that the first child is popped first
+fn push_children<'p, 'a>(
+    stack: &mut Vec<SerializeStep<'p, 'a>>,
+    tag: &HTMLTag<'a>,
+    parser: &'p Parser<'a>,
+    escape: bool,
+) {
+    for handle in tag.children().top().as_slice().iter().rev() {
+        stack.push(SerializeStep::Node(handle.get(parser).unwrap(), escape));
+    }
+}
+
+/// The adapter returned by [`Node::display`]
+struct NodeDisplay<'p, 'a> {
+    node: &'p Node<'a>,
+    parser: &'p Parser<'a>,
+}
+
+impl fmt::Display for NodeDisplay<'_, '_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.node.outer_html_to(self.parser, f)
+    }
+}
 
 /// The type of map for "raw" attributes
 pub type RawAttributesMap<'a> = InlineHashMap<Bytes<'a>, Option<Bytes<'a>>, INLINED_ATTRIBUTES>;
@@ -20,6 +77,45 @@ pub type RawAttributesMap<'a> = InlineHashMap<Bytes<'a>, Option<Bytes<'a>>, INLI
 /// The type of vector for children of an HTML tag
 pub type RawChildren = InlineVec<NodeHandle, INLINED_SUBNODES>;
 
+/// Where `id` and `class` are placed among the raw attributes, so that attributes are iterated in source order
+#[derive(Debug, Clone, Copy, Default)]
+struct SpecialPositions {
+    /// The number of raw attributes before `id`
+    id: usize,
+    /// The number of raw attributes before `class`
+    class: usize,
+    /// Whether `class` comes before `id`, which is needed if both have the same position
+    class_first: bool,
+}
+
+impl SpecialPositions {
+    /// Inserts the `id` and `class` items, if present, into an iterator over the raw attributes
+    fn place<T>(
+        self,
+        raw: impl Iterator<Item = T>,
+        id: Option<T>,
+        class: Option<T>,
+    ) -> impl Iterator<Item = T> {
+        let mut special = [(self.id, id), (self.class, class)];
+        if self.class_first {
+            special.swap(0, 1);
+        }
+
+        let mut special = special.into_iter().peekable();
+        let mut raw = raw.enumerate().peekable();
+        std::iter::from_fn(move || loop {
+            match special.peek() {
+                Some(&(position, _)) if raw.peek().is_none_or(|&(index, _)| index >= position) => {
+                    if let Some((_, Some(item))) = special.next() {
+                        return Some(item);
+                    }
+                }
+                _ => return raw.next().map(|(_, item)| item),
+            }
+        })
+    }
+}
+
 /// Stores all attributes of an HTML tag, as well as additional metadata such as `id` and `class`
 #[derive(Debug, Clone)]
 pub struct Attributes<'a> {
@@ -29,6 +125,8 @@ pub struct Attributes<'a> {
     pub(crate) id: Option<Bytes<'a>>,
     /// A list of class names of this HTML element, if present
     pub(crate) class: Option<Bytes<'a>>,
+    /// The positions of `id` and `class` among the raw attributes
+    positions: SpecialPositions,
 }
 
 impl<'a> Attributes<'a> {
@@ -38,7 +136,26 @@ impl<'a> Attributes<'a> {
             raw: InlineHashMap::new(),
             id: None,
             class: None,
+            positions: SpecialPositions::default(),
+        }
+    }
+
+    /// Sets the `id` attribute. If it did not exist yet, it is placed after all other attributes.
+    pub(crate) fn set_id(&mut self, value: Option<Bytes<'a>>) {
+        if self.id.is_none() {
+            self.positions.id = self.raw.len();
+            self.positions.class_first = true;
         }
+        self.id = value;
+    }
+
+    /// Sets the `class` attribute. If it did not exist yet, it is placed after all other attributes.
+    pub(crate) fn set_class(&mut self, value: Option<Bytes<'a>>) {
+        if self.class.is_none() {
+            self.positions.class = self.raw.len();
+            self.positions.class_first = false;
+        }
+        self.class = value;
     }
 
     /// Counts the number of attributes
@@ -61,17 +178,28 @@ impl<'a> Attributes<'a> {
     /// Checks whether a given string is in the class names list
     pub fn is_class_member<B: AsRef<[u8]>>(&self, member: B) -> bool {
         self.class_iter()
-            .map_or(false, |mut i| i.any(|s| s.as_bytes() == member.as_ref()))
+            .is_some_and(|mut i| i.any(|s| s.as_bytes() == member.as_ref()))
     }
 
     /// Checks whether this attributes collection contains a given key and returns its value
     ///
     /// Attributes that exist in this tag but have no value set will have their inner Option set to None
+    ///
+    /// Attribute names are ASCII case-insensitive: they are lowercased when parsing, and so is the given key.
+    ///
+    /// # Example
+    /// ```
+    /// let dom = tl::parse(r#"<a HREF="/">link</a>"#, Default::default()).unwrap();
+    /// let attributes = dom.nodes()[0].as_tag().unwrap().attributes();
+    ///
+    /// assert_eq!(attributes.get("href"), Some(Some(&"/".into())));
+    /// assert_eq!(attributes.get("Href"), Some(Some(&"/".into())));
+    /// ```
     pub fn get<B>(&self, key: B) -> Option<Option<&Bytes<'a>>>
     where
         B: Into<Bytes<'a>>,
     {
-        let key: Bytes = key.into();
+        let key = util::to_lower_bytes(key);
 
         match key.as_bytes() {
             b"id" => self.id.as_ref().map(Some),
@@ -81,6 +209,8 @@ impl<'a> Attributes<'a> {
     }
 
     /// Checks whether this attributes collection contains a given key
+    ///
+    /// Like [`Attributes::get`], the case of the key is ignored.
     pub fn contains<B>(&self, key: B) -> bool
     where
         B: Into<Bytes<'a>>,
@@ -88,6 +218,84 @@ impl<'a> Attributes<'a> {
         self.get(key).is_some()
     }
 
+    /// Checks whether a boolean attribute, such as `disabled` or `checked`, is present.
+    ///
+    /// The value of the attribute is ignored, so `disabled`, `disabled=""` and even `disabled="false"` all count as present,
+    /// which is how browsers treat boolean attributes.
+    ///
+    /// # Example
+    /// ```
+    /// let dom = tl::parse(r#"<input DISABLED>"#, Default::default()).unwrap();
+    /// let attributes = dom.nodes()[0].as_tag().unwrap().attributes();
+    ///
+    /// assert!(attributes.has_bool("disabled"));
+    /// assert!(!attributes.has_bool("readonly"));
+    /// ```
+    pub fn has_bool(&self, key: &str) -> bool {
+        self.contains(key)
+    }
+
+    /// Checks whether a boolean attribute is present and has one of the values allowed by the HTML standard:
+    /// no value, the empty string, or the name of the attribute itself (ignoring case).
+    ///
+    /// This is stricter than [`Attributes::has_bool`], which accepts any value.
+    ///
+    /// # Example
+    /// ```
+    /// let dom = tl::parse(r#"<option selected="selected" hidden="no">"#, Default::default()).unwrap();
+    /// let attributes = dom.nodes()[0].as_tag().unwrap().attributes();
+    ///
+    /// assert!(attributes.is_truthy("selected"));
+    /// assert!(!attributes.is_truthy("hidden"));
+    /// assert!(attributes.has_bool("hidden"));
+    /// ```
+    pub fn is_truthy(&self, key: &str) -> bool {
+        self.get(key).is_some_and(|value| {
+            value.is_none_or(|value| {
+                let value = value.as_bytes();
+                value.is_empty() || value.eq_ignore_ascii_case(key.as_bytes())
+            })
+        })
+    }
+
+    /// Returns the position `(start, end)` of the value of an attribute in the source string, excluding quotes
+    ///
+    /// Unlike [`HTMLTag::boundaries`], `end` is exclusive, so that empty values can be represented.
+    /// `None` is returned if the attribute does not exist, has no value,
+    /// or if its value was changed and no longer refers to the source string.
+    ///
+    /// # Example
+    /// ```
+    /// let source = r#"<a href="/old" title=''>link</a>"#;
+    /// let dom = tl::parse(source, Default::default()).unwrap();
+    /// let attributes = dom.nodes()[0].as_tag().unwrap().attributes();
+    ///
+    /// let (start, end) = attributes.value_span("href", dom.parser()).unwrap();
+    /// assert_eq!(&source[start..end], "/old");
+    ///
+    /// let patched = format!("{}{}{}", &source[..start], "/new", &source[end..]);
+    /// assert_eq!(patched, r#"<a href="/new" title=''>link</a>"#);
+    ///
+    /// assert_eq!(attributes.value_span("title", dom.parser()), Some((22, 22)));
+    /// assert_eq!(attributes.value_span("id", dom.parser()), None);
+    /// ```
+    pub fn value_span<B>(&self, key: B, parser: &Parser<'a>) -> Option<(usize, usize)>
+    where
+        B: Into<Bytes<'a>>,
+    {
+        let value = self.get(key).flatten()?.as_bytes_borrowed()?;
+        let input = parser.stream.data().as_ptr_range();
+        let value = value.as_ptr_range();
+
+        if value.start < input.start || value.end > input.end {
+            return None;
+        }
+
+        let start = value.start as usize - input.start as usize;
+        let end = value.end as usize - input.start as usize;
+        Some((start, end))
+    }
+
     /// Removes an attribute from this collection and returns it.
     ///
     /// As with [`Attributes::get()`], the outer Option is set to None if the attribute does not exist.
@@ -106,12 +314,23 @@ impl<'a> Attributes<'a> {
     where
         B: Into<Bytes<'a>>,
     {
-        let key: Bytes = key.into();
+        let key = util::to_lower_bytes(key);
 
         match key.as_bytes() {
             b"id" => self.id.take().map(Some),
             b"class" => self.class.take().map(Some),
-            _ => self.raw.remove(&key),
+            _ => {
+                let index = self.raw.iter().position(|(k, _)| *k == key)?;
+
+                // `id` and `class` keep their place relative to the remaining attributes
+                for position in [&mut self.positions.id, &mut self.positions.class] {
+                    if *position > index {
+                        *position -= 1;
+                    }
+                }
+
+                self.raw.remove(&key)
+            }
         }
     }
 
@@ -130,7 +349,7 @@ impl<'a> Attributes<'a> {
     where
         B: Into<Bytes<'a>>,
     {
-        let key: Bytes = key.into();
+        let key = util::to_lower_bytes(key);
 
         match key.as_bytes() {
             b"id" => self.id.take(),
@@ -144,7 +363,7 @@ impl<'a> Attributes<'a> {
     where
         B: Into<Bytes<'a>>,
     {
-        let key: Bytes = key.into();
+        let key = util::to_lower_bytes(key);
 
         match key.as_bytes() {
             b"id" => self.id.as_mut().map(Some),
@@ -159,37 +378,97 @@ impl<'a> Attributes<'a> {
         K: Into<Bytes<'a>>,
         V: Into<Bytes<'a>>,
     {
-        let key: Bytes = key.into();
+        let key = util::to_lower_bytes(key);
         let value = value.map(Into::into);
 
         match key.as_bytes() {
-            b"id" => self.id = value,
-            b"class" => self.class = value,
+            b"id" => self.set_id(value),
+            b"class" => self.set_class(value),
             _ => self.raw.insert(key, value),
         };
     }
 
+    /// Renames an attribute, keeping its value.
+    ///
+    /// Returns `false` if there is no attribute with the key `from`, in which case nothing is changed.
+    /// An existing attribute with the key `to` is replaced.
+    /// Since `id` and `class` cannot exist without a value, renaming a valueless attribute to one of these removes it.
+    ///
+    /// # Example
+    /// ```
+    /// let mut dom = tl::parse(r#"<img datasrc="a.png" hidden>"#, Default::default()).unwrap();
+    /// let attributes = dom.nodes_mut()[0].as_tag_mut().unwrap().attributes_mut();
+    ///
+    /// assert!(attributes.rename("datasrc", "data-src"));
+    /// assert!(!attributes.rename("datasrc", "src"));
+    /// assert_eq!(attributes.get("data-src"), Some(Some(&"a.png".into())));
+    /// assert_eq!(attributes.get("datasrc"), None);
+    /// ```
+    pub fn rename<K1, K2>(&mut self, from: K1, to: K2) -> bool
+    where
+        K1: Into<Bytes<'a>>,
+        K2: Into<Bytes<'a>>,
+    {
+        let Some(value) = self.remove(from) else {
+            return false;
+        };
+
+        let to: Bytes = to.into();
+        self.remove(to.clone());
+        self.insert(to, value);
+        true
+    }
+
     /// Returns an iterator `(attribute_key, attribute_value)` over the attributes of this `HTMLTag`
-    pub fn iter(&self) -> impl Iterator<Item = (Cow<str>, Option<Cow<str>>)> + '_ {
-        self.raw
-            .iter()
-            .map(|(k, v)| {
-                let k = k.as_utf8_str();
-                let v = v.as_ref().map(|x| x.as_utf8_str());
+    ///
+    /// Attributes are yielded in the order they were inserted, which is the source order for parsed tags.
+    pub fn iter(&self) -> impl Iterator<Item = (Cow<'_, str>, Option<Cow<'_, str>>)> + '_ {
+        fn special<'s>(
+            key: &'static str,
+            value: &'s Option<Bytes<'_>>,
+        ) -> Option<(Cow<'s, str>, Option<Cow<'s, str>>)> {
+            value
+                .as_ref()
+                .map(|value| (Cow::Borrowed(key), Some(value.as_utf8_str())))
+        }
 
-                (Some(k), v)
-            })
-            .chain([
-                (
-                    self.id.is_some().then(|| Cow::Borrowed("id")),
-                    self.id.as_ref().map(|x| x.as_utf8_str()),
-                ),
-                (
-                    self.class.is_some().then(|| Cow::Borrowed("class")),
-                    self.class.as_ref().map(|x| x.as_utf8_str()),
-                ),
-            ])
-            .flat_map(|(k, v)| k.map(|k| (k, v)))
+        let raw = self.raw.iter().map(|(k, v)| {
+            let k = k.as_utf8_str();
+            let v = v.as_ref().map(|x| x.as_utf8_str());
+
+            (k, v)
+        });
+
+        self.positions
+            .place(raw, special("id", &self.id), special("class", &self.class))
+    }
+
+    /// Returns an iterator `(attribute_key, attribute_value)` over the attributes of this `HTMLTag`, with mutable references to the values
+    ///
+    /// Attributes are visited in the same order as [`Attributes::iter`].
+    /// Removing the value of `id` or `class` by setting it to `None` removes the attribute, because these do not support empty values.
+    ///
+    /// # Example
+    /// ```
+    /// let mut dom = tl::parse(r#"<a href="http://example.com" id="x"></a>"#, Default::default()).unwrap();
+    /// let attributes = dom.nodes_mut()[0].as_tag_mut().unwrap().attributes_mut();
+    ///
+    /// for (key, value) in attributes.iter_mut() {
+    ///     if let (b"href", Some(value)) = (key.as_bytes(), value) {
+    ///         let https = value.as_utf8_str().replacen("http:", "https:", 1);
+    ///         value.set(https).unwrap();
+    ///     }
+    /// }
+    ///
+    /// assert_eq!(attributes.get("href"), Some(Some(&"https://example.com".into())));
+    /// ```
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = (&Bytes<'a>, &mut Option<Bytes<'a>>)> + '_ {
+        const ID: &Bytes<'static> = &Bytes::from_static(b"id");
+        const CLASS: &Bytes<'static> = &Bytes::from_static(b"class");
+
+        let id = self.id.is_some().then_some((ID, &mut self.id));
+        let class = self.class.is_some().then_some((CLASS, &mut self.class));
+        self.positions.place(self.raw.iter_mut(), id, class)
     }
 
     /// Returns the `id` attribute of this HTML tag, if present
@@ -210,6 +489,159 @@ impl<'a> Attributes<'a> {
             .map(str::split_ascii_whitespace)
     }
 
+    /// Returns an iterator over the `(property, value)` declarations of the inline `style` attribute
+    ///
+    /// Declarations are separated by `;`, and the property is separated from the value by the first `:`.
+    /// Both are trimmed. Empty segments and declarations without a colon are skipped.
+    /// This is a simple split and does not understand CSS syntax, so a `;` inside of a string or `url()` ends the declaration.
+    /// If the attribute is not valid UTF-8, no declarations are returned.
+    ///
+    /// # Example
+    /// ```
+    /// let dom = tl::parse(r#"<p style="color: red; ;margin:0 auto;bogus;">"#, Default::default()).unwrap();
+    /// let attributes = dom.nodes()[0].as_tag().unwrap().attributes();
+    ///
+    /// let declarations = attributes.style_declarations().collect::<Vec<_>>();
+    /// assert_eq!(declarations, [("color", "red"), ("margin", "0 auto")]);
+    /// ```
+    pub fn style_declarations(&self) -> impl Iterator<Item = (&str, &str)> + '_ {
+        self.get("style")
+            .flatten()
+            .and_then(Bytes::try_as_utf8_str)
+            .into_iter()
+            .flat_map(|style| style.split(';'))
+            .filter_map(|declaration| {
+                let (property, value) = declaration.split_once(':')?;
+                let property = property.trim();
+                (!property.is_empty()).then(|| (property, value.trim()))
+            })
+    }
+
+    /// Checks whether the given class is in the class names list
+    ///
+    /// This is the same as [`Attributes::is_class_member`].
+    pub fn has_class(&self, class: &str) -> bool {
+        self.is_class_member(class)
+    }
+
+    /// Adds a class to the class names list, creating the `class` attribute if needed.
+    ///
+    /// Returns `false` if the class was already present, in which case nothing is changed.
+    /// The class name must not contain whitespace.
+    ///
+    /// # Example
+    /// ```
+    /// let mut tag = tl::HTMLTag::create("div");
+    /// let attributes = tag.attributes_mut();
+    ///
+    /// assert!(attributes.add_class("a"));
+    /// assert!(attributes.add_class("b"));
+    /// assert!(!attributes.add_class("a"));
+    /// assert_eq!(attributes.class(), Some(&"a b".into()));
+    /// ```
+    pub fn add_class(&mut self, class: &str) -> bool {
+        if self.has_class(class) {
+            return false;
+        }
+
+        let mut value = self
+            .class
+            .as_ref()
+            .map(|c| c.as_bytes().to_vec())
+            .unwrap_or_default();
+
+        if value.last().is_some_and(|c| !c.is_ascii_whitespace()) {
+            value.push(b' ');
+        }
+        value.extend_from_slice(class.as_bytes());
+
+        self.set_class_value(value);
+        true
+    }
+
+    /// Removes a class from the class names list.
+    ///
+    /// Returns `false` if the class was not present, in which case nothing is changed.
+    /// The remaining class names are separated by a single space. The `class` attribute itself is kept, even if it becomes empty.
+    pub fn remove_class(&mut self, class: &str) -> bool {
+        if !self.has_class(class) {
+            return false;
+        }
+
+        let value = self
+            .class_iter()
+            .into_iter()
+            .flatten()
+            .filter(|&member| member != class)
+            .collect::<Vec<_>>()
+            .join(" ");
+
+        self.set_class_value(value.into_bytes());
+        true
+    }
+
+    /// Removes the class if it is present, otherwise adds it.
+    ///
+    /// Returns whether the class is present afterwards.
+    pub fn toggle_class(&mut self, class: &str) -> bool {
+        !self.remove_class(class) && self.add_class(class)
+    }
+
+    fn set_class_value(&mut self, value: Vec<u8>) {
+        let mut class = Bytes::new();
+        class
+            .set(value)
+            .expect("class length must not exceed u32::MAX");
+        self.set_class(Some(class));
+    }
+
+    /// Returns the value of a `data-*` attribute, like [`HTMLElement.dataset`](https://developer.mozilla.org/en-US/docs/Web/API/HTMLElement/dataset) in browsers
+    ///
+    /// The key is given without the `data-` prefix. Since attribute names are lowercased when parsing, the key needs to be lowercase.
+    /// As with [`Attributes::get()`], the inner Option is set to None if the attribute exists but has no value.
+    ///
+    /// # Example
+    /// ```
+    /// let dom = tl::parse(r#"<div data-user-id="42" DATA-hidden></div>"#, Default::default()).unwrap();
+    /// let attributes = dom.nodes()[0].as_tag().unwrap().attributes();
+    ///
+    /// assert_eq!(attributes.data("user-id"), Some(Some(&"42".into())));
+    /// assert_eq!(attributes.data("hidden"), Some(None));
+    /// assert_eq!(attributes.data("USER-ID"), None);
+    /// ```
+    pub fn data(&self, key: &str) -> Option<Option<&Bytes<'a>>> {
+        self.raw
+            .iter()
+            .find(|(k, _)| strip_data_prefix(k.as_bytes()) == Some(key.as_bytes()))
+            .map(|(_, v)| v.as_ref())
+    }
+
+    /// Returns an iterator over all `data-*` attributes, with the `data-` prefix stripped from the keys
+    pub fn data_iter(&self) -> impl Iterator<Item = (Cow<'_, str>, Option<Cow<'_, str>>)> + '_ {
+        self.raw.iter().filter_map(|(k, v)| {
+            let key = strip_data_prefix(k.as_bytes())?;
+            Some((
+                String::from_utf8_lossy(key),
+                v.as_ref().map(Bytes::as_utf8_str),
+            ))
+        })
+    }
+
+    /// Copies all attributes into owned `Bytes`
+    pub(crate) fn to_static(&self) -> Attributes<'static> {
+        let mut raw = InlineHashMap::new();
+        for (key, value) in self.raw.iter() {
+            raw.insert(key.to_static(), value.as_ref().map(Bytes::to_static));
+        }
+
+        Attributes {
+            raw,
+            id: self.id.as_ref().map(Bytes::to_static),
+            class: self.class.as_ref().map(Bytes::to_static),
+            positions: self.positions,
+        }
+    }
+
     /// Returns the underlying raw map for attributes
     ///
     /// ## A note on stability
@@ -229,6 +661,10 @@ pub struct HTMLTag<'a> {
     pub(crate) _attributes: Attributes<'a>,
     pub(crate) _children: RawChildren,
     pub(crate) _raw: Bytes<'a>,
+    /// The id of the name of this tag in the interning table of the parser, if tag names are interned
+    pub(crate) _name_id: Option<NonZeroU32>,
+    /// Whether the name, attributes or children of this tag may have been changed through a mutable accessor
+    pub(crate) _modified: bool,
 }
 
 impl<'a> HTMLTag<'a> {
@@ -245,17 +681,150 @@ impl<'a> HTMLTag<'a> {
             _attributes: attr,
             _children: children,
             _raw: raw,
+            _name_id: None,
+            _modified: false,
         }
     }
 
+    /// Creates a new HTML tag with the given name, without any attributes or children
+    ///
+    /// The tag needs to be added to a DOM using [`Parser::push_node`] before it can be inserted into the tree.
+    /// Since it does not come from the source string, [`HTMLTag::raw`] is empty for created tags.
+    ///
+    /// # Example
+    /// ```
+    /// let mut tag = tl::HTMLTag::create("a");
+    /// tag.attributes_mut().insert("href", Some("/"));
+    /// assert_eq!(tag.name(), "a");
+    /// ```
+    pub fn create(name: &str) -> HTMLTag<'static> {
+        let mut bytes = Bytes::new();
+        bytes
+            .set(name)
+            .expect("tag name length must not exceed u32::MAX");
+
+        HTMLTag::new(bytes, Attributes::new(), InlineVec::new(), Bytes::new())
+    }
+
     /// Returns a wrapper around the children of this HTML tag
     #[inline]
     pub fn children(&self) -> Children<'a, '_> {
         Children(self)
     }
 
+    /// Returns an iterator over the direct children of this HTML tag that are elements, skipping text, comment and CDATA nodes.
+    ///
+    /// The position of a handle in this iterator is its index among its element siblings,
+    /// which is not affected by whitespace between tags.
+    ///
+    /// # Example
+    /// ```
+    /// let dom = tl::parse("<ul>\n  <li>a</li>\n  <li>b</li>\n</ul>", Default::default()).unwrap();
+    /// let parser = dom.parser();
+    /// let ul = dom.nodes()[0].as_tag().unwrap();
+    ///
+    /// assert_eq!(ul.children().top().len(), 5);
+    /// assert_eq!(ul.element_children(parser).count(), 2);
+    /// ```
+    pub fn element_children<'p>(
+        &'p self,
+        parser: &'p Parser<'a>,
+    ) -> impl Iterator<Item = NodeHandle> + 'p {
+        self._children
+            .as_slice()
+            .iter()
+            .copied()
+            .filter(move |handle| {
+                handle
+                    .get(parser)
+                    .is_some_and(|node| node.as_tag().is_some())
+            })
+    }
+
+    /// Counts the elements among all descendants of this HTML tag, not including the tag itself.
+    ///
+    /// Like [`HTMLTag::descendants`], this follows the children of each node, so it is also correct for trees that have been modified.
+    ///
+    /// # Example
+    /// ```
+    /// let dom = tl::parse("<div>a<p>b<b>c</b></p><br></div>", Default::default()).unwrap();
+    /// let div = dom.nodes()[0].as_tag().unwrap();
+    /// assert_eq!(div.descendant_element_count(dom.parser()), 3);
+    /// ```
+    pub fn descendant_element_count(&self, parser: &Parser<'a>) -> usize {
+        self.descendants(parser)
+            .filter(|handle| {
+                handle
+                    .get(parser)
+                    .is_some_and(|node| node.as_tag().is_some())
+            })
+            .count()
+    }
+
+    /// Returns an iterator over all descendants of this HTML tag in document order, not including the tag itself.
+    ///
+    /// Unlike [`Children::all`], this follows the children of each node instead of relying on
+    /// descendants being stored contiguously, so it also works for trees that have been modified.
+    ///
+    /// # Example
+    /// ```
+    /// let dom = tl::parse("<div><p>a<b>b</b></p>c</div>", Default::default()).unwrap();
+    /// let parser = dom.parser();
+    /// let div = dom.nodes()[0].as_tag().unwrap();
+    /// let text = div
+    ///     .descendants(parser)
+    ///     .filter_map(|handle| handle.get(parser).unwrap().as_raw())
+    ///     .map(|raw| raw.as_utf8_str())
+    ///     .collect::<String>();
+    /// assert_eq!(text, "abc");
+    /// ```
+    pub fn descendants<'p>(
+        &'p self,
+        parser: &'p Parser<'a>,
+    ) -> impl Iterator<Item = NodeHandle> + 'p {
+        self.descendants_with_depth(parser)
+            .map(|(handle, _)| handle)
+    }
+
+    /// Returns an iterator over all descendants of this HTML tag in document order, along with their depth.
+    ///
+    /// Direct children of this tag have a depth of 1, their children a depth of 2, and so on.
+    ///
+    /// # Example
+    /// ```
+    /// let dom = tl::parse("<ul><li><b>x</b></li></ul>", Default::default()).unwrap();
+    /// let parser = dom.parser();
+    /// let ul = dom.nodes()[0].as_tag().unwrap();
+    /// let depths = ul.descendants_with_depth(parser).map(|(_, depth)| depth).collect::<Vec<_>>();
+    /// assert_eq!(depths, [1, 2, 3]);
+    /// ```
+    pub fn descendants_with_depth<'p>(
+        &'p self,
+        parser: &'p Parser<'a>,
+    ) -> impl Iterator<Item = (NodeHandle, usize)> + 'p {
+        let mut stack = vec![self._children.as_slice().iter()];
+
+        std::iter::from_fn(move || loop {
+            let depth = stack.len();
+            let handle = match stack.last_mut()?.next() {
+                Some(&handle) => handle,
+                None => {
+                    stack.pop();
+                    continue;
+                }
+            };
+
+            if let Some(tag) = handle.get(parser).and_then(Node::as_tag) {
+                stack.push(tag._children.as_slice().iter());
+            }
+
+            return Some((handle, depth));
+        })
+    }
+
     /// Returns a mutable wrapper around the children of this HTML tag.
     pub fn children_mut(&mut self) -> ChildrenMut<'a, '_> {
+        self._modified = true;
         ChildrenMut(self)
     }
 
@@ -268,6 +837,8 @@ impl<'a> HTMLTag<'a> {
     /// Returns a mutable reference to the name of this HTML tag
     #[inline]
     pub fn name_mut(&mut self) -> &mut Bytes<'a> {
+        self._modified = true;
+        self._name_id = None;
         &mut self._name
     }
 
@@ -280,74 +851,309 @@ impl<'a> HTMLTag<'a> {
     /// Returns a mutable reference to the attributes of this HTML tag
     #[inline]
     pub fn attributes_mut(&mut self) -> &mut Attributes<'a> {
+        self._modified = true;
         &mut self._attributes
     }
 
+    /// Sets the value of an attribute, adding it if it does not exist yet
+    ///
+    /// This is a shorthand for [`Attributes::insert`] with a value.
+    /// Use [`HTMLTag::set_attribute_flag`] to add an attribute without a value.
+    ///
+    /// Equivalent to [Element#setAttribute](https://developer.mozilla.org/en-US/docs/Web/API/Element/setAttribute) in browsers.
+    ///
+    /// # Example
+    /// ```
+    /// let mut dom = tl::parse("<a>link</a>", Default::default()).unwrap();
+    /// let a = dom.nodes_mut()[0].as_tag_mut().unwrap();
+    /// a.set_attribute("href", "/");
+    /// a.set_attribute("id", "home");
+    /// assert_eq!(dom.outer_html(), r#"<a href="/" id="home">link</a>"#);
+    /// ```
+    pub fn set_attribute<K, V>(&mut self, name: K, value: V)
+    where
+        K: Into<Bytes<'a>>,
+        V: Into<Bytes<'a>>,
+    {
+        self.attributes_mut().insert(name, Some(value));
+    }
+
+    /// Adds a boolean attribute without a value, such as `disabled`, replacing the value of an existing attribute
+    ///
+    /// Since `id` and `class` cannot exist without a value, this removes them instead.
+    ///
+    /// # Example
+    /// ```
+    /// let mut dom = tl::parse(r#"<input disabled="false">"#, Default::default()).unwrap();
+    /// let input = dom.nodes_mut()[0].as_tag_mut().unwrap();
+    /// input.set_attribute_flag("disabled");
+    /// input.set_attribute_flag("required");
+    /// assert_eq!(dom.outer_html(), "<input disabled required>");
+    /// ```
+    pub fn set_attribute_flag<K>(&mut self, name: K)
+    where
+        K: Into<Bytes<'a>>,
+    {
+        self.attributes_mut().insert(name, None::<Bytes<'a>>);
+    }
+
+    /// Removes an attribute and returns its value, if it existed
+    ///
+    /// This is a shorthand for [`Attributes::remove`].
+    ///
+    /// Equivalent to [Element#removeAttribute](https://developer.mozilla.org/en-US/docs/Web/API/Element/removeAttribute) in browsers.
+    ///
+    /// # Example
+    /// ```
+    /// let mut dom = tl::parse(r#"<a href="/" target="_blank">link</a>"#, Default::default()).unwrap();
+    /// let a = dom.nodes_mut()[0].as_tag_mut().unwrap();
+    /// assert_eq!(a.remove_attribute("target"), Some(Some("_blank".into())));
+    /// assert_eq!(a.remove_attribute("target"), None);
+    /// assert_eq!(dom.outer_html(), r#"<a href="/">link</a>"#);
+    /// ```
+    pub fn remove_attribute<K>(&mut self, name: K) -> Option<Option<Bytes<'a>>>
+    where
+        K: Into<Bytes<'a>>,
+    {
+        self.attributes_mut().remove(name)
+    }
+
     /// Returns the contained markup
     ///
+    /// Text and attribute values are written as they appear in the source.
+    /// The only exception are double quotes in attribute values, which are written as `&quot;` so that the output stays valid HTML.
+    /// Use [`HTMLTag::outer_html_escaped`] to also escape text and attribute values.
+    ///
     /// ## Limitations
-    /// - The order of tag attributes is not guaranteed
+    /// - All attributes are written in source order, but only the first of duplicate attributes is kept
     /// - Spaces within the tag are not preserved (i.e. `<img      src="">` may become `<img src="">`)
     ///
     /// Equivalent to [Element#outerHTML](https://developer.mozilla.org/en-US/docs/Web/API/Element/outerHTML) in browsers.
     pub fn outer_html<'p>(&'p self, parser: &'p Parser<'a>) -> String {
-        let tag_name = self._name.as_utf8_str();
-        let is_void_element = HTML_VOID_ELEMENTS.contains(&tag_name.as_ref());
-        let mut outer_html = format!("<{}", &tag_name);
+        let mut outer_html = String::new();
+        self.outer_html_to(parser, &mut outer_html)
+            .expect("writing to a String cannot fail");
+        outer_html
+    }
+
+    /// Writes the contained markup to the given writer
+    ///
+    /// This produces the same output as [`HTMLTag::outer_html`], but does not allocate a string for every element.
+    pub fn outer_html_to<W: fmt::Write>(&self, parser: &Parser<'a>, out: &mut W) -> fmt::Result {
+        self.write_outer_html(parser, out, false)
+    }
+
+    /// Returns the contained markup, borrowing it from the source string if this tag is unmodified
+    ///
+    /// If neither this tag nor any of its descendants were changed after parsing, this returns the source
+    /// of the tag as it was written (the same as [`HTMLTag::raw`]) without allocating.
+    /// Otherwise, the tag is serialized with [`HTMLTag::outer_html`].
+    ///
+    /// Note that the source is returned verbatim, so it may be formatted differently than [`HTMLTag::outer_html`],
+    /// e.g. it keeps the original attribute order and whitespace.
+    ///
+    /// # Example
+    /// ```
+    /// use std::borrow::Cow;
+    ///
+    /// let mut dom = tl::parse("<div><p  class='a'>Hello</p></div>", Default::default()).unwrap();
+    /// let div = dom.query_selector_first("div").unwrap();
+    /// let html = div.get(dom.parser()).unwrap().as_tag().unwrap().outer_html_cow(dom.parser());
+    /// assert!(matches!(html, Cow::Borrowed("<div><p  class='a'>Hello</p></div>")));
+    ///
+    /// let p = dom.query_selector_first("p").unwrap();
+    /// p.get_mut(dom.parser_mut()).unwrap().as_tag_mut().unwrap().attributes_mut().remove("class");
+    /// let html = div.get(dom.parser()).unwrap().as_tag().unwrap().outer_html_cow(dom.parser());
+    /// assert!(matches!(html, Cow::Owned(ref html) if html == "<div><p>Hello</p></div>"));
+    /// ```
+    pub fn outer_html_cow<'p>(&'p self, parser: &'p Parser<'a>) -> Cow<'p, str> {
+        match self.raw().try_as_utf8_str() {
+            Some(source) if self.is_unmodified(parser) => Cow::Borrowed(source),
+            _ => Cow::Owned(self.outer_html(parser)),
+        }
+    }
+
+    /// Checks whether this tag and its descendants still correspond to the source they were parsed from
+    ///
+    /// Text that was changed is owned, and text that was replaced with a borrowed string from somewhere else
+    /// points outside of the source of this tag.
+    fn is_unmodified(&self, parser: &Parser<'a>) -> bool {
+        let Some(raw) = self._raw.as_bytes_borrowed().filter(|raw| !raw.is_empty()) else {
+            return false;
+        };
+        let range = raw.as_ptr_range();
+        let is_within = |bytes: &Bytes<'a>| {
+            bytes.as_bytes_borrowed().is_some_and(|bytes| {
+                let inner = bytes.as_ptr_range();
+                range.start <= inner.start && inner.end <= range.end
+            })
+        };
+
+        !self._modified
+            && self
+                .descendants(parser)
+                .all(|handle| match handle.get(parser) {
+                    Some(Node::Tag(tag)) => {
+                        !tag._modified && !tag._raw.as_bytes().is_empty() && is_within(&tag._raw)
+                    }
+                    Some(Node::Raw(bytes) | Node::Comment(bytes) | Node::CData(bytes)) => {
+                        is_within(bytes)
+                    }
+                    None => false,
+                })
+    }
+
+    /// Returns the contained markup, with special characters in text and attribute values escaped
+    ///
+    /// Text and attribute values are normalized by decoding any character references and escaping the result again:
+    /// `&`, `<` and `>` in text, and `&` and `"` in attribute values.
+    /// Text that was valid HTML stays equivalent, while text that was set programmatically (e.g. `a < b`) is escaped.
+    /// The contents of `<script>` and `<style>` tags, comments and CDATA sections are written as they are.
+    ///
+    /// # Example
+    /// ```
+    /// let dom = tl::parse(r#"<a title='he said "hi"'>Fish &amp; Chips</a>"#, Default::default()).unwrap();
+    /// let tag = dom.nodes()[0].as_tag().unwrap();
+    /// assert_eq!(
+    ///     tag.outer_html_escaped(dom.parser()),
+    ///     r#"<a title="he said &quot;hi&quot;">Fish &amp; Chips</a>"#
+    /// );
+    /// ```
+    pub fn outer_html_escaped(&self, parser: &Parser<'a>) -> String {
+        let mut outer_html = String::new();
+        self.write_outer_html(parser, &mut outer_html, true)
+            .expect("writing to a String cannot fail");
+        outer_html
+    }
+
+    /// Checks whether this is a void element, which has neither content nor a closing tag
+    ///
+    /// This includes the tags that were configured as void with [`ParserOptions::extra_void_tags`](crate::ParserOptions::extra_void_tags).
+    pub(crate) fn is_void_element(&self, parser: &Parser<'a>) -> bool {
+        parser.options.is_void_tag(self._name.as_bytes())
+    }
 
-        #[inline]
-        fn write_attribute(dest: &mut String, k: Cow<str>, v: Option<Cow<str>>) {
-            dest.push(' ');
+    /// Writes the start tag of this element, including its attributes: `<a href="/">`
+    pub(crate) fn write_start_tag<W: fmt::Write>(&self, out: &mut W, escape: bool) -> fmt::Result {
+        out.write_char('<')?;
+        out.write_str(&self._name.as_utf8_str())?;
 
-            dest.push_str(&k);
+        for (k, v) in self.attributes().iter() {
+            out.write_char(' ')?;
+            out.write_str(&k)?;
 
             if let Some(value) = v {
-                dest.push_str("=\"");
-                dest.push_str(&value);
-                dest.push('"');
+                out.write_str("=\"")?;
+                if escape {
+                    entities::escape_to(
+                        out,
+                        &entities::decode_attribute(&value),
+                        entities::ATTRIBUTE,
+                    )?;
+                } else {
+                    entities::escape_to(out, &value, entities::QUOTE)?;
+                }
+                out.write_char('"')?;
             }
         }
 
-        let attr = self.attributes();
-
-        for (k, v) in attr.iter() {
-            write_attribute(&mut outer_html, k, v);
-        }
+        out.write_char('>')
+    }
 
-        outer_html.push('>');
+    fn write_outer_html<W: fmt::Write>(
+        &self,
+        parser: &Parser<'a>,
+        out: &mut W,
+        escape: bool,
+    ) -> fmt::Result {
+        self.write_start_tag(out, escape)?;
 
         // void elements have neither content nor a closing tag.
-        if is_void_element {
-            return outer_html;
+        if self.is_void_element(parser) {
+            return Ok(());
         }
 
-        // TODO(y21): More of an idea than a TODO, but a potential perf improvement
-        // could be having some kind of internal inner_html function that takes a &mut String
-        // and simply writes to it instead of returning a newly allocated string for every element
-        // and appending it
-        outer_html.push_str(&self.inner_html(parser));
-
-        outer_html.push_str("</");
-        outer_html.push_str(&self._name.as_utf8_str());
-        outer_html.push('>');
-
-        outer_html
+        self.write_inner_html(parser, out, self.escapes_children(escape))?;
+        self.write_end_tag(out)
     }
 
     /// Returns the contained markup
     ///
+    /// See [`HTMLTag::outer_html`] for how text and attribute values are written.
+    ///
     /// ## Limitations
-    /// - The order of tag attributes is not guaranteed
+    /// - All attributes are written in source order, but only the first of duplicate attributes is kept
     /// - Spaces within the tag are not preserved (i.e. `<img      src="">` may become `<img src="">`)
     ///
     /// Equivalent to [Element#innerHTML](https://developer.mozilla.org/en-US/docs/Web/API/Element/innerHTML) in browsers.
     pub fn inner_html<'p>(&'p self, parser: &'p Parser<'a>) -> String {
-        self.children()
-            .top()
-            .iter()
-            .map(|handle| handle.get(parser).unwrap())
-            .map(|node| node.outer_html(parser))
-            .collect::<String>()
+        let mut inner_html = String::new();
+        self.inner_html_to(parser, &mut inner_html)
+            .expect("writing to a String cannot fail");
+        inner_html
+    }
+
+    /// Writes the markup of the children of this tag to the given writer
+    ///
+    /// This produces the same output as [`HTMLTag::inner_html`], but does not allocate a string for every element.
+    pub fn inner_html_to<W: fmt::Write>(&self, parser: &Parser<'a>, out: &mut W) -> fmt::Result {
+        self.write_inner_html(parser, out, false)
+    }
+
+    /// Returns the markup of the children of this tag, with special characters in text and attribute values escaped
+    ///
+    /// See [`HTMLTag::outer_html_escaped`] for details.
+    pub fn inner_html_escaped(&self, parser: &Parser<'a>) -> String {
+        let mut inner_html = String::new();
+        self.write_inner_html(parser, &mut inner_html, true)
+            .expect("writing to a String cannot fail");
+        inner_html
+    }
+
+    /// Writes the children of this tag
+    ///
+    /// Descendants are visited using a heap allocated stack instead of recursion,
+    /// so that deeply nested trees cannot overflow the call stack.
+    fn write_inner_html<W: fmt::Write>(
+        &self,
+        parser: &Parser<'a>,
+        out: &mut W,
+        escape: bool,
+    ) -> fmt::Result {
+        let mut stack = Vec::new();
+        push_children(&mut stack, self, parser, escape);
+
+        while let Some(step) = stack.pop() {
+            match step {
+                SerializeStep::Node(Node::Tag(tag), escape) => {
+                    tag.write_start_tag(out, escape)?;
+
+                    // void elements have neither content nor a closing tag.
+                    if tag.is_void_element(parser) {
+                        continue;
+                    }
+
+                    stack.push(SerializeStep::EndTag(tag));
+                    push_children(&mut stack, tag, parser, tag.escapes_children(escape));
+                }
+                SerializeStep::Node(node, escape) => node.write_outer_html(parser, out, escape)?,
+                SerializeStep::EndTag(tag) => tag.write_end_tag(out)?,
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Returns whether the children of this tag are escaped, given whether the tag itself is escaped
+    fn escapes_children(&self, escape: bool) -> bool {
+        // the contents of these tags are not parsed as HTML by browsers, so escaping them would change their meaning
+        escape && !self.is_raw_text_element()
+    }
+
+    fn write_end_tag<W: fmt::Write>(&self, out: &mut W) -> fmt::Result {
+        out.write_str("</")?;
+        out.write_str(&self._name.as_utf8_str())?;
+        out.write_char('>')
     }
 
     /// Returns the raw HTML of this tag.
@@ -384,7 +1190,32 @@ impl<'a> HTMLTag<'a> {
     /// Equivalent to [Element#innerText](https://developer.mozilla.org/en-US/docs/Web/API/HTMLElement/innerText) in browsers.
     /// This function may not allocate memory for a new string as it can just return the part of the tag that doesn't have markup.
     /// For tags that *do* have more than one subnode, this will allocate memory
+    ///
+    /// Character references such as `&amp;` are returned as they appear in the source.
+    /// Use [`HTMLTag::inner_text_decoded`] to decode them.
     pub fn inner_text<'p>(&self, parser: &'p Parser<'a>) -> Cow<'p, str> {
+        self.inner_text_impl(parser, false)
+    }
+
+    /// Returns the contained text of this element, excluding any markup, with HTML character references decoded.
+    ///
+    /// Named references (`&amp;`, `&lt;`, `&nbsp;`, ...), decimal references (`&#169;`) and hexadecimal references (`&#x2764;`) are decoded.
+    /// Unknown or malformed references are left as they are.
+    /// Like browsers, a few common legacy references (such as `&amp` or `&copy`) are also recognized without a semicolon.
+    ///
+    /// This does not allocate if the text consists of a single text node without any references.
+    ///
+    /// # Example
+    /// ```
+    /// let dom = tl::parse("<p>Fish &amp; Chips &#169; &#x2764;</p>", Default::default()).unwrap();
+    /// let tag = dom.nodes()[0].as_tag().unwrap();
+    /// assert_eq!(tag.inner_text_decoded(dom.parser()), "Fish & Chips \u{a9} \u{2764}");
+    /// ```
+    pub fn inner_text_decoded<'p>(&self, parser: &'p Parser<'a>) -> Cow<'p, str> {
+        self.inner_text_impl(parser, true)
+    }
+
+    fn inner_text_impl<'p>(&self, parser: &'p Parser<'a>, decode: bool) -> Cow<'p, str> {
         let len = self._children.len();
 
         if len == 0 {
@@ -396,27 +1227,98 @@ impl<'a> HTMLTag<'a> {
 
         if len == 1 {
             match &first {
-                Node::Tag(t) => return t.inner_text(parser),
-                Node::Raw(e) => return e.as_utf8_str(),
+                Node::Tag(t) => return t.inner_text_impl(parser, decode),
+                Node::Raw(e) => return raw_text(e, decode),
                 Node::Comment(_) => return Cow::Borrowed(""),
+                Node::CData(c) => return c.as_utf8_str(),
             }
         }
 
-        // If there are >1 nodes, we need to allocate a new string and push each inner_text in it
-        // TODO: check if String::with_capacity() is worth it
-        let mut s = String::from(first.inner_text(parser));
+        // If there are >1 nodes, we need to allocate a new string and push the text of all descendants in it.
+        // Decoding character references only makes the text shorter, so the length of the source text is a good estimate.
+        let mut s = String::with_capacity(self.text_len(parser));
+        self.push_inner_text(parser, decode, &mut s);
+        Cow::Owned(s)
+    }
 
-        for &id in self._children.iter().skip(1) {
+    fn push_inner_text(&self, parser: &Parser<'a>, decode: bool, out: &mut String) {
+        for &id in self._children.iter() {
             let node = id.get(parser).unwrap();
 
             match &node {
-                Node::Tag(t) => s.push_str(&t.inner_text(parser)),
-                Node::Raw(e) => s.push_str(&e.as_utf8_str()),
+                Node::Tag(t) => t.push_inner_text(parser, decode, out),
+                Node::Raw(e) => out.push_str(&raw_text(e, decode)),
                 Node::Comment(_) => { /* no op */ }
+                Node::CData(c) => out.push_str(&c.as_utf8_str()),
             }
         }
+    }
 
-        Cow::Owned(s)
+    /// Returns the combined length of the text and CDATA nodes in this tag, as they appear in the source
+    fn text_len(&self, parser: &Parser<'a>) -> usize {
+        self._children
+            .iter()
+            .map(|child| match child.get(parser) {
+                Some(Node::Tag(tag)) => tag.text_len(parser),
+                Some(Node::Raw(text)) => text.as_bytes().len(),
+                Some(Node::CData(cdata)) => cdata.as_bytes().len(),
+                Some(Node::Comment(_)) | None => 0,
+            })
+            .sum()
+    }
+
+    /// Returns the text of all descendant text nodes, with HTML character references decoded.
+    /// Equivalent to [Node#textContent](https://developer.mozilla.org/en-US/docs/Web/API/Node/textContent) in browsers.
+    ///
+    /// This differs from [`HTMLTag::inner_text_decoded`] in the same way that `textContent` differs from `innerText`:
+    /// the contents of `<script>` and `<style>` tags are raw text, so character references in them are not decoded.
+    /// Comments are skipped, and CDATA sections are included as they are.
+    ///
+    /// # Example
+    /// ```
+    /// let dom = tl::parse("<div>Fish &amp; Chips<style>a::after { content: '&amp;' }</style></div>", Default::default()).unwrap();
+    /// let div = dom.nodes()[0].as_tag().unwrap();
+    /// assert_eq!(div.text_content(dom.parser()), "Fish & Chipsa::after { content: '&amp;' }");
+    /// ```
+    pub fn text_content<'p>(&self, parser: &'p Parser<'a>) -> Cow<'p, str> {
+        let decode = !self.is_raw_text_element();
+
+        // A single text node can usually be returned without allocating
+        if let [child] = self._children.as_slice() {
+            match child.get(parser) {
+                Some(Node::Raw(text)) => return raw_text(text, decode),
+                Some(Node::CData(cdata)) => return cdata.as_utf8_str(),
+                _ => {}
+            }
+        }
+
+        let mut text = String::with_capacity(self.text_len(parser));
+        self.push_text_content(parser, &mut text);
+        Cow::Owned(text)
+    }
+
+    fn push_text_content(&self, parser: &Parser<'a>, out: &mut String) {
+        let decode = !self.is_raw_text_element();
+
+        for child in self._children.iter() {
+            match child.get(parser) {
+                Some(Node::Tag(tag)) => tag.push_text_content(parser, out),
+                Some(Node::Raw(text)) => out.push_str(&raw_text(text, decode)),
+                Some(Node::CData(cdata)) => out.push_str(&cdata.as_utf8_str()),
+                Some(Node::Comment(_)) | None => {}
+            }
+        }
+    }
+
+    /// Checks whether the contents of this tag are raw text, which browsers do not parse as HTML
+    fn is_raw_text_element(&self) -> bool {
+        util::is_tag_in(self._name.as_bytes(), constants::RAW_TEXT_TAGS)
+    }
+
+    /// Checks whether whitespace in the contents of this tag is significant, e.g. in `<pre>` or `<script>`
+    pub(crate) fn preserves_whitespace(&self) -> bool {
+        self.is_raw_text_element()
+            || util::is_tag_in(self._name.as_bytes(), constants::PREFORMATTED_TAGS)
     }
 
     /// Tries to parse the query selector and returns an iterator over elements that match the given query selector.
@@ -466,6 +1368,53 @@ impl<'a> HTMLTag<'a> {
         Some(iter)
     }
 
+    /// Tries to parse the query selector and returns the first element in this subtree that matches it.
+    ///
+    /// `None` is returned both if the selector is invalid and if no element matches it.
+    /// Use [`HTMLTag::query_selector`] if you need to tell these cases apart.
+    pub fn query_selector_first(&self, parser: &Parser<'a>, selector: &str) -> Option<NodeHandle> {
+        self.query_selector(parser, selector)?.next()
+    }
+
+    /// Checks whether this tag matches the given, already parsed query selector
+    ///
+    /// Parse the selector once with [`crate::parse_query_selector`] and reuse it to check many tags.
+    /// Tags do not know where they are stored, so `handle` must be the handle of this tag in the given parser,
+    /// which is needed to find its parent and siblings.
+    /// `false` is returned if it points to any other node.
+    ///
+    /// Selectors with combinators like `div > p` look up the parent of this tag, which walks the whole document on every call.
+    /// To check all elements against such a selector, use [`VDom::query_selector_parsed`](crate::VDom::query_selector_parsed).
+    ///
+    /// # Example
+    /// ```
+    /// let dom = tl::parse(r#"<p class="a">x</p><p>y</p><a href="/">z</a>"#, Default::default()).unwrap();
+    /// let parser = dom.parser();
+    /// let selector = tl::parse_query_selector("p.a, a[href]").unwrap();
+    ///
+    /// let matches = dom
+    ///     .nodes()
+    ///     .iter()
+    ///     .enumerate()
+    ///     .filter_map(|(id, node)| Some((tl::NodeHandle::new(id as u32), node.as_tag()?)))
+    ///     .filter(|(handle, tag)| tag.matches_selector(parser, *handle, &selector))
+    ///     .count();
+    /// assert_eq!(matches, 2);
+    /// ```
+    pub fn matches_selector(
+        &self,
+        parser: &Parser<'a>,
+        handle: NodeHandle,
+        selector: &Selector<'_>,
+    ) -> bool {
+        let is_self = handle
+            .get(parser)
+            .and_then(Node::as_tag)
+            .is_some_and(|tag| std::ptr::eq(tag, self));
+
+        is_self && handle.matches_selector(parser, selector)
+    }
+
     /// Calls the given closure with each tag as parameter
     ///
     /// The closure must return a boolean, indicating whether it should stop iterating
@@ -483,6 +1432,43 @@ impl<'a> HTMLTag<'a> {
         }
         None
     }
+
+    /// Returns all descendants of this tag for which the given closure returns `true`, in document order
+    ///
+    /// Unlike [`HTMLTag::find_node`], this searches the whole subtree and does not stop at the first match.
+    ///
+    /// # Example
+    /// ```
+    /// let dom = tl::parse(r#"<ul><li data-x>a</li><li>b<i data-x>c</i></li></ul>"#, Default::default()).unwrap();
+    /// let parser = dom.parser();
+    /// let ul = dom.nodes()[0].as_tag().unwrap();
+    /// let found = ul.find_nodes(parser, &mut |node| {
+    ///     node.as_tag().is_some_and(|tag| tag.attributes().contains("data-x"))
+    /// });
+    /// assert_eq!(found.len(), 2);
+    /// assert_eq!(found[1].get(parser).unwrap().inner_text(parser), "c");
+    /// ```
+    pub fn find_nodes<F>(&self, parser: &Parser<'a>, f: &mut F) -> Vec<NodeHandle>
+    where
+        F: FnMut(&Node<'a>) -> bool,
+    {
+        self.find_nodes_iter(parser, f).collect()
+    }
+
+    /// Returns an iterator over all descendants of this tag for which the given closure returns `true`, in document order
+    ///
+    /// This is the lazy version of [`HTMLTag::find_nodes`].
+    pub fn find_nodes_iter<'p, F>(
+        &'p self,
+        parser: &'p Parser<'a>,
+        mut f: F,
+    ) -> impl Iterator<Item = NodeHandle> + use<'a, 'p, F>
+    where
+        F: FnMut(&Node<'a>) -> bool + 'p,
+    {
+        self.descendants(parser)
+            .filter(move |handle| handle.get(parser).is_some_and(&mut f))
+    }
 }
 
 /// A thin wrapper around the children of [`HTMLTag`]
@@ -517,6 +1503,9 @@ impl<'a, 'b> Children<'a, 'b> {
     /// // It does **not** contain the inner span tags
     /// assert_eq!(a.children().top().len(), 3);
     /// ```
+    ///
+    /// The whitespace text nodes can be left out with [`ParserOptions::skip_whitespace_text_nodes`](crate::ParserOptions::skip_whitespace_text_nodes),
+    /// in which case the slice would only contain div#b.
     #[inline]
     pub fn top(&self) -> &RawChildren {
         &self.0._children
@@ -572,6 +1561,44 @@ impl<'a, 'b> Children<'a, 'b> {
             .map(|(start, end)| &parser.tags[start as usize..=end as usize])
             .unwrap_or(&[])
     }
+
+    /// Returns an iterator over the direct children of this tag that are elements, skipping text, comment and CDATA nodes.
+    ///
+    /// This is the same as [`HTMLTag::element_children`].
+    ///
+    /// # Example
+    /// ```
+    /// let dom = tl::parse("<ul>\n  <li>a</li>\n  <li>b</li>\n</ul>", Default::default()).unwrap();
+    /// let parser = dom.parser();
+    /// let ul = dom.nodes()[0].as_tag().unwrap();
+    ///
+    /// assert_eq!(ul.children().top().len(), 5);
+    /// assert_eq!(ul.children().elements(parser).count(), 2);
+    /// ```
+    pub fn elements(
+        &self,
+        parser: &'b Parser<'a>,
+    ) -> impl Iterator<Item = NodeHandle> + use<'a, 'b> {
+        self.0.element_children(parser)
+    }
+
+    /// Returns the `n`th direct child of this tag that is an element, starting at 0.
+    ///
+    /// Text, comment and CDATA nodes are not counted, so whitespace between tags does not change the result.
+    ///
+    /// # Example
+    /// ```
+    /// let dom = tl::parse("<ul>\n  <li>a</li>\n  <li>b</li>\n</ul>", Default::default()).unwrap();
+    /// let parser = dom.parser();
+    /// let ul = dom.nodes()[0].as_tag().unwrap();
+    ///
+    /// let second = ul.children().nth_element(parser, 1).unwrap();
+    /// assert_eq!(second.get(parser).unwrap().inner_text(parser), "b");
+    /// assert!(ul.children().nth_element(parser, 2).is_none());
+    /// ```
+    pub fn nth_element(&self, parser: &Parser<'a>, n: usize) -> Option<NodeHandle> {
+        self.0.element_children(parser).nth(n)
+    }
 }
 
 /// A thin mutable wrapper around the children of [`HTMLTag`]
@@ -586,6 +1613,88 @@ impl<'a, 'b> ChildrenMut<'a, 'b> {
     pub fn top_mut(&mut self) -> &mut RawChildren {
         &mut self.0._children
     }
+
+    /// Appends a node to the end of the children of this tag.
+    ///
+    /// The handle must have been obtained from the same parser that owns this tag, e.g. through [`Parser::push_node`].
+    /// Appending a node that is already part of the tree does not detach it from its current parent.
+    pub fn append(&mut self, handle: NodeHandle) {
+        self.0._children.push(handle);
+    }
+
+    /// Inserts a node directly before the given child of this tag.
+    ///
+    /// Returns `false` and does nothing if `reference` is not a direct child of this tag.
+    ///
+    /// # Example
+    /// ```
+    /// let mut dom = tl::parse("<div><p>a</p><p>b</p></div>", Default::default()).unwrap();
+    /// let second = dom.query_selector("p").unwrap().nth(1).unwrap();
+    /// let parser = dom.parser_mut();
+    /// let hr = parser.push_node(tl::Node::Tag(tl::HTMLTag::create("hr")));
+    ///
+    /// let div = tl::NodeHandle::new(0).get_mut(parser).unwrap().as_tag_mut().unwrap();
+    /// assert!(div.children_mut().insert_before(second, hr));
+    /// assert_eq!(dom.outer_html(), "<div><p>a</p><hr><p>b</p></div>");
+    /// ```
+    pub fn insert_before(&mut self, reference: NodeHandle, handle: NodeHandle) -> bool {
+        self.insert_at_offset(reference, handle, 0)
+    }
+
+    /// Inserts a node directly after the given child of this tag.
+    ///
+    /// Returns `false` and does nothing if `reference` is not a direct child of this tag.
+    pub fn insert_after(&mut self, reference: NodeHandle, handle: NodeHandle) -> bool {
+        self.insert_at_offset(reference, handle, 1)
+    }
+
+    fn insert_at_offset(
+        &mut self,
+        reference: NodeHandle,
+        handle: NodeHandle,
+        offset: usize,
+    ) -> bool {
+        let children = &mut self.0._children;
+
+        match children.as_slice().iter().position(|&h| h == reference) {
+            Some(index) => {
+                children.insert(index + offset, handle);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Replaces the given child of this tag with another node, at the same position.
+    ///
+    /// Returns `false` and does nothing if `target` is not a direct child of this tag.
+    pub fn replace(&mut self, target: NodeHandle, replacement: NodeHandle) -> bool {
+        let children = self.0._children.as_mut_slice();
+
+        match children.iter_mut().find(|h| **h == target) {
+            Some(slot) => {
+                *slot = replacement;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Removes the given node from the children of this tag.
+    ///
+    /// Returns `false` if the node is not a direct child of this tag.
+    /// The node itself stays in the nodes table of the parser, it is just no longer referenced by this tag.
+    pub fn remove(&mut self, handle: NodeHandle) -> bool {
+        let children = &mut self.0._children;
+
+        match children.as_slice().iter().position(|&h| h == handle) {
+            Some(index) => {
+                children.remove(index);
+                true
+            }
+            None => false,
+        }
+    }
 }
 
 /// Attempts to find the very last node handle that is contained in the given tag
@@ -605,6 +1714,63 @@ fn find_last_node_handle<'a>(tag: &HTMLTag<'a>, parser: &Parser<'a>) -> Option<N
     }
 }
 
+/// How text is compared by [`Node::structural_eq_with`]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub enum WhitespaceMode {
+    /// Text must be exactly equal
+    Exact,
+    /// Runs of ASCII whitespace are equal regardless of their length, and leading and trailing whitespace is ignored.
+    /// Text nodes that only contain whitespace are skipped when comparing the children of tags.
+    #[default]
+    Collapse,
+}
+
+impl WhitespaceMode {
+    fn text_eq(self, a: &[u8], b: &[u8]) -> bool {
+        match self {
+            WhitespaceMode::Exact => a == b,
+            WhitespaceMode::Collapse => {
+                fn words(s: &[u8]) -> impl Iterator<Item = &[u8]> {
+                    s.split(u8::is_ascii_whitespace).filter(|w| !w.is_empty())
+                }
+                words(a).eq(words(b))
+            }
+        }
+    }
+}
+
+/// Returns the children of a tag that are compared by [`Node::structural_eq_with`]
+fn structural_children<'p, 'a>(
+    tag: &'p HTMLTag<'a>,
+    parser: &'p Parser<'a>,
+    whitespace: WhitespaceMode,
+) -> impl Iterator<Item = &'p Node<'a>> + 'p {
+    tag._children
+        .as_slice()
+        .iter()
+        .filter_map(move |h| h.get(parser))
+        .filter(move |node| match (whitespace, node) {
+            (WhitespaceMode::Collapse, Node::Raw(r)) => {
+                !r.as_bytes().iter().all(u8::is_ascii_whitespace)
+            }
+            _ => true,
+        })
+}
+
+/// Checks whether two sets of attributes contain the same attributes, in any order
+fn attributes_eq(a: &Attributes<'_>, b: &Attributes<'_>) -> bool {
+    let sorted = |attributes: &Attributes<'_>| {
+        let mut v = attributes
+            .iter()
+            .map(|(k, v)| (k.into_owned(), v.map(Cow::into_owned)))
+            .collect::<Vec<_>>();
+        v.sort_unstable();
+        v
+    };
+
+    a.len() == b.len() && sorted(a) == sorted(b)
+}
+
 /// An HTML Node
 #[derive(Debug, Clone)]
 pub enum Node<'a> {
@@ -614,6 +1780,10 @@ pub enum Node<'a> {
     Raw(Bytes<'a>),
     /// Comment (<!-- -->)
     Comment(Bytes<'a>),
+    /// CDATA section (<!\[CDATA\[ ... \]\]>)
+    ///
+    /// Only the text between the delimiters is stored.
+    CData(Bytes<'a>),
 }
 
 impl<'a> Node<'a> {
@@ -622,24 +1792,91 @@ impl<'a> Node<'a> {
         match self {
             Node::Comment(_) => Cow::Borrowed(""),
             Node::Raw(r) => r.as_utf8_str(),
+            Node::CData(c) => c.as_utf8_str(),
             Node::Tag(t) => t.inner_text(parser),
         }
     }
 
+    /// Returns the inner text of this node, with HTML character references decoded
+    ///
+    /// See [`HTMLTag::inner_text_decoded`] for details.
+    pub fn inner_text_decoded<'s, 'p: 's>(&'s self, parser: &'p Parser<'a>) -> Cow<'s, str> {
+        match self {
+            Node::Comment(_) => Cow::Borrowed(""),
+            Node::Raw(r) => raw_text(r, true),
+            // Character references are not decoded inside of CDATA sections
+            Node::CData(c) => c.as_utf8_str(),
+            Node::Tag(t) => t.inner_text_decoded(parser),
+        }
+    }
+
+    /// Returns the text content of this node
+    ///
+    /// See [`HTMLTag::text_content`] for details.
+    pub fn text_content<'s, 'p: 's>(&'s self, parser: &'p Parser<'a>) -> Cow<'s, str> {
+        match self {
+            Node::Comment(_) => Cow::Borrowed(""),
+            Node::Raw(r) => raw_text(r, true),
+            Node::CData(c) => c.as_utf8_str(),
+            Node::Tag(t) => t.text_content(parser),
+        }
+    }
+
     /// Returns the outer HTML of this node
     pub fn outer_html<'s>(&'s self, parser: &Parser<'a>) -> Cow<'s, str> {
         match self {
             Node::Comment(c) => c.as_utf8_str(),
             Node::Raw(r) => r.as_utf8_str(),
+            Node::CData(c) => Cow::Owned(format!("<![CDATA[{}]]>", c.as_utf8_str())),
             Node::Tag(t) => Cow::Owned(t.outer_html(parser)),
         }
     }
 
+    /// Returns a wrapper that implements [`Display`](fmt::Display) by writing the outer HTML of this node
+    ///
+    /// This makes it possible to format a node with `format!` or `println!` without first allocating its HTML.
+    ///
+    /// # Example
+    /// ```
+    /// let dom = tl::parse("<p>Hello <b>world</b></p>", Default::default()).unwrap();
+    /// let parser = dom.parser();
+    /// let node = dom.children()[0].get(parser).unwrap();
+    ///
+    /// println!("{}", node.display(parser));
+    /// assert_eq!(format!("[{}]", node.display(parser)), "[<p>Hello <b>world</b></p>]");
+    /// ```
+    pub fn display<'p>(&'p self, parser: &'p Parser<'a>) -> impl fmt::Display + 'p {
+        NodeDisplay { node: self, parser }
+    }
+
+    /// Writes the outer HTML of this node to the given writer
+    ///
+    /// See [`HTMLTag::outer_html_to`] for details.
+    pub fn outer_html_to<W: fmt::Write>(&self, parser: &Parser<'a>, out: &mut W) -> fmt::Result {
+        self.write_outer_html(parser, out, false)
+    }
+
+    fn write_outer_html<W: fmt::Write>(
+        &self,
+        parser: &Parser<'a>,
+        out: &mut W,
+        escape: bool,
+    ) -> fmt::Result {
+        match self {
+            Node::Comment(c) => out.write_str(&c.as_utf8_str()),
+            Node::Raw(r) if escape => entities::escape_to(out, &raw_text(r, true), entities::TEXT),
+            Node::Raw(r) => out.write_str(&r.as_utf8_str()),
+            Node::CData(c) => write!(out, "<![CDATA[{}]]>", c.as_utf8_str()),
+            Node::Tag(t) => t.write_outer_html(parser, out, escape),
+        }
+    }
+
     /// Returns the inner HTML of this node
     pub fn inner_html<'s>(&'s self, parser: &Parser<'a>) -> Cow<'s, str> {
         match self {
             Node::Comment(c) => c.as_utf8_str(),
             Node::Raw(r) => r.as_utf8_str(),
+            Node::CData(c) => c.as_utf8_str(),
             Node::Tag(t) => Cow::Owned(t.inner_html(parser)),
         }
     }
@@ -677,6 +1914,122 @@ impl<'a> Node<'a> {
         None
     }
 
+    /// Returns all descendants of this node for which the given closure returns `true`, in document order
+    ///
+    /// See [`HTMLTag::find_nodes`]. Nodes other than tags have no descendants, so this returns an empty vector for them.
+    pub fn find_nodes<F>(&self, parser: &Parser<'a>, f: &mut F) -> Vec<NodeHandle>
+    where
+        F: FnMut(&Node<'a>) -> bool,
+    {
+        self.find_nodes_iter(parser, f).collect()
+    }
+
+    /// Returns an iterator over all descendants of this node for which the given closure returns `true`, in document order
+    ///
+    /// See [`HTMLTag::find_nodes_iter`].
+    pub fn find_nodes_iter<'p, F>(
+        &'p self,
+        parser: &'p Parser<'a>,
+        f: F,
+    ) -> impl Iterator<Item = NodeHandle> + use<'a, 'p, F>
+    where
+        F: FnMut(&Node<'a>) -> bool + 'p,
+    {
+        self.as_tag()
+            .map(|tag| tag.find_nodes_iter(parser, f))
+            .into_iter()
+            .flatten()
+    }
+
+    /// Checks whether this node and `other` have the same structure and content, ignoring incidental whitespace
+    ///
+    /// This is [`Node::structural_eq_with`] with [`WhitespaceMode::Collapse`].
+    ///
+    /// # Example
+    /// ```
+    /// let a = tl::parse(r#"<ul class="x" id="y"> <li>a  b</li> </ul>"#, Default::default()).unwrap();
+    /// let b = tl::parse(r#"<UL id='y' class=x><li>a b</li></UL>"#, Default::default()).unwrap();
+    /// let c = tl::parse(r#"<ul id="y" class="x"><li>ab</li></ul>"#, Default::default()).unwrap();
+    ///
+    /// let node = a.children()[0].get(a.parser()).unwrap();
+    /// assert!(node.structural_eq(b.nodes().first().unwrap(), a.parser(), b.parser()));
+    /// assert!(!node.structural_eq(c.nodes().first().unwrap(), a.parser(), c.parser()));
+    /// ```
+    pub fn structural_eq<'b>(
+        &self,
+        other: &Node<'b>,
+        parser: &Parser<'a>,
+        other_parser: &Parser<'b>,
+    ) -> bool {
+        self.structural_eq_with(other, parser, other_parser, WhitespaceMode::Collapse)
+    }
+
+    /// Checks whether this node and `other` have the same structure and content
+    ///
+    /// Tags are equal if their names are equal ignoring ASCII case, they have the same attributes in any order,
+    /// and their children are equal. Text, comments and CDATA sections are compared according to `whitespace`.
+    /// The nodes may come from different parsers, and the source positions of the nodes are not compared.
+    pub fn structural_eq_with<'b>(
+        &self,
+        other: &Node<'b>,
+        parser: &Parser<'a>,
+        other_parser: &Parser<'b>,
+        whitespace: WhitespaceMode,
+    ) -> bool {
+        let mut stack: Vec<(&Node<'a>, &Node<'b>)> = vec![(self, other)];
+
+        while let Some(pair) = stack.pop() {
+            match pair {
+                (Node::Tag(a), Node::Tag(b)) => {
+                    if !a.name().eq_ignore_ascii_case(b.name().as_bytes())
+                        || !attributes_eq(a.attributes(), b.attributes())
+                    {
+                        return false;
+                    }
+
+                    let mut a = structural_children(a, parser, whitespace);
+                    let mut b = structural_children(b, other_parser, whitespace);
+                    loop {
+                        match (a.next(), b.next()) {
+                            (Some(a), Some(b)) => stack.push((a, b)),
+                            (None, None) => break,
+                            _ => return false,
+                        }
+                    }
+                }
+                (Node::Raw(a), Node::Raw(b))
+                | (Node::Comment(a), Node::Comment(b))
+                | (Node::CData(a), Node::CData(b)) => {
+                    if !whitespace.text_eq(a.as_bytes(), b.as_bytes()) {
+                        return false;
+                    }
+                }
+                _ => return false,
+            }
+        }
+
+        true
+    }
+
+    /// Copies this node into a node that owns all of its data
+    ///
+    /// Child handles are copied as they are, so they still refer to the nodes table of the original parser.
+    pub(crate) fn to_static(&self) -> Node<'static> {
+        match self {
+            Node::Tag(t) => Node::Tag(HTMLTag {
+                _name: t._name.to_static(),
+                _attributes: t._attributes.to_static(),
+                _children: t._children.clone(),
+                _raw: t._raw.to_static(),
+                _name_id: None,
+                _modified: t._modified,
+            }),
+            Node::Raw(r) => Node::Raw(r.to_static()),
+            Node::Comment(c) => Node::Comment(c.to_static()),
+            Node::CData(c) => Node::CData(c.to_static()),
+        }
+    }
+
     /// Tries to coerce this node into a `HTMLTag` variant
     pub fn as_tag(&self) -> Option<&HTMLTag<'a>> {
         match self {
@@ -701,6 +2054,26 @@ impl<'a> Node<'a> {
         }
     }
 
+    /// Tries to coerce this node into a comment, returning the text without the `<!--` and `-->` delimiters
+    ///
+    /// If `trim` is true, leading and trailing whitespace is removed as well.
+    /// A comment that is missing its closing delimiter, such as one created with `Node::Comment("<!-- a".into())`,
+    /// only has the opening delimiter removed.
+    ///
+    /// # Example
+    /// ```
+    /// let dom = tl::parse("<!-- note -->", Default::default()).unwrap();
+    /// let comment = &dom.nodes()[0];
+    /// assert_eq!(comment.as_comment().unwrap(), "<!-- note -->");
+    /// assert_eq!(comment.comment_text(false).unwrap(), " note ");
+    /// assert_eq!(comment.comment_text(true).unwrap(), "note");
+    /// ```
+    pub fn comment_text(&self, trim: bool) -> Option<Cow<'_, str>> {
+        let content = comment_content(self.as_comment()?.as_bytes());
+        let content = if trim { content.trim_ascii() } else { content };
+        Some(String::from_utf8_lossy(content))
+    }
+
     /// Tries to coerce this node into a comment, returning the text
     pub fn as_comment_mut(&mut self) -> Option<&mut Bytes<'a>> {
         match self {
@@ -709,6 +2082,14 @@ impl<'a> Node<'a> {
         }
     }
 
+    /// Tries to coerce this node into a CDATA section, returning the text between the delimiters
+    pub fn as_cdata(&self) -> Option<&Bytes<'a>> {
+        match self {
+            Self::CData(c) => Some(c),
+            _ => None,
+        }
+    }
+
     /// Tries to coerce this node into a raw text node, returning the text
     ///
     /// "Raw text nodes" are nodes that are not HTML tags, but just text
@@ -0,0 +1,233 @@
+use std::cell::{Cell, OnceCell, RefCell};
+use std::num::NonZeroU32;
+
+use super::iterable::QueryIterable;
+use super::selector::unescape;
+
+use crate::{HTMLTag, InnerNodeHandle, Node, NodeHandle, Parser};
+
+/// Structural information about a DOM that is needed to match selectors
+/// which depend on the position of a node in the tree, such as combinators.
+///
+/// Parents and siblings are looked up in the collection that is queried, if any.
+/// HTML tags only store handles to their children, so for nodes outside of it the parent of every node
+/// is computed lazily the first time it is needed and then reused for the rest of the query.
+#[derive(Clone)]
+pub(crate) struct MatchContext<'p, 'a> {
+    parser: &'p Parser<'a>,
+    collection: Option<&'p dyn QueryIterable<'a>>,
+    parents: OnceCell<Vec<Option<NodeHandle>>>,
+    has_html_root: OnceCell<bool>,
+    scope: Cell<Option<NodeHandle>>,
+    /// Interned ids of the tag names in the selector, keyed by the (escaped) name
+    tag_name_ids: RefCell<Vec<(Vec<u8>, Option<NonZeroU32>)>>,
+}
+
+impl<'p, 'a> MatchContext<'p, 'a> {
+    /// Creates a new context for the given parser
+    pub fn new(parser: &'p Parser<'a>) -> Self {
+        Self {
+            parser,
+            collection: None,
+            parents: OnceCell::new(),
+            has_html_root: OnceCell::new(),
+            scope: Cell::new(None),
+            tag_name_ids: RefCell::new(Vec::new()),
+        }
+    }
+
+    /// Creates a new context for a query on the given collection
+    pub fn with_collection(parser: &'p Parser<'a>, collection: &'p dyn QueryIterable<'a>) -> Self {
+        Self {
+            collection: Some(collection),
+            ..Self::new(parser)
+        }
+    }
+
+    /// Returns the parser this context was created for
+    #[inline]
+    pub fn parser(&self) -> &'p Parser<'a> {
+        self.parser
+    }
+
+    /// Resolves a node handle
+    #[inline]
+    pub fn node(&self, handle: NodeHandle) -> Option<&'p Node<'a>> {
+        handle.get(self.parser)
+    }
+
+    fn parents(&self) -> &[Option<NodeHandle>] {
+        self.parents.get_or_init(|| {
+            let tags = &self.parser.tags;
+            let mut parents = vec![None; tags.len()];
+
+            for (index, node) in tags.iter().enumerate() {
+                if let Some(tag) = node.as_tag() {
+                    let handle = NodeHandle::new(index as InnerNodeHandle);
+
+                    for child in tag._children.iter() {
+                        if let Some(parent) = parents.get_mut(child.get_inner() as usize) {
+                            *parent = Some(handle);
+                        }
+                    }
+                }
+            }
+
+            parents
+        })
+    }
+
+    /// Returns the parent of a node, or `None` if it is a top level node
+    pub fn parent(&self, handle: NodeHandle) -> Option<NodeHandle> {
+        if let Some(parent) = self
+            .collection
+            .and_then(|collection| collection.parent(self.parser, handle))
+        {
+            return Some(parent);
+        }
+
+        self.parents()
+            .get(handle.get_inner() as usize)
+            .copied()
+            .flatten()
+    }
+
+    /// Returns the element that relative selectors in `:has()` are currently evaluated for
+    #[inline]
+    pub fn scope(&self) -> Option<NodeHandle> {
+        self.scope.get()
+    }
+
+    /// Sets the element that relative selectors are evaluated for and returns the previous one,
+    /// so that it can be restored when `:has()` is nested
+    #[inline]
+    pub fn set_scope(&self, scope: Option<NodeHandle>) -> Option<NodeHandle> {
+        self.scope.replace(scope)
+    }
+
+    /// Checks whether the given node is the root element of the document
+    ///
+    /// This is the top level `<html>` element, or any top level element if the document has none (e.g. a fragment).
+    pub fn is_root(&self, handle: NodeHandle) -> bool {
+        let is_html = |tag: &HTMLTag| tag.name().eq_ignore_ascii_case(b"html");
+
+        let Some(tag) = self.node(handle).and_then(Node::as_tag) else {
+            return false;
+        };
+        if self.parent(handle).is_some() {
+            return false;
+        }
+
+        let has_html_root = *self.has_html_root.get_or_init(|| {
+            self.parser
+                .ast
+                .iter()
+                .filter_map(|h| self.node(*h).and_then(Node::as_tag))
+                .any(is_html)
+        });
+
+        !has_html_root || is_html(tag)
+    }
+
+    /// Returns all nodes that share a parent with the given node, including the node itself
+    pub fn siblings(&self, handle: NodeHandle) -> &'p [NodeHandle] {
+        if let Some(siblings) = self
+            .collection
+            .and_then(|collection| collection.siblings(self.parser, handle))
+        {
+            return siblings;
+        }
+
+        match self.parent(handle) {
+            Some(parent) => self
+                .node(parent)
+                .and_then(Node::as_tag)
+                .map_or(&[], |tag| tag._children.as_slice()),
+            None => &self.parser.ast,
+        }
+    }
+
+    /// Returns an iterator over the siblings that follow the given node, closest first
+    pub fn following_siblings(&self, handle: NodeHandle) -> impl Iterator<Item = NodeHandle> + '_ {
+        let siblings = self.siblings(handle);
+        let position = siblings.iter().position(|&h| h == handle);
+
+        position
+            .map_or(&[][..], |position| &siblings[position + 1..])
+            .iter()
+            .copied()
+    }
+
+    /// Returns an iterator over the element siblings that follow the given node, closest first.
+    ///
+    /// Text and comment nodes are skipped.
+    pub fn next_element_siblings(
+        &self,
+        handle: NodeHandle,
+    ) -> impl Iterator<Item = NodeHandle> + use<'_, 'p, 'a> {
+        self.following_siblings(handle)
+            .filter(move |&h| self.node(h).is_some_and(|node| node.as_tag().is_some()))
+    }
+
+    /// Checks whether both nodes are elements with the same tag name
+    pub fn is_same_type(&self, a: NodeHandle, b: NodeHandle) -> bool {
+        let tag = |h| self.node(h).and_then(Node::as_tag);
+        let (Some(a), Some(b)) = (tag(a), tag(b)) else {
+            return false;
+        };
+
+        match (a._name_id, b._name_id) {
+            (Some(a), Some(b)) => a == b,
+            _ => a.name() == b.name(),
+        }
+    }
+
+    /// Checks whether the node is an element with the given (escaped) tag name from a selector
+    ///
+    /// If tag names are interned, this compares the id of the name instead of the name itself.
+    pub fn has_tag_name(&self, handle: NodeHandle, name: &[u8]) -> bool {
+        let Some(tag) = self.node(handle).and_then(Node::as_tag) else {
+            return false;
+        };
+
+        match tag._name_id {
+            Some(id) => self.tag_name_id(name) == Some(id),
+            None => tag._name.as_bytes() == &*unescape(name),
+        }
+    }
+
+    /// Looks up the interned id of a tag name from a selector.
+    ///
+    /// The same selector is matched against many nodes, so the result is cached for the rest of the query.
+    fn tag_name_id(&self, name: &[u8]) -> Option<NonZeroU32> {
+        let mut cache = self.tag_name_ids.borrow_mut();
+
+        if let Some(&(_, id)) = cache.iter().find(|(key, _)| **key == *name) {
+            return id;
+        }
+
+        let id = self
+            .parser
+            .interned_tag_name_ids(|n| n == &*unescape(name))
+            .next();
+        cache.push((name.to_vec(), id));
+        id
+    }
+
+    /// Returns an iterator over the element siblings that precede the given node, closest first.
+    ///
+    /// Text and comment nodes are skipped.
+    pub fn previous_element_siblings(
+        &self,
+        handle: NodeHandle,
+    ) -> impl Iterator<Item = NodeHandle> + use<'_, 'p, 'a> {
+        let siblings = self.siblings(handle);
+        let position = siblings.iter().position(|&h| h == handle).unwrap_or(0);
+
+        siblings[..position]
+            .iter()
+            .rev()
+            .copied()
+            .filter(move |&h| self.node(h).is_some_and(|node| node.as_tag().is_some()))
+    }
+}
@@ -1,14 +1,19 @@
-use std::marker::PhantomData;
+use std::{borrow::Cow, marker::PhantomData};
 
 use crate::{NodeHandle, Parser};
 
-use super::{iterable::QueryIterable, Selector};
+use super::{context::MatchContext, iterable::QueryIterable, Selector};
 
 /// A query selector iterator that yields matching HTML nodes
+///
+/// Every node in the collection is visited exactly once, in the order in which it was parsed,
+/// and checked against the whole selector. A node is therefore never yielded more than once,
+/// even if it matches several branches of a selector list (`div, .content`)
+/// or a combinator can be satisfied in several ways (`div div p` with nested `div`s).
 pub struct QuerySelectorIterator<'a, 'b, Q: QueryIterable<'a>> {
-    selector: Selector<'b>,
+    selector: Cow<'b, Selector<'b>>,
     collection: &'b Q,
-    parser: &'b Parser<'a>,
+    context: MatchContext<'b, 'a>,
     index: usize,
     len: usize,
     _a: PhantomData<&'a ()>,
@@ -19,7 +24,7 @@ impl<'a, 'b, Q: QueryIterable<'a>> Clone for QuerySelectorIterator<'a, 'b, Q> {
         Self {
             selector: self.selector.clone(),
             collection: self.collection,
-            parser: self.parser,
+            context: self.context.clone(),
             index: self.index,
             len: self.len,
             _a: PhantomData,
@@ -30,12 +35,29 @@ impl<'a, 'b, Q: QueryIterable<'a>> Clone for QuerySelectorIterator<'a, 'b, Q> {
 impl<'a, 'b, Q: QueryIterable<'a>> QuerySelectorIterator<'a, 'b, Q> {
     /// Creates a new query selector iterator
     pub fn new(selector: Selector<'b>, parser: &'b Parser<'a>, collection: &'b Q) -> Self {
+        Self::with_selector(Cow::Owned(selector), parser, collection)
+    }
+
+    /// Creates a new query selector iterator that borrows an already parsed selector
+    pub(crate) fn borrowed(
+        selector: &'b Selector<'b>,
+        parser: &'b Parser<'a>,
+        collection: &'b Q,
+    ) -> Self {
+        Self::with_selector(Cow::Borrowed(selector), parser, collection)
+    }
+
+    fn with_selector(
+        selector: Cow<'b, Selector<'b>>,
+        parser: &'b Parser<'a>,
+        collection: &'b Q,
+    ) -> Self {
         Self {
             selector,
             collection,
             index: 0,
             len: collection.len(parser),
-            parser,
+            context: MatchContext::with_collection(parser, collection),
             _a: PhantomData,
         }
     }
@@ -46,10 +68,10 @@ impl<'a, 'b, Q: QueryIterable<'a>> Iterator for QuerySelectorIterator<'a, 'b, Q>
 
     fn next(&mut self) -> Option<Self::Item> {
         while self.index < self.len {
-            let node = self.collection.get(self.parser, self.index);
+            let node = self.collection.get(self.context.parser(), self.index);
             self.index += 1;
-            if let Some((node, id)) = node {
-                let matches = self.selector.matches(node);
+            if let Some((_, id)) = node {
+                let matches = self.selector.matches_in(&self.context, id);
 
                 if matches {
                     return Some(id);
@@ -5,6 +5,11 @@ mod private {
 }
 
 /// Trait for types that a query selector can iterate over
+///
+/// Besides the candidate nodes, e.g. the descendants of an `HTMLTag`, this provides the parent and sibling lookups
+/// that combinators and structural pseudo-classes need within the scope of the collection.
+/// Matching falls back to the whole document for nodes outside of it,
+/// so `div > a` queried on a tag also matches links whose parent `div` is the tag itself.
 pub trait QueryIterable<'a>: private::Sealed {
     /// Gets a node at a specific index
     fn get<'b>(
@@ -16,6 +21,59 @@ pub trait QueryIterable<'a>: private::Sealed {
     fn len(&self, parser: &Parser) -> usize;
     /// Gets the starting index
     fn start(&self) -> Option<InnerNodeHandle>;
+    /// Gets the parent of a node if it is part of this collection
+    ///
+    /// Returns `None` for the top level nodes of the collection, e.g. the direct children of an `HTMLTag`,
+    /// and for nodes that are not part of it.
+    fn parent(&self, parser: &Parser<'a>, handle: NodeHandle) -> Option<NodeHandle>;
+    /// Gets all nodes that share a parent with the given node, including the node itself
+    ///
+    /// Returns `None` if the node is not part of this collection.
+    fn siblings<'b>(
+        &'b self,
+        parser: &'b Parser<'a>,
+        handle: NodeHandle,
+    ) -> Option<&'b [NodeHandle]>;
+}
+
+/// Finds the parent of a node by descending from the given top level nodes.
+///
+/// Nodes are stored in the order in which they were parsed, so the subtree that contains the node
+/// starts at the last child whose handle is not greater than it.
+/// Returns `Some(None)` if the node is one of the top level nodes and `None` if it is not found.
+fn find_parent(
+    parser: &Parser<'_>,
+    top: &[NodeHandle],
+    handle: NodeHandle,
+) -> Option<Option<NodeHandle>> {
+    let mut children = top;
+    let mut parent = None;
+
+    loop {
+        let index = children
+            .partition_point(|&child| child <= handle)
+            .checked_sub(1)?;
+        let child = children[index];
+
+        if child == handle {
+            return Some(parent);
+        }
+
+        children = child.get(parser)?.as_tag()?._children.as_slice();
+        parent = Some(child);
+    }
+}
+
+/// Returns the children of the given parent, or the top level nodes if there is none
+fn children_of<'b>(
+    parser: &'b Parser<'_>,
+    top: &'b [NodeHandle],
+    parent: Option<NodeHandle>,
+) -> Option<&'b [NodeHandle]> {
+    match parent {
+        Some(parent) => Some(parent.get(parser)?.as_tag()?._children.as_slice()),
+        None => Some(top),
+    }
 }
 
 impl<'a> private::Sealed for VDom<'a> {}
@@ -44,6 +102,22 @@ impl<'a> QueryIterable<'a> for VDom<'a> {
         // The starting ID is always 0 in a VDom
         Some(0)
     }
+
+    fn parent(&self, parser: &Parser<'a>, handle: NodeHandle) -> Option<NodeHandle> {
+        // Nodes that were added after parsing are not necessarily stored in tree order
+        find_parent(parser, &parser.ast, handle).unwrap_or_else(|| handle.parent(parser))
+    }
+
+    fn siblings<'b>(
+        &'b self,
+        parser: &'b Parser<'a>,
+        handle: NodeHandle,
+    ) -> Option<&'b [NodeHandle]> {
+        match find_parent(parser, &parser.ast, handle) {
+            Some(parent) => children_of(parser, &parser.ast, parent),
+            None => handle.siblings(parser).map(|(siblings, _)| siblings),
+        }
+    }
 }
 
 impl<'a> private::Sealed for HTMLTag<'a> {}
@@ -74,4 +148,17 @@ impl<'a> QueryIterable<'a> for HTMLTag<'a> {
     fn start(&self) -> Option<InnerNodeHandle> {
         self.children().start()
     }
+
+    fn parent(&self, parser: &Parser<'a>, handle: NodeHandle) -> Option<NodeHandle> {
+        find_parent(parser, self._children.as_slice(), handle).flatten()
+    }
+
+    fn siblings<'b>(
+        &'b self,
+        parser: &'b Parser<'a>,
+        handle: NodeHandle,
+    ) -> Option<&'b [NodeHandle]> {
+        let parent = find_parent(parser, self._children.as_slice(), handle)?;
+        children_of(parser, self._children.as_slice(), parent)
+    }
 }
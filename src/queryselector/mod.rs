@@ -1,3 +1,4 @@
+pub(crate) mod context;
 /// Query selector iterator
 pub mod iter;
 /// Query selector iterable
@@ -1,7 +1,14 @@
-use crate::{stream::Stream, util};
+use crate::{
+    errors::{SelectorParseError, SelectorParseErrorKind},
+    stream::Stream,
+    util,
+};
 
 use super::Selector;
 
+/// Constructor of a [`Selector`] variant that combines two selectors
+type Combinator<'a> = fn(Box<Selector<'a>>, Box<Selector<'a>>) -> Selector<'a>;
+
 /// A query selector parser
 pub struct Parser<'a> {
     stream: Stream<'a, u8>,
@@ -15,6 +22,39 @@ impl<'a> Parser<'a> {
         }
     }
 
+    /// Creates an error at the current position
+    fn error(&self, kind: SelectorParseErrorKind) -> SelectorParseError {
+        SelectorParseError {
+            offset: self.stream.idx,
+            kind,
+        }
+    }
+
+    /// Creates an error for the character at the current position, or for the end of the input
+    fn unexpected(&self) -> SelectorParseError {
+        if self.stream.is_eof() {
+            return self.error(SelectorParseErrorKind::UnexpectedEof);
+        }
+
+        let rest = self.stream.slice(self.stream.idx, self.stream.len());
+        let valid = match std::str::from_utf8(rest) {
+            Ok(valid) => valid,
+            // Everything up to `valid_up_to` is known to be valid UTF-8
+            Err(err) => std::str::from_utf8(&rest[..err.valid_up_to()]).unwrap_or_default(),
+        };
+        let c = valid.chars().next().unwrap_or(char::REPLACEMENT_CHARACTER);
+
+        self.error(SelectorParseErrorKind::UnexpectedCharacter(c))
+    }
+
+    /// Skips the given character, or returns an error if it is not at the current position
+    fn expect(&mut self, c: u8) -> Result<(), SelectorParseError> {
+        match self.stream.expect_and_skip(c) {
+            Some(_) => Ok(()),
+            None => Err(self.error(SelectorParseErrorKind::Expected(c as char))),
+        }
+    }
+
     fn skip_whitespaces(&mut self) -> bool {
         let has_whitespace = self.stream.expect_and_skip_cond(b' ');
         while !self.stream.is_eof() {
@@ -25,12 +65,12 @@ impl<'a> Parser<'a> {
         has_whitespace
     }
 
-    fn read_identifier(&mut self) -> &'a [u8] {
+    fn read_while(&mut self, predicate: fn(u8) -> bool) -> &'a [u8] {
         let start = self.stream.idx;
 
         while !self.stream.is_eof() {
-            let is_ident = self.stream.current().copied().map_or(false, util::is_ident);
-            if !is_ident {
+            let matches = self.stream.current().copied().is_some_and(predicate);
+            if !matches {
                 break;
             } else {
                 self.stream.advance();
@@ -40,42 +80,88 @@ impl<'a> Parser<'a> {
         self.stream.slice(start, self.stream.idx)
     }
 
-    fn parse_combinator(&mut self, left: Selector<'a>) -> Option<Selector<'a>> {
-        let has_whitespaces = self.skip_whitespaces();
-
-        let tok = if let Some(tok) = self.stream.current_cpy() {
-            tok
-        } else {
-            return Some(left);
-        };
+    /// Reads a tag name, ID or class name
+    ///
+    /// CSS escapes are kept as they are written: a backslash followed by up to 6 hex digits and an optional space,
+    /// or a backslash followed by any other character (`.md\:flex`). They are resolved when matching.
+    fn read_identifier(&mut self) -> &'a [u8] {
+        let start = self.stream.idx;
 
-        let combinator = match tok {
-            b',' => {
-                self.stream.advance();
-                let right = self.selector()?;
-                Selector::Or(Box::new(left), Box::new(right))
-            }
-            b'>' => {
+        while let Some(c) = self.stream.current_cpy() {
+            if util::is_selector_ident(c) {
                 self.stream.advance();
-                let right = self.selector()?;
-                Selector::Parent(Box::new(left), Box::new(right))
+                continue;
             }
-            _ if has_whitespaces => {
-                let right = self.selector()?;
-                Selector::Descendant(Box::new(left), Box::new(right))
+
+            // a backslash at the end of the input does not escape anything
+            if c != b'\\' || self.stream.idx + 1 >= self.stream.len() {
+                break;
             }
-            _ if !has_whitespaces => {
-                let right = self.selector()?;
-                Selector::And(Box::new(left), Box::new(right))
+            self.stream.advance();
+
+            let hex_digits = self
+                .stream
+                .slice(self.stream.idx, self.stream.len())
+                .iter()
+                .take(6)
+                .take_while(|c| c.is_ascii_hexdigit())
+                .count();
+
+            if hex_digits > 0 {
+                self.stream.advance_by(hex_digits);
+                self.stream.expect_and_skip_cond(b' ');
+            } else {
+                self.stream.advance();
             }
-            _ => unreachable!(),
+        }
+
+        self.stream.slice(start, self.stream.idx)
+    }
+
+    /// Reads an attribute name or value
+    ///
+    /// This accepts a few more characters than [`Parser::read_identifier`],
+    /// so that values like `og:title` can be used without quotes.
+    fn read_attribute_identifier(&mut self) -> &'a [u8] {
+        self.read_while(util::is_ident)
+    }
+
+    /// Reads a string up to the given closing quote, which is skipped
+    ///
+    /// The opening quote must already have been skipped.
+    fn read_quoted(&mut self, quote: u8) -> Result<&'a [u8], SelectorParseError> {
+        let start = self.stream.idx;
+        while self.stream.current_cpy().is_some_and(|c| c != quote) {
+            self.stream.advance();
+        }
+        let text = self.stream.slice(start, self.stream.idx);
+        self.expect(quote)?;
+        Ok(text)
+    }
+
+    /// Parses the value of an attribute selector, including the optional flag and the closing bracket
+    ///
+    /// Returns the value and whether it should be compared case-insensitively.
+    fn parse_attribute_value(&mut self) -> Result<(&'a [u8], bool), SelectorParseError> {
+        // quoted values can contain any character but the quote, e.g. `[title="a > b"]`
+        let value = match self.stream.expect_oneof_and_skip(b"\"'") {
+            Some(quote) => self.read_quoted(quote)?,
+            None => self.read_attribute_identifier(),
+        };
+
+        self.skip_whitespaces();
+        let case_insensitive = match self.stream.expect_oneof_and_skip(b"iIsS") {
+            Some(b'i' | b'I') => true,
+            Some(_) | None => false,
         };
+        self.skip_whitespaces();
 
-        Some(combinator)
+        self.expect(b']')?;
+        Ok((value, case_insensitive))
     }
 
-    fn parse_attribute(&mut self) -> Option<Selector<'a>> {
-        let attribute = self.read_identifier();
+    fn parse_attribute(&mut self) -> Result<Selector<'a>, SelectorParseError> {
+        let attribute = self.read_attribute_identifier();
         let ty = match self.stream.current_cpy() {
             Some(b']') => {
                 self.stream.advance();
@@ -83,44 +169,195 @@ impl<'a> Parser<'a> {
             }
             Some(b'=') => {
                 self.stream.advance();
-                let quote = self.stream.expect_oneof_and_skip(&[b'"', b'\'']);
-                let value = self.read_identifier();
-                if let Some(quote) = quote {
-                    // Only require the given quote if the value starts with a quote
-                    self.stream.expect_and_skip(quote)?;
-                }
-                self.stream.expect_and_skip(b']')?;
-                Selector::AttributeValue(attribute, value)
+                let (value, case_insensitive) = self.parse_attribute_value()?;
+                Selector::AttributeValue(attribute, value, case_insensitive)
             }
-            Some(c @ b'~' | c @ b'^' | c @ b'$' | c @ b'*') => {
+            Some(c @ b'~' | c @ b'^' | c @ b'$' | c @ b'*' | c @ b'|') => {
                 self.stream.advance();
-                self.stream.expect_and_skip(b'=')?;
-                let quote = self.stream.expect_oneof_and_skip(&[b'"', b'\'']);
-                let value = self.read_identifier();
-                if let Some(quote) = quote {
-                    // Only require the given quote if the value starts with a quote
-                    self.stream.expect_and_skip(quote)?;
-                }
-                self.stream.expect_and_skip(b']')?;
+                self.expect(b'=')?;
+                let (value, case_insensitive) = self.parse_attribute_value()?;
                 match c {
-                    b'~' => Selector::AttributeValueWhitespacedContains(attribute, value),
-                    b'^' => Selector::AttributeValueStartsWith(attribute, value),
-                    b'$' => Selector::AttributeValueEndsWith(attribute, value),
-                    b'*' => Selector::AttributeValueSubstring(attribute, value),
+                    b'~' => Selector::AttributeValueWhitespacedContains(
+                        attribute,
+                        value,
+                        case_insensitive,
+                    ),
+                    b'^' => Selector::AttributeValueStartsWith(attribute, value, case_insensitive),
+                    b'$' => Selector::AttributeValueEndsWith(attribute, value, case_insensitive),
+                    b'*' => Selector::AttributeValueSubstring(attribute, value, case_insensitive),
+                    b'|' => Selector::AttributeValueDashMatch(attribute, value, case_insensitive),
                     _ => unreachable!(),
                 }
             }
-            _ => return None,
+            _ => return Err(self.error(SelectorParseErrorKind::Expected(']'))),
         };
-        Some(ty)
+        Ok(ty)
     }
 
-    /// Parses a full selector
-    pub fn selector(&mut self) -> Option<Selector<'a>> {
+    /// Parses a pseudo-class, without the leading colon: `not(.foo)`
+    fn parse_pseudo_class(&mut self) -> Result<Selector<'a>, SelectorParseError> {
+        let start = self.stream.idx;
+        let name = self.read_identifier();
+
+        match name {
+            b"not" => {
+                self.expect(b'(')?;
+                let inner = self.selector_list()?;
+                self.skip_whitespaces();
+                self.expect(b')')?;
+                Ok(Selector::Not(Box::new(inner)))
+            }
+            b"is" | b"where" => {
+                self.expect(b'(')?;
+                let mut list = vec![self.complex_selector()?];
+                while self.stream.expect_and_skip_cond(b',') {
+                    list.push(self.complex_selector()?);
+                }
+                self.skip_whitespaces();
+                self.expect(b')')?;
+                Ok(Selector::Is(list))
+            }
+            b"has" => {
+                self.expect(b'(')?;
+                let mut relative = self.relative_selector()?;
+                while self.stream.expect_and_skip_cond(b',') {
+                    let right = self.relative_selector()?;
+                    relative = Selector::Or(Box::new(relative), Box::new(right));
+                }
+                self.skip_whitespaces();
+                self.expect(b')')?;
+                Ok(Selector::Has(Box::new(relative)))
+            }
+            b"contains" => {
+                self.expect(b'(')?;
+                let text = self.parse_contains_argument()?;
+                Ok(Selector::Contains(text))
+            }
+            b"nth-child" => {
+                let (a, b) = self.parse_nth_argument()?;
+                Ok(Selector::NthChild { a, b })
+            }
+            b"nth-of-type" => {
+                let (a, b) = self.parse_nth_argument()?;
+                Ok(Selector::NthOfType { a, b })
+            }
+            b"first-child" => Ok(Selector::NthChild { a: 0, b: 1 }),
+            b"first-of-type" => Ok(Selector::FirstOfType),
+            b"last-of-type" => Ok(Selector::LastOfType),
+            b"only-child" => Ok(Selector::OnlyChild),
+            b"only-of-type" => Ok(Selector::OnlyOfType),
+            b"root" => Ok(Selector::Root),
+            _ => Err(SelectorParseError {
+                offset: start,
+                kind: SelectorParseErrorKind::UnknownPseudoClass,
+            }),
+        }
+    }
+
+    /// Parses the argument of `:contains()`, which is either quoted or everything up to the closing parenthesis
+    fn parse_contains_argument(&mut self) -> Result<&'a [u8], SelectorParseError> {
+        self.skip_whitespaces();
+
+        let text = match self.stream.expect_oneof_and_skip(b"\"'") {
+            Some(quote) => {
+                let text = self.read_quoted(quote)?;
+                self.skip_whitespaces();
+                text
+            }
+            None => {
+                let start = self.stream.idx;
+                while self.stream.current_cpy().is_some_and(|c| c != b')') {
+                    self.stream.advance();
+                }
+                self.stream.slice(start, self.stream.idx).trim_ascii_end()
+            }
+        };
+
+        self.expect(b')')?;
+        Ok(text)
+    }
+
+    /// Reads a non-negative integer, clamping values that do not fit in an `i32` like browsers do
+    fn read_number(&mut self) -> Option<i32> {
+        let digits = self.read_while(|c| c.is_ascii_digit());
+        if digits.is_empty() {
+            return None;
+        }
+
+        Some(digits.iter().fold(0i32, |number, digit| {
+            number
+                .saturating_mul(10)
+                .saturating_add(i32::from(digit - b'0'))
+        }))
+    }
+
+    /// Parses the parenthesized argument of `:nth-child()` and `:nth-of-type()`: `(2n+1)`
+    fn parse_nth_argument(&mut self) -> Result<(i32, i32), SelectorParseError> {
+        self.expect(b'(')?;
+        let (a, b) = self
+            .parse_nth()
+            .ok_or_else(|| self.error(SelectorParseErrorKind::InvalidNth))?;
+        self.expect(b')')?;
+        Ok((a, b))
+    }
+
+    /// Parses the argument of `:nth-child()` using the An+B microsyntax: `2n+1`, `-n + 3`, `odd`, `4`
+    ///
+    /// Returns the coefficients `a` and `b`.
+    fn parse_nth(&mut self) -> Option<(i32, i32)> {
         self.skip_whitespaces();
-        let tok = self.stream.current_cpy()?;
 
-        let left = match tok {
+        let result = match self.stream.current_cpy()? {
+            b'o' | b'e' => match self.read_identifier() {
+                b"odd" => (2, 1),
+                b"even" => (2, 0),
+                _ => return None,
+            },
+            _ => {
+                let sign = match self.stream.expect_oneof_and_skip(b"+-") {
+                    Some(b'-') => -1,
+                    _ => 1,
+                };
+                let has_digits = self.stream.current().is_some_and(u8::is_ascii_digit);
+                let number = if has_digits {
+                    Some(self.read_number()?)
+                } else {
+                    None
+                };
+
+                if self.stream.expect_oneof_and_skip(b"nN").is_some() {
+                    let a = sign * number.unwrap_or(1);
+                    self.skip_whitespaces();
+
+                    let b = match self.stream.expect_oneof_and_skip(b"+-") {
+                        Some(op) => {
+                            self.skip_whitespaces();
+                            let b = self.read_number()?;
+                            if op == b'-' {
+                                -b
+                            } else {
+                                b
+                            }
+                        }
+                        None => 0,
+                    };
+
+                    (a, b)
+                } else {
+                    (0, sign * number?)
+                }
+            }
+        };
+
+        self.skip_whitespaces();
+        Some(result)
+    }
+
+    /// Parses a single simple selector, such as `div`, `#foo` or `[href]`
+    fn simple_selector(&mut self) -> Result<Selector<'a>, SelectorParseError> {
+        let tok = self.stream.current_cpy().ok_or_else(|| self.unexpected())?;
+
+        let selector = match tok {
             b'#' => {
                 self.stream.advance();
                 let id = self.read_identifier();
@@ -139,13 +376,124 @@ impl<'a> Parser<'a> {
                 self.stream.advance();
                 self.parse_attribute()?
             }
-            _ if util::is_ident(tok) => {
+            b':' => {
+                self.stream.advance();
+                self.parse_pseudo_class()?
+            }
+            _ if util::is_selector_ident(tok) || tok == b'\\' => {
                 let tag = self.read_identifier();
                 Selector::Tag(tag)
             }
-            _ => return None,
+            _ => return Err(self.unexpected()),
+        };
+
+        Ok(selector)
+    }
+
+    /// Parses a sequence of simple selectors that must all match the same element: `div.foo[bar]`
+    fn compound_selector(&mut self) -> Result<Selector<'a>, SelectorParseError> {
+        let mut selector = self.simple_selector()?;
+
+        // Tag names and `*` may only appear at the start of a compound selector
+        while let Some(b'#' | b'.' | b'[' | b':') = self.stream.current_cpy() {
+            let right = self.simple_selector()?;
+            selector = Selector::And(Box::new(selector), Box::new(right));
+        }
+
+        Ok(selector)
+    }
+
+    /// Parses compound selectors separated by combinators: `div > p + span ~ a`
+    ///
+    /// Combinators are left associative, so the right hand side of a combinator selector
+    /// is always the compound selector that the matched element itself has to satisfy.
+    fn complex_selector(&mut self) -> Result<Selector<'a>, SelectorParseError> {
+        self.skip_whitespaces();
+        let selector = self.compound_selector()?;
+        self.combinators(selector)
+    }
+
+    /// Parses the combinators and compound selectors that follow an already parsed selector
+    fn combinators(
+        &mut self,
+        mut selector: Selector<'a>,
+    ) -> Result<Selector<'a>, SelectorParseError> {
+        loop {
+            let has_whitespaces = self.skip_whitespaces();
+
+            let combinator: Combinator<'a> = match self.stream.current_cpy() {
+                // A closing parenthesis ends the argument of a functional pseudo-class
+                None | Some(b',' | b')') => break,
+                Some(b'>') => {
+                    self.stream.advance();
+                    Selector::Parent
+                }
+                Some(b'+') => {
+                    self.stream.advance();
+                    Selector::AdjacentSibling
+                }
+                // `~=` only ever appears inside of attribute selectors, which are fully consumed by `parse_attribute`
+                Some(b'~') => {
+                    self.stream.advance();
+                    Selector::GeneralSibling
+                }
+                Some(_) if has_whitespaces => Selector::Descendant,
+                Some(_) => return Err(self.unexpected()),
+            };
+
+            self.skip_whitespaces();
+            let right = self.compound_selector()?;
+            selector = combinator(Box::new(selector), Box::new(right));
+        }
+
+        Ok(selector)
+    }
+
+    /// Parses a selector relative to the element in `:has()`: `> img`, `+ p`, `.foo .bar`
+    ///
+    /// The element is represented by [`Selector::Scope`], and a selector without a leading combinator
+    /// is relative to the descendants of the element.
+    fn relative_selector(&mut self) -> Result<Selector<'a>, SelectorParseError> {
+        self.skip_whitespaces();
+
+        let combinator: Combinator<'a> = match self.stream.expect_oneof_and_skip(b">+~") {
+            Some(b'>') => Selector::Parent,
+            Some(b'+') => Selector::AdjacentSibling,
+            Some(_) => Selector::GeneralSibling,
+            None => Selector::Descendant,
         };
 
-        self.parse_combinator(left)
+        self.skip_whitespaces();
+        let right = self.compound_selector()?;
+        self.combinators(combinator(Box::new(Selector::Scope), Box::new(right)))
+    }
+
+    /// Parses a comma separated list of selectors: `.foo, .bar`
+    fn selector_list(&mut self) -> Result<Selector<'a>, SelectorParseError> {
+        let mut selector = self.complex_selector()?;
+
+        while self.stream.expect_and_skip_cond(b',') {
+            let right = self.complex_selector()?;
+            selector = Selector::Or(Box::new(selector), Box::new(right));
+        }
+
+        Ok(selector)
+    }
+
+    /// Parses a full selector
+    pub fn selector(&mut self) -> Option<Selector<'a>> {
+        self.selector_checked().ok()
+    }
+
+    /// Parses a full selector, returning the position and reason of the error if it is invalid
+    pub fn selector_checked(&mut self) -> Result<Selector<'a>, SelectorParseError> {
+        let selector = self.selector_list()?;
+
+        // Anything that is left over at this point is not part of a valid selector
+        if !self.stream.is_eof() {
+            return Err(self.unexpected());
+        }
+
+        Ok(selector)
     }
 }
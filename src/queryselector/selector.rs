@@ -1,9 +1,16 @@
-use crate::Node;
+use std::borrow::Cow;
+
+use crate::{Node, NodeHandle};
+
+use super::context::MatchContext;
 
 /// A single query selector node
 #[derive(Debug, Clone)]
 pub enum Selector<'a> {
     /// Tag selector: foo
+    ///
+    /// Like [`Selector::Id`] and [`Selector::Class`], the name is stored as it is written in the selector,
+    /// including CSS escapes such as `\:` or `\3A `, which are resolved when matching.
     Tag(&'a [u8]),
     /// ID selector: #foo
     Id(&'a [u8]),
@@ -19,71 +26,399 @@ pub enum Selector<'a> {
     Descendant(Box<Selector<'a>>, Box<Selector<'a>>),
     /// Parent combinator: .foo > .bar
     Parent(Box<Selector<'a>>, Box<Selector<'a>>),
+    /// Adjacent sibling combinator: .foo + .bar
+    AdjacentSibling(Box<Selector<'a>>, Box<Selector<'a>>),
+    /// General sibling combinator: .foo ~ .bar
+    GeneralSibling(Box<Selector<'a>>, Box<Selector<'a>>),
+    /// Negation pseudo-class: :not(.foo)
+    Not(Box<Selector<'a>>),
+    /// Matches-any pseudo-class: :is(h1, h2)
+    ///
+    /// Matches elements that match any of the selectors in the list.
+    /// `:where()` is parsed to the same variant, since specificity is not tracked.
+    Is(Vec<Selector<'a>>),
+    /// Relational pseudo-class: :has(> img)
+    ///
+    /// Matches elements for which the relative selector matches another element.
+    /// The relative selector is anchored to the element with [`Selector::Scope`],
+    /// e.g. `:has(> img)` is parsed as `Has(Parent(Scope, Tag("img")))`.
+    Has(Box<Selector<'a>>),
+    /// The element that the relative selector of a [`Selector::Has`] is evaluated for
+    ///
+    /// This does not match anything outside of `:has()`.
+    Scope,
+    /// Text pseudo-class: :contains("foo")
+    ///
+    /// Matches elements whose text content contains the given string. The comparison is case-sensitive.
+    ///
+    /// This is not part of the CSS specification, but an extension that originates from jQuery.
+    /// The text content is only available with a parser, so [`Selector::matches`] never matches it.
+    Contains(&'a [u8]),
+    /// Positional pseudo-class: :nth-child(an+b)
+    ///
+    /// Matches elements whose 1-based index among their element siblings is `a*n + b` for some `n >= 0`.
+    /// `:first-child` is parsed as `:nth-child(1)`.
+    NthChild {
+        /// The step size
+        a: i32,
+        /// The offset
+        b: i32,
+    },
+    /// Positional pseudo-class: :nth-of-type(an+b)
+    ///
+    /// Like [`Selector::NthChild`], but only siblings with the same tag name are counted.
+    NthOfType {
+        /// The step size
+        a: i32,
+        /// The offset
+        b: i32,
+    },
+    /// Root pseudo-class: :root
+    ///
+    /// Matches the top level `<html>` element, or any top level element if there is none.
+    /// This depends on the position of the node in the tree, so [`Selector::matches`] never matches it.
+    Root,
+    /// Positional pseudo-class: :first-of-type
+    ///
+    /// Matches elements that are the first among their siblings with the same tag name.
+    FirstOfType,
+    /// Positional pseudo-class: :last-of-type
+    ///
+    /// Matches elements that are the last among their siblings with the same tag name.
+    LastOfType,
+    /// Positional pseudo-class: :only-child
+    ///
+    /// Matches elements without element siblings. Text and comment nodes are ignored.
+    OnlyChild,
+    /// Positional pseudo-class: :only-of-type
+    ///
+    /// Matches elements without siblings that have the same tag name.
+    OnlyOfType,
     /// Attribute: \[foo\]
     Attribute(&'a [u8]),
     /// Attribute with value: [foo=bar]
-    AttributeValue(&'a [u8], &'a [u8]),
+    ///
+    /// The last field is set if the value is compared case-insensitively: [foo=bar i]
+    AttributeValue(&'a [u8], &'a [u8], bool),
     /// Attribute with whitespace-separated list of values that contains a value: [foo~=bar]
-    AttributeValueWhitespacedContains(&'a [u8], &'a [u8]),
+    ///
+    /// The last field is set if the value is compared case-insensitively: [foo~=bar i]
+    AttributeValueWhitespacedContains(&'a [u8], &'a [u8], bool),
     /// Attribute with value that starts with: [foo^=bar]
-    AttributeValueStartsWith(&'a [u8], &'a [u8]),
+    ///
+    /// The last field is set if the value is compared case-insensitively: [foo^=bar i]
+    AttributeValueStartsWith(&'a [u8], &'a [u8], bool),
     /// Attribute with value that ends with: [foo$=bar]
-    AttributeValueEndsWith(&'a [u8], &'a [u8]),
+    ///
+    /// The last field is set if the value is compared case-insensitively: [foo$=bar i]
+    AttributeValueEndsWith(&'a [u8], &'a [u8], bool),
     /// Attribute with value that contains: [foo*=bar]
-    AttributeValueSubstring(&'a [u8], &'a [u8]),
+    ///
+    /// The last field is set if the value is compared case-insensitively: [foo*=bar i]
+    AttributeValueSubstring(&'a [u8], &'a [u8], bool),
+    /// Attribute with value that is either equal to or starts with the value followed by a hyphen: [foo|=bar]
+    ///
+    /// The last field is set if the value is compared case-insensitively: [foo|=bar i]
+    AttributeValueDashMatch(&'a [u8], &'a [u8], bool),
 }
 
 impl<'a> Selector<'a> {
+    /// Checks if the node referred to by the given handle matches this selector
+    ///
+    /// Unlike [`Selector::matches`], this also takes the position of the node in the tree into account,
+    /// which is needed for combinators.
+    pub(crate) fn matches_in(&self, ctx: &MatchContext<'_, '_>, handle: NodeHandle) -> bool {
+        match self {
+            Self::Tag(tag) => ctx.has_tag_name(handle, tag),
+            Self::And(a, b) => a.matches_in(ctx, handle) && b.matches_in(ctx, handle),
+            Self::Or(a, b) => a.matches_in(ctx, handle) || b.matches_in(ctx, handle),
+            Self::Is(list) => list.iter().any(|s| s.matches_in(ctx, handle)),
+            Self::Not(inner) => {
+                ctx.node(handle).is_some_and(|n| n.as_tag().is_some())
+                    && !inner.matches_in(ctx, handle)
+            }
+            Self::NthChild { a, b } => {
+                ctx.node(handle).is_some_and(|n| n.as_tag().is_some()) && {
+                    let index = ctx.previous_element_siblings(handle).count() as i32 + 1;
+                    nth_matches(*a, *b, index)
+                }
+            }
+            Self::NthOfType { a, b } => {
+                ctx.node(handle).is_some_and(|n| n.as_tag().is_some()) && {
+                    let index = ctx
+                        .previous_element_siblings(handle)
+                        .filter(|&h| ctx.is_same_type(h, handle))
+                        .count() as i32
+                        + 1;
+                    nth_matches(*a, *b, index)
+                }
+            }
+            Self::Root => ctx.is_root(handle),
+            Self::OnlyChild => {
+                ctx.node(handle).is_some_and(|n| n.as_tag().is_some())
+                    && ctx.previous_element_siblings(handle).next().is_none()
+                    && ctx.next_element_siblings(handle).next().is_none()
+            }
+            Self::OnlyOfType => {
+                ctx.node(handle).is_some_and(|n| n.as_tag().is_some())
+                    && !ctx
+                        .previous_element_siblings(handle)
+                        .chain(ctx.next_element_siblings(handle))
+                        .any(|h| ctx.is_same_type(h, handle))
+            }
+            Self::FirstOfType => {
+                ctx.node(handle).is_some_and(|n| n.as_tag().is_some())
+                    && !ctx
+                        .previous_element_siblings(handle)
+                        .any(|h| ctx.is_same_type(h, handle))
+            }
+            Self::LastOfType => {
+                ctx.node(handle).is_some_and(|n| n.as_tag().is_some())
+                    && !ctx
+                        .next_element_siblings(handle)
+                        .any(|h| ctx.is_same_type(h, handle))
+            }
+            Self::Scope => ctx.scope() == Some(handle),
+            Self::Contains(text) => ctx.node(handle).and_then(Node::as_tag).is_some_and(|tag| {
+                tag.inner_text(ctx.parser())
+                    .contains(&*String::from_utf8_lossy(text))
+            }),
+            Self::Has(relative) => {
+                let Some(tag) = ctx.node(handle).and_then(Node::as_tag) else {
+                    return false;
+                };
+                let parser = ctx.parser();
+                let matches_subtree = |h: NodeHandle| {
+                    relative.matches_in(ctx, h)
+                        || ctx.node(h).and_then(Node::as_tag).is_some_and(|t| {
+                            t.descendants(parser).any(|d| relative.matches_in(ctx, d))
+                        })
+                };
+
+                let previous = ctx.set_scope(Some(handle));
+                let found = tag.descendants(parser).any(|h| relative.matches_in(ctx, h))
+                    || (relative.is_sibling_relative()
+                        && ctx.following_siblings(handle).any(matches_subtree));
+                ctx.set_scope(previous);
+
+                found
+            }
+            Self::Descendant(ancestor, this) => {
+                this.matches_in(ctx, handle)
+                    && std::iter::successors(ctx.parent(handle), |&h| ctx.parent(h))
+                        .any(|h| ancestor.matches_in(ctx, h))
+            }
+            Self::Parent(parent, this) => {
+                this.matches_in(ctx, handle)
+                    && ctx
+                        .parent(handle)
+                        .is_some_and(|h| parent.matches_in(ctx, h))
+            }
+            Self::AdjacentSibling(previous, this) => {
+                this.matches_in(ctx, handle)
+                    && ctx
+                        .previous_element_siblings(handle)
+                        .next()
+                        .is_some_and(|h| previous.matches_in(ctx, h))
+            }
+            Self::GeneralSibling(previous, this) => {
+                this.matches_in(ctx, handle)
+                    && ctx
+                        .previous_element_siblings(handle)
+                        .any(|h| previous.matches_in(ctx, h))
+            }
+            _ => ctx.node(handle).is_some_and(|node| self.matches(node)),
+        }
+    }
+
+    /// Checks if this relative selector can match elements outside of the subtree of its scope,
+    /// i.e. if it starts with a sibling combinator: `+ p` or `~ p`
+    fn is_sibling_relative(&self) -> bool {
+        match self {
+            Self::Or(a, b) => a.is_sibling_relative() || b.is_sibling_relative(),
+            Self::AdjacentSibling(left, _) | Self::GeneralSibling(left, _)
+                if matches!(**left, Self::Scope) =>
+            {
+                true
+            }
+            Self::Descendant(left, _)
+            | Self::Parent(left, _)
+            | Self::AdjacentSibling(left, _)
+            | Self::GeneralSibling(left, _) => left.is_sibling_relative(),
+            _ => false,
+        }
+    }
+
     /// Checks if the given node matches this selector
+    ///
+    /// Selectors that depend on the position of the node in the tree, such as combinators, `:root` or `:nth-child()`,
+    /// cannot be checked with only the node, and never match. This includes negations of them, so `:not(:root)` does not match either.
+    /// Use [`NodeHandle::matches`] to check these selectors.
     pub fn matches<'b>(&self, node: &Node<'b>) -> bool {
-        match self {
-            Self::Tag(tag) => node.as_tag().map_or(false, |t| t._name.as_bytes().eq(*tag)),
-            Self::Id(id) => node
+        self.matches_node(node) == Some(true)
+    }
+
+    /// Checks if the given node matches this selector, returning `None` if this depends on the position of the node in the tree
+    fn matches_node(&self, node: &Node<'_>) -> Option<bool> {
+        let matches = match self {
+            Self::Tag(tag) => node
                 .as_tag()
-                .map_or(false, |t| t._attributes.id == Some((*id).into())),
+                .is_some_and(|t| t._name.as_bytes() == &*unescape(tag)),
+            Self::Id(id) => node.as_tag().is_some_and(|t| {
+                t._attributes
+                    .id
+                    .as_ref()
+                    .is_some_and(|x| x.as_bytes() == &*unescape(id))
+            }),
             Self::Class(class) => node
                 .as_tag()
-                .map_or(false, |t| t._attributes.is_class_member(*class)),
-            Self::And(a, b) => a.matches(node) && b.matches(node),
-            Self::Or(a, b) => a.matches(node) || b.matches(node),
+                .is_some_and(|t| t._attributes.is_class_member(unescape(class))),
+            Self::And(a, b) => match (a.matches_node(node), b.matches_node(node)) {
+                (Some(false), _) | (_, Some(false)) => false,
+                (Some(true), Some(true)) => true,
+                _ => return None,
+            },
+            Self::Or(a, b) => return either(a.matches_node(node), b.matches_node(node)),
+            Self::Not(inner) => match node.as_tag() {
+                Some(_) => !inner.matches_node(node)?,
+                None => false,
+            },
+            Self::Is(list) => {
+                return list
+                    .iter()
+                    .map(|s| s.matches_node(node))
+                    .fold(Some(false), either)
+            }
             Self::All => true,
             Self::Attribute(attribute) => node
                 .as_tag()
-                .map_or(false, |t| t._attributes.get(*attribute).is_some()),
-            Self::AttributeValue(attribute, value) => {
-                check_attribute(node, attribute, value, |attr, value| attr == value)
+                .is_some_and(|t| t._attributes.get(*attribute).is_some()),
+            Self::AttributeValue(attribute, value, case_insensitive) => {
+                check_attribute(node, attribute, value, *case_insensitive, |attr, value| {
+                    attr == value
+                })
             }
-            Self::AttributeValueEndsWith(attribute, value) => {
-                check_attribute(node, attribute, value, |attr, value| attr.ends_with(value))
+            Self::AttributeValueEndsWith(attribute, value, case_insensitive) => {
+                check_attribute(node, attribute, value, *case_insensitive, |attr, value| {
+                    attr.ends_with(value)
+                })
             }
-            Self::AttributeValueStartsWith(attribute, value) => {
-                check_attribute(node, attribute, value, |attr, value| {
+            Self::AttributeValueStartsWith(attribute, value, case_insensitive) => {
+                check_attribute(node, attribute, value, *case_insensitive, |attr, value| {
                     attr.starts_with(value)
                 })
             }
-            Self::AttributeValueSubstring(attribute, value) => {
-                check_attribute(node, attribute, value, |attr, value| attr.contains(value))
+            Self::AttributeValueSubstring(attribute, value, case_insensitive) => {
+                check_attribute(node, attribute, value, *case_insensitive, |attr, value| {
+                    attr.contains(value)
+                })
+            }
+            Self::AttributeValueDashMatch(attribute, value, case_insensitive) => {
+                check_attribute(node, attribute, value, *case_insensitive, |attr, value| {
+                    attr.strip_prefix(value)
+                        .is_some_and(|rest| rest.is_empty() || rest.starts_with('-'))
+                })
             }
-            Self::AttributeValueWhitespacedContains(attribute, value) => {
-                check_attribute(node, attribute, value, |attr, value| {
+            Self::AttributeValueWhitespacedContains(attribute, value, case_insensitive) => {
+                check_attribute(node, attribute, value, *case_insensitive, |attr, value| {
                     attr.split_whitespace().any(|x| x == value)
                 })
             }
-            _ => false,
+            _ => return None,
+        };
+
+        Some(matches)
+    }
+}
+
+/// Combines two results of [`Selector::matches_node`] for a selector that matches if either of them does
+fn either(a: Option<bool>, b: Option<bool>) -> Option<bool> {
+    match (a, b) {
+        (Some(true), _) | (_, Some(true)) => Some(true),
+        (Some(false), Some(false)) => Some(false),
+        _ => None,
+    }
+}
+
+/// Resolves the CSS escapes in an identifier: `md\:flex` becomes `md:flex` and `\31 0` becomes `10`
+///
+/// This only allocates if the identifier contains a backslash.
+pub(crate) fn unescape(ident: &[u8]) -> Cow<'_, [u8]> {
+    if !ident.contains(&b'\\') {
+        return Cow::Borrowed(ident);
+    }
+
+    let mut out = Vec::with_capacity(ident.len());
+    let mut bytes = ident.iter().copied().peekable();
+
+    while let Some(c) = bytes.next() {
+        if c != b'\\' {
+            out.push(c);
+            continue;
+        }
+
+        let mut code_point = 0;
+        let mut hex_digits = 0;
+        while let Some(digit) = bytes
+            .peek()
+            .and_then(|&c| (c as char).to_digit(16))
+            .filter(|_| hex_digits < 6)
+        {
+            code_point = code_point * 16 + digit;
+            hex_digits += 1;
+            bytes.next();
         }
+
+        if hex_digits == 0 {
+            out.extend(bytes.next());
+            continue;
+        }
+
+        // a single space after a hex escape terminates it and is not part of the identifier
+        bytes.next_if_eq(&b' ');
+
+        let c = char::from_u32(code_point)
+            .filter(|&c| c != '\0')
+            .unwrap_or(char::REPLACEMENT_CHARACTER);
+        out.extend_from_slice(c.encode_utf8(&mut [0; 4]).as_bytes());
     }
+
+    Cow::Owned(out)
 }
 
-fn check_attribute<F>(node: &Node, attribute: &[u8], value: &[u8], callback: F) -> bool
+/// Checks whether `index` can be written as `a*n + b` for some non-negative integer `n`
+///
+/// The arithmetic is done in `i64`, so that extreme values like `n-2147483647` cannot overflow.
+fn nth_matches(a: i32, b: i32, index: i32) -> bool {
+    let (a, b, index) = (i64::from(a), i64::from(b), i64::from(index));
+    if a == 0 {
+        return index == b;
+    }
+
+    let diff = index - b;
+    diff % a == 0 && diff / a >= 0
+}
+
+fn check_attribute<F>(
+    node: &Node,
+    attribute: &[u8],
+    value: &[u8],
+    case_insensitive: bool,
+    callback: F,
+) -> bool
 where
     F: Fn(&str, &str) -> bool,
 {
-    node.as_tag().map_or(false, |t| {
-        t._attributes
-            .get(attribute)
-            .flatten()
-            .map_or(false, |attr| {
-                callback(&attr.as_utf8_str(), &String::from_utf8_lossy(value))
-            })
+    node.as_tag().is_some_and(|t| {
+        t._attributes.get(attribute).flatten().is_some_and(|attr| {
+            let attr = attr.as_utf8_str();
+            let value = String::from_utf8_lossy(value);
+
+            if case_insensitive {
+                callback(&attr.to_ascii_lowercase(), &value.to_ascii_lowercase())
+            } else {
+                callback(&attr, &value)
+            }
+        })
     })
 }
@@ -0,0 +1,121 @@
+use serde::ser::{Serialize, SerializeMap, SerializeSeq, SerializeStruct, Serializer};
+
+use crate::{Attributes, HTMLTag, Node, NodeHandle, Parser, VDom};
+
+/// A node together with the parser it belongs to, which implements [`Serialize`]
+///
+/// Nodes only store handles to their children, so the parser is needed to serialize the whole subtree.
+///
+/// Tags are serialized as `{ "type": "tag", "name": ..., "attributes": { ... }, "children": [ ... ] }`.
+/// Text, comment and CDATA nodes are serialized as `{ "type": "text" | "comment" | "cdata", "content": ... }`.
+///
+/// # Example
+/// ```
+/// let dom = tl::parse("<p id=\"a\">Hello</p>", Default::default()).unwrap();
+/// let node = dom.children()[0].get(dom.parser()).unwrap();
+/// let json = serde_json::to_string(&tl::SerializableNode::new(node, dom.parser())).unwrap();
+/// assert_eq!(
+///     json,
+///     r#"{"type":"tag","name":"p","attributes":{"id":"a"},"children":[{"type":"text","content":"Hello"}]}"#
+/// );
+/// ```
+#[derive(Debug, Clone, Copy)]
+pub struct SerializableNode<'p, 'a> {
+    node: &'p Node<'a>,
+    parser: &'p Parser<'a>,
+}
+
+impl<'p, 'a> SerializableNode<'p, 'a> {
+    /// Wraps a node so that it can be serialized
+    pub fn new(node: &'p Node<'a>, parser: &'p Parser<'a>) -> Self {
+        Self { node, parser }
+    }
+}
+
+impl Serialize for SerializableNode<'_, '_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let (kind, content) = match self.node {
+            Node::Tag(tag) => return SerializableTag::new(tag, self.parser).serialize(serializer),
+            Node::Raw(content) => ("text", content),
+            Node::Comment(content) => ("comment", content),
+            Node::CData(content) => ("cdata", content),
+        };
+
+        let mut state = serializer.serialize_struct("Node", 2)?;
+        state.serialize_field("type", kind)?;
+        state.serialize_field("content", content)?;
+        state.end()
+    }
+}
+
+/// An HTML tag together with the parser it belongs to, which implements [`Serialize`]
+///
+/// See [`SerializableNode`] for the format.
+#[derive(Debug, Clone, Copy)]
+pub struct SerializableTag<'p, 'a> {
+    tag: &'p HTMLTag<'a>,
+    parser: &'p Parser<'a>,
+}
+
+impl<'p, 'a> SerializableTag<'p, 'a> {
+    /// Wraps a tag so that it can be serialized
+    pub fn new(tag: &'p HTMLTag<'a>, parser: &'p Parser<'a>) -> Self {
+        Self { tag, parser }
+    }
+}
+
+impl Serialize for SerializableTag<'_, '_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut state = serializer.serialize_struct("Node", 4)?;
+        state.serialize_field("type", "tag")?;
+        state.serialize_field("name", &self.tag._name)?;
+        state.serialize_field("attributes", &self.tag._attributes)?;
+        state.serialize_field(
+            "children",
+            &SerializableChildren {
+                children: self.tag._children.as_slice(),
+                parser: self.parser,
+            },
+        )?;
+        state.end()
+    }
+}
+
+/// A list of node handles that is serialized as a sequence of nodes
+struct SerializableChildren<'p, 'a> {
+    children: &'p [NodeHandle],
+    parser: &'p Parser<'a>,
+}
+
+impl Serialize for SerializableChildren<'_, '_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut seq = serializer.serialize_seq(Some(self.children.len()))?;
+        // Handles that do not resolve (e.g. after manual mutation) are skipped
+        for node in self.children.iter().filter_map(|h| h.get(self.parser)) {
+            seq.serialize_element(&SerializableNode::new(node, self.parser))?;
+        }
+        seq.end()
+    }
+}
+
+/// Attributes are serialized as a map. Attributes without a value are serialized as `null`.
+impl Serialize for Attributes<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut map = serializer.serialize_map(Some(self.len()))?;
+        for (key, value) in self.iter() {
+            map.serialize_entry(&key, &value)?;
+        }
+        map.end()
+    }
+}
+
+/// A DOM is serialized as the sequence of its top level nodes
+impl Serialize for VDom<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        SerializableChildren {
+            children: self.children(),
+            parser: self.parser(),
+        }
+        .serialize(serializer)
+    }
+}
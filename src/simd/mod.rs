@@ -39,6 +39,7 @@ pub fn search_non_ident(haystack: &[u8]) -> Option<usize> {
 
 /// Searches for the first occurence in `haystack`
 #[inline]
+#[cfg_attr(not(feature = "__INTERNALS_DO_NOT_USE"), allow(dead_code))]
 pub fn find4(haystack: &[u8], needle: [u8; 4]) -> Option<usize> {
     decide!(
         nightly::find4(haystack, needle),
@@ -46,6 +47,17 @@ pub fn find4(haystack: &[u8], needle: [u8; 4]) -> Option<usize> {
     )
 }
 
+/// Searches for the first occurence of any of the bytes in `needle` in `haystack`
+///
+/// This is the same as [`find4`], but for any number of bytes.
+#[inline]
+pub fn find_any<const N: usize>(haystack: &[u8], needle: [u8; N]) -> Option<usize> {
+    decide!(
+        nightly::find_any(haystack, needle),
+        stable::find_any(haystack, needle)
+    )
+}
+
 /// Searches for the first occurence of `needle` in `haystack`
 #[inline]
 pub fn find(haystack: &[u8], needle: u8) -> Option<usize> {
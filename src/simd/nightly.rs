@@ -36,6 +36,44 @@ pub fn find(haystack: &[u8], needle: u8) -> Option<usize> {
     fallback::find(&haystack[i..], needle).map(|x| i + x)
 }
 
+/// Optimized function for finding one of `N` bytes in `haystack`
+pub fn find_any<const N: usize>(haystack: &[u8], needle: [u8; N]) -> Option<usize> {
+    #[inline(never)]
+    #[cold]
+    fn unlikely_find<const N: usize>(haystack: &[u8], needle: [u8; N]) -> Option<usize> {
+        fallback::find_multi(haystack, needle)
+    }
+
+    let len = haystack.len();
+    let ptr = haystack.as_ptr();
+    if len < 16 {
+        return unlikely_find(haystack, needle);
+    }
+
+    let mut i = 0;
+    let needles = needle.map(u8x16::splat);
+
+    while i <= len - 16 {
+        let mut bytes = [0; 16];
+        unsafe { ptr::copy_nonoverlapping(ptr.add(i), bytes.as_mut_ptr(), 16) };
+
+        let bytes = u8x16::from_array(bytes);
+
+        let or = needles
+            .iter()
+            .fold(mask8x16::splat(false), |acc, &n| acc | bytes.simd_eq(n))
+            .to_int();
+        let num = unsafe { std::mem::transmute::<i8x16, u128>(or) };
+        if num != 0 {
+            return Some(i + (num.trailing_zeros() >> 3) as usize);
+        }
+
+        i += 16;
+    }
+
+    fallback::find_multi(&haystack[i..], needle).map(|x| i + x)
+}
+
 /// Optimized function for finding one of 4 bytes in `haystack`
 pub fn find4(haystack: &[u8], needle: [u8; 4]) -> Option<usize> {
     #[inline(never)]
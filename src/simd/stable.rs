@@ -17,9 +17,9 @@ pub fn find_multi<const N: usize>(haystack: &[u8], needle: [u8; N]) -> Option<us
         index = i * 16;
         let mut mask = 0u16;
 
-        for (j, &byte) in chunk.into_iter().enumerate() {
-            for k in 0..N {
-                mask |= ((byte == needle[k]) as u16) << j;
+        for (j, &byte) in chunk.iter().enumerate() {
+            for &n in needle.iter() {
+                mask |= ((byte == n) as u16) << j;
             }
         }
 
@@ -32,6 +32,16 @@ pub fn find_multi<const N: usize>(haystack: &[u8], needle: [u8; N]) -> Option<us
     fallback::find_multi(&haystack[index..], needle).map(|x| x + index)
 }
 
+/// Stable function for finding any byte in `haystack`, for inputs where a match is expected within the first few bytes
+///
+/// Unlike [`find_multi`], this does not process the input in chunks, which is faster for short inputs such as attribute names.
+#[inline]
+pub fn find_any<const N: usize>(haystack: &[u8], needle: [u8; N]) -> Option<usize> {
+    haystack
+        .iter()
+        .position(|&c| needle.iter().fold(false, |acc, &n| acc | (c == n)))
+}
+
 /// Optimized, stable function for finding a byte in `haystack`
 pub fn find(haystack: &[u8], needle: u8) -> Option<usize> {
     let mut index = 0;
@@ -40,7 +50,7 @@ pub fn find(haystack: &[u8], needle: u8) -> Option<usize> {
         index = i * 16;
         let mut mask = 0u16;
 
-        for (j, &byte) in chunk.into_iter().enumerate() {
+        for (j, &byte) in chunk.iter().enumerate() {
             mask |= ((byte == needle) as u16) << j;
         }
 
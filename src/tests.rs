@@ -1,5 +1,5 @@
 use crate::{parse, parse_owned, Bytes};
-use crate::{parser::*, HTMLTag, Node};
+use crate::{parser::*, HTMLTag, Node, ParseError, VDom, Visitor, VisitorMut};
 
 fn force_as_tag<'a, 'b>(actual: &'a Node<'b>) -> &'a HTMLTag<'b> {
     match actual {
@@ -126,6 +126,54 @@ fn html5() {
     assert_eq!(dom.children().len(), 1)
 }
 
+#[test]
+fn html401() {
+    fn version(doctype: &str) -> Option<HTMLVersion> {
+        let input = format!("{doctype}\n<p>hello</p>");
+        let dom = parse(&input, ParserOptions::default()).unwrap();
+        assert_eq!(dom.outer_html(), "\n<p>hello</p>");
+        dom.version()
+    }
+
+    assert_eq!(
+        version(
+            r#"<!DOCTYPE HTML PUBLIC "-//W3C//DTD HTML 4.01//EN" "http://www.w3.org/TR/html4/strict.dtd">"#
+        ),
+        Some(HTMLVersion::StrictHTML401)
+    );
+    assert_eq!(
+        version(
+            r#"<!DOCTYPE HTML PUBLIC "-//W3C//DTD HTML 4.01 Transitional//EN" "http://www.w3.org/TR/html4/loose.dtd">"#
+        ),
+        Some(HTMLVersion::TransitionalHTML401)
+    );
+    assert_eq!(
+        version(
+            r#"<!DOCTYPE HTML PUBLIC "-//W3C//DTD HTML 4.01 Frameset//EN" "http://www.w3.org/TR/html4/frameset.dtd">"#
+        ),
+        Some(HTMLVersion::FramesetHTML401)
+    );
+    assert_eq!(
+        version("<!doctype html public '-//w3c//dtd html 4.01//en'>"),
+        Some(HTMLVersion::StrictHTML401)
+    );
+    assert_eq!(
+        version(r#"<!DOCTYPE html PUBLIC "" "http://www.w3.org/TR/html4/loose.dtd">"#),
+        Some(HTMLVersion::TransitionalHTML401)
+    );
+    assert_eq!(
+        version(r#"<!DOCTYPE html SYSTEM "about:legacy-compat">"#),
+        Some(HTMLVersion::HTML5)
+    );
+    assert_eq!(
+        version(
+            r#"<!DOCTYPE html PUBLIC "-//W3C//DTD XHTML 1.0 Strict//EN" "http://www.w3.org/TR/xhtml1/DTD/xhtml1-strict.dtd">"#
+        ),
+        None
+    );
+    assert_eq!(version("<!DOCTYPE svg>"), None);
+}
+
 #[test]
 fn ignore_void_closing_tags() {
     let input = r#"
@@ -161,7 +209,7 @@ pub fn children_mut() {
 
     let mut children = child.children_mut();
     let top = children.top_mut();
-    let handle = top[0].clone();
+    let handle = top[0];
     let node = handle.get_mut(dom.parser_mut()).unwrap();
     *node = Node::Raw("Hello".into());
 
@@ -171,7 +219,7 @@ pub fn children_mut() {
 #[test]
 fn nested_inner_text() {
     let dom = parse(
-        "<p>hello <p>nested element</p></p>",
+        "<div>hello <div>nested element</div></div>",
         ParserOptions::default(),
     )
     .unwrap();
@@ -186,8 +234,7 @@ fn nested_inner_text() {
 fn owned_dom() {
     let owned_dom = {
         let input = String::from("<p id=\"test\">hello</p>");
-        let dom = unsafe { parse_owned(input, ParserOptions::default()).unwrap() };
-        dom
+        unsafe { parse_owned(input, ParserOptions::default()).unwrap() }
     };
 
     let dom = owned_dom.get_ref();
@@ -219,6 +266,52 @@ fn move_owned() {
     assert_eq!(el.inner_text(parser), "hello");
 }
 
+#[test]
+fn shared_dom_clone() {
+    let original = {
+        let input = String::from(r#"<p id="test">hello <b>world</b></p>"#);
+        crate::parse_shared(input, ParserOptions::default().track_ids()).unwrap()
+    };
+    let mut copy = original.clone();
+    assert!(std::sync::Arc::ptr_eq(original.source(), copy.source()));
+
+    copy.with_mut(|dom| {
+        let node = dom.get_element_by_id("test").unwrap();
+        let tag = node
+            .get_mut(dom.parser_mut())
+            .unwrap()
+            .as_tag_mut()
+            .unwrap();
+        tag.set_attribute("class", "greeting");
+        tag.children_mut().top_mut().remove(1);
+    });
+
+    assert_eq!(
+        original.get_ref().outer_html(),
+        r#"<p id="test">hello <b>world</b></p>"#
+    );
+
+    let copy = std::thread::spawn(move || {
+        drop(original);
+        copy
+    })
+    .join()
+    .unwrap();
+
+    let dom = copy.get_ref();
+    assert_eq!(
+        dom.outer_html(),
+        r#"<p id="test" class="greeting">hello </p>"#
+    );
+    let el = force_as_tag(
+        dom.get_element_by_id("test")
+            .unwrap()
+            .get(dom.parser())
+            .unwrap(),
+    );
+    assert_eq!(el.inner_text(dom.parser()), "hello ");
+}
+
 #[test]
 fn with() {
     let input = r#"<p>hello <span>whats up</span></p>"#;
@@ -229,7 +322,7 @@ fn with() {
     let tag = dom
         .nodes()
         .iter()
-        .find(|x| x.as_tag().map_or(false, |x| x.name() == "span"));
+        .find(|x| x.as_tag().is_some_and(|x| x.name() == "span"));
 
     assert_eq!(
         tag.map(|tag| tag.inner_text(parser)),
@@ -251,7 +344,7 @@ fn dom_nodes() {
     let element = dom
         .nodes()
         .iter()
-        .find(|x| x.as_tag().map_or(false, |x| x.name().eq("a")));
+        .find(|x| x.as_tag().is_some_and(|x| x.name().eq("a")));
 
     assert_eq!(element.map(|x| x.inner_text(parser)), Some("nested".into()));
 }
@@ -343,6 +436,34 @@ mod simd {
         assert_eq!(crate::simd::find4(b"ef ghijklmnopqrstu", NEEDLE), None);
     }
 
+    #[test]
+    fn string_search_any() {
+        const NEEDLE: [u8; 5] = [b' ', b'\n', b'=', b'/', b'>'];
+
+        assert_eq!(crate::simd::find_any(b"", NEEDLE), None);
+        assert_eq!(crate::simd::find_any(b"href", NEEDLE), None);
+        assert_eq!(crate::simd::find_any(b"href=", NEEDLE), Some(4));
+        assert_eq!(crate::simd::find_any(b"stroke-linejoin", NEEDLE), None);
+        assert_eq!(crate::simd::find_any(b"stroke-linejoin/", NEEDLE), Some(15));
+        assert_eq!(
+            crate::simd::find_any(b"stroke-linejoin\n", NEEDLE),
+            Some(15)
+        );
+        assert_eq!(
+            crate::simd::find_any(b"stroke-dashoffset >", NEEDLE),
+            Some(17)
+        );
+        assert_eq!(
+            crate::simd::find_any(b"abcdefghijklmnopqrstuvwxyz>", NEEDLE),
+            Some(26)
+        );
+        assert_eq!(
+            crate::simd::find_any(b"abcdefghijklmnopqrstuvwxyz", NEEDLE),
+            None
+        );
+        assert_eq!(crate::simd::find_any(b"abc", [b'c']), Some(2));
+    }
+
     #[test]
     #[rustfmt::skip]
     fn search_non_ident() {
@@ -389,6 +510,93 @@ mod bytes {
         assert_eq!(Bytes::from("hello").as_utf8_str(), "hello");
     }
 
+    #[test]
+    fn as_ref() {
+        fn len(data: impl AsRef<[u8]>) -> usize {
+            data.as_ref().len()
+        }
+
+        let borrowed = Bytes::from("hello");
+        let mut owned = Bytes::new();
+        owned.set("hi").unwrap();
+
+        assert_eq!(len(&borrowed), 5);
+        assert_eq!(len(owned), 2);
+        assert_eq!(borrowed.as_ref(), b"hello");
+    }
+
+    #[test]
+    fn into_owned() {
+        let mut owned = Bytes::new();
+        owned.set("hello").unwrap();
+        let ptr = owned.as_ptr();
+        let owned = owned.into_owned();
+        assert_eq!(owned, "hello");
+        assert_eq!(owned.as_ptr(), ptr);
+
+        let values: Vec<Bytes<'static>> = {
+            let input = String::from("<a href=\"/a\"></a><a href=\"/b\"></a>");
+            let dom = crate::parse(&input, Default::default()).unwrap();
+            dom.nodes()
+                .iter()
+                .filter_map(|node| node.as_tag()?.attributes().get("href").flatten())
+                .map(|href| href.clone().into_owned())
+                .collect()
+        };
+
+        assert_eq!(values, ["/a", "/b"]);
+        assert!(values.iter().all(|b| b.as_bytes_borrowed().is_none()));
+    }
+
+    #[test]
+    fn trim() {
+        let input = String::from(" \t value\n");
+        let bytes = Bytes::from(input.as_str());
+        let trimmed = bytes.trim();
+        assert_eq!(trimmed, "value");
+        assert_eq!(trimmed.as_ptr(), input[3..].as_ptr());
+
+        let mut owned = Bytes::new();
+        owned.set("  value  ").unwrap();
+        let trimmed = owned.trim();
+        drop(owned);
+        assert_eq!(trimmed, "value");
+
+        assert_eq!(Bytes::from("   ").trim(), "");
+    }
+
+    #[test]
+    fn eq_ignore_ascii_case() {
+        let bytes = Bytes::from("UTF-8");
+        assert!(bytes.eq_ignore_ascii_case(b"utf-8"));
+        assert!(!bytes.eq_ignore_ascii_case(b"utf8"));
+    }
+
+    #[test]
+    fn to_str() {
+        assert_eq!(Bytes::from("hello").to_str(), Ok("hello"));
+
+        let invalid = Bytes::from(b"a\xffb" as &[u8]);
+        assert_eq!(invalid.to_str().unwrap_err().valid_up_to(), 1);
+        assert_eq!(invalid.try_as_utf8_str(), None);
+        assert_eq!(invalid.as_utf8_str_lossy(), "a\u{fffd}b");
+    }
+
+    #[test]
+    fn compare_with_str() {
+        let owned = Bytes::from("div");
+        let borrowed = &owned;
+
+        assert!(owned == "div");
+        assert!(owned == *"div");
+        assert!(borrowed == "div");
+        assert!("div" == owned);
+        assert!(*"div" == owned);
+        assert!(owned == b"div" as &[u8]);
+        assert!(owned != "span");
+        assert_eq!(owned, "div");
+    }
+
     #[test]
     fn clone_shallow() {
         // cloning a borrowed slice does not deep-clone
@@ -538,7 +746,7 @@ mod query_selector {
 
         // one child
         let dom = parse(
-            r#"<p><div class="z">PASS</div></p>"#,
+            r#"<section><div class="z">PASS</div></section>"#,
             ParserOptions::default(),
         )
         .unwrap();
@@ -561,7 +769,7 @@ mod query_selector {
 
         // nested
         let dom = parse(
-            r#"<p><div class="z"><div class="y">PASS</div></div></p>"#,
+            r#"<section><div class="z"><div class="y">PASS</div></div></section>"#,
             ParserOptions::default(),
         )
         .unwrap();
@@ -591,26 +799,593 @@ mod query_selector {
         let node_option = dom
             .query_selector(r#"meta[property="og:title"]"#)
             .and_then(|mut iter| iter.next());
-        let value = if let Some(node) = node_option {
-            Some(
-                node.get(parser)
-                    .unwrap()
-                    .as_tag()
-                    .unwrap()
-                    .attributes()
-                    .get("content")
-                    .flatten()
-                    .unwrap()
-                    .try_as_utf8_str()
-                    .unwrap()
-                    .to_string(),
-            )
-        } else {
-            None
-        };
+        let value = node_option.map(|node| {
+            node.get(parser)
+                .unwrap()
+                .as_tag()
+                .unwrap()
+                .attributes()
+                .get("content")
+                .flatten()
+                .unwrap()
+                .try_as_utf8_str()
+                .unwrap()
+                .to_string()
+        });
 
         assert_eq!(value, Some("hello".to_string()));
     }
+
+    fn select_texts(input: &str, selector: &str) -> Vec<String> {
+        let dom = parse(input, ParserOptions::default()).unwrap();
+        let parser = dom.parser();
+        dom.query_selector(selector)
+            .unwrap()
+            .map(|handle| handle.get(parser).unwrap().inner_text(parser).into_owned())
+            .collect()
+    }
+
+    #[test]
+    fn escaped_identifiers() {
+        let input = r#"<div class="md:flex w-[100px]" id="my:id">a</div><p class="foo.bar">b</p><p class="10">c</p><p class="a b">d</p>"#;
+        assert_eq!(select_texts(input, r".md\:flex"), ["a"]);
+        assert_eq!(select_texts(input, r".w-\[100px\]"), ["a"]);
+        assert_eq!(select_texts(input, r"#my\:id"), ["a"]);
+        assert_eq!(select_texts(input, r"#my\3A id"), ["a"]);
+        assert_eq!(select_texts(input, r".foo\.bar"), ["b"]);
+        assert_eq!(select_texts(input, r"p.\31 0"), ["c"]);
+        assert_eq!(select_texts(input, r"\64 iv"), ["a"]);
+        // the space after a hex escape is part of the escape, the second one is a combinator
+        assert_eq!(select_texts(input, r"body .\31 0").len(), 0);
+        assert!(select_texts(input, r".a\ b").is_empty());
+        assert!(crate::parse_query_selector(r".foo\").is_none());
+    }
+
+    #[test]
+    fn namespaced_tag_names() {
+        // like in browsers, a colon in a tag name must be escaped, otherwise it starts a pseudo-class
+        let input = "<svg:rect>a</svg:rect><svg>b</svg>";
+        assert_eq!(select_texts(input, r"svg\:rect"), ["a"]);
+        assert_eq!(
+            crate::parse_query_selector_checked("svg:rect")
+                .unwrap_err()
+                .kind,
+            crate::errors::SelectorParseErrorKind::UnknownPseudoClass
+        );
+    }
+
+    #[test]
+    fn descendant_and_child_combinator() {
+        let input = "<div><p>a</p><section><p>b</p></section></div><p>c</p>";
+        assert_eq!(select_texts(input, "div p"), ["a", "b"]);
+        assert_eq!(select_texts(input, "div > p"), ["a"]);
+        assert_eq!(select_texts(input, "div > section > p"), ["b"]);
+    }
+
+    #[test]
+    fn adjacent_sibling_combinator() {
+        let input = "<div>a</div><p>b</p><p>c</p><span>d</span>";
+        assert_eq!(select_texts(input, "div + p"), ["b"]);
+        assert_eq!(select_texts(input, "p + p"), ["c"]);
+        assert_eq!(select_texts(input, "div + span"), Vec::<String>::new());
+        assert_eq!(select_texts(input, "div+p"), ["b"]);
+    }
+
+    #[test]
+    fn adjacent_sibling_combinator_chain() {
+        let input = "<div>a</div><p>b</p><span>c</span><p>d</p><span>e</span>";
+        assert_eq!(select_texts(input, "div + p + span"), ["c"]);
+    }
+
+    #[test]
+    fn general_sibling_combinator() {
+        let input = "<p>a</p><h2>b</h2><p>c</p><span>d</span><h2>e</h2><p>f</p>";
+        assert_eq!(select_texts(input, "h2 ~ p"), ["c", "f"]);
+        assert_eq!(select_texts(input, "h2~span"), ["d"]);
+        assert_eq!(select_texts(input, "span ~ h2 + p"), ["f"]);
+        assert_eq!(select_texts(input, "div ~ p"), Vec::<String>::new());
+    }
+
+    #[test]
+    fn general_sibling_combinator_same_parent() {
+        let input = "<div><h2>a</h2></div><p>b</p><div><p>c</p><h2>d</h2><p>e</p></div>";
+        assert_eq!(select_texts(input, "h2 ~ p"), ["e"]);
+        assert_eq!(select_texts(input, "[class~=x] ~ p, div ~ p"), ["b"]);
+    }
+
+    #[test]
+    fn not_pseudo_class() {
+        let input = r#"<div class="hidden">a</div><div>b</div><a rel="x">c</a><a>d</a>"#;
+        assert_eq!(select_texts(input, "div:not(.hidden)"), ["b"]);
+        assert_eq!(select_texts(input, "a:not([rel])"), ["d"]);
+        assert_eq!(select_texts(input, "div:not(:not(.hidden))"), ["a"]);
+        assert_eq!(select_texts(input, ":not(div, a[rel])"), ["d"]);
+        assert_eq!(select_texts(input, "div:not( .hidden )"), ["b"]);
+    }
+
+    #[test]
+    fn selector_matches_without_context() {
+        let dom = parse(r#"<div class="a">x</div>"#, ParserOptions::default()).unwrap();
+        let node = &dom.nodes()[0];
+        let matches = |selector| crate::parse_query_selector(selector).unwrap().matches(node);
+
+        assert!(matches("div.a"));
+        assert!(matches("div:not(span)"));
+        assert!(matches(":is(span, .a)"));
+        assert!(matches("span, div"));
+        assert!(matches(":root, div"));
+
+        // selectors that depend on the position in the tree are never matched, even when negated
+        assert!(!matches(":root"));
+        assert!(!matches(":not(:root)"));
+        assert!(!matches("div:not(span > div)"));
+        assert!(!matches(":not(:first-child)"));
+        assert!(!matches(":not(:is(span, :root))"));
+        assert!(!matches(":not(span):not(:has(p))"));
+
+        // unless the rest of the selector already rules the node out
+        assert!(!matches("span:root"));
+        assert!(!matches(":not(div, :root)"));
+    }
+
+    #[test]
+    fn not_pseudo_class_invalid() {
+        let dom = parse("<div></div>", ParserOptions::default()).unwrap();
+        assert!(dom.query_selector("div:not(.a").is_none());
+        assert!(dom.query_selector("div:not()").is_none());
+        assert!(dom.query_selector("div:unknown").is_none());
+        assert!(dom.query_selector("div)").is_none());
+    }
+
+    #[test]
+    fn nth_child_pseudo_class() {
+        let input = "<ul><li>1</li><li>2</li> text <li>3</li><li>4</li><li>5</li><li>6</li></ul>";
+        assert_eq!(select_texts(input, "li:nth-child(2n+1)"), ["1", "3", "5"]);
+        assert_eq!(select_texts(input, "li:nth-child(odd)"), ["1", "3", "5"]);
+        assert_eq!(select_texts(input, "li:nth-child(even)"), ["2", "4", "6"]);
+        assert_eq!(select_texts(input, "li:nth-child(3)"), ["3"]);
+        assert_eq!(select_texts(input, "li:nth-child(0n+2)"), ["2"]);
+        assert_eq!(select_texts(input, "li:nth-child(-n+3)"), ["1", "2", "3"]);
+        assert_eq!(
+            select_texts(input, "li:nth-child( 2n + 1 )"),
+            ["1", "3", "5"]
+        );
+        assert_eq!(select_texts(input, "li:nth-child(3n-1)"), ["2", "5"]);
+        assert_eq!(select_texts(input, "li:nth-child(n)").len(), 6);
+        assert_eq!(select_texts(input, "li:nth-child(-2n+5)"), ["1", "3", "5"]);
+        assert_eq!(select_texts(input, "li:first-child"), ["1"]);
+        assert_eq!(select_texts(input, "li:not(:first-child)").len(), 5);
+    }
+
+    #[test]
+    fn nth_child_pseudo_class_invalid() {
+        let dom = parse("<div></div>", ParserOptions::default()).unwrap();
+        assert!(dom.query_selector("div:nth-child()").is_none());
+        assert!(dom.query_selector("div:nth-child(foo)").is_none());
+        assert!(dom.query_selector("div:nth-child(2n+)").is_none());
+        assert!(dom.query_selector("div:nth-child(2n 1)").is_none());
+    }
+
+    #[test]
+    fn nth_child_pseudo_class_extreme_values() {
+        let input = "<ul><li>1</li><li>2</li><li>3</li></ul>";
+        assert_eq!(select_texts(input, "li:nth-child(n-2147483647)").len(), 3);
+        assert_eq!(select_texts(input, "li:nth-child(-n+2147483647)").len(), 3);
+        assert_eq!(select_texts(input, "li:nth-child(2147483647n+1)"), ["1"]);
+        assert_eq!(
+            select_texts(input, "li:nth-child(-2147483647n-2147483647)").len(),
+            0
+        );
+        assert_eq!(select_texts(input, "li:nth-of-type(n-2147483647)").len(), 3);
+        // out-of-range numbers are clamped
+        assert_eq!(select_texts(input, "li:nth-child(99999999999n+2)"), ["2"]);
+        assert_eq!(select_texts(input, "li:nth-child(-n+99999999999)").len(), 3);
+        assert_eq!(select_texts(input, "li:nth-child(99999999999)").len(), 0);
+    }
+
+    #[test]
+    fn is_pseudo_class() {
+        let input =
+            "<h1><a>a</a></h1><h2><a>b</a></h2><div><a>c</a></div><h3><span><a>d</a></span></h3>";
+        assert_eq!(select_texts(input, ":is(h1, h2, h3) a"), ["a", "b", "d"]);
+        assert_eq!(select_texts(input, ":where(h1, h2, h3) a"), ["a", "b", "d"]);
+        assert_eq!(
+            select_texts(input, ":is(h1, h2) > :is(a, span)"),
+            ["a", "b"]
+        );
+        assert_eq!(select_texts(input, ":is(div > a, h3 a)"), ["c", "d"]);
+        assert_eq!(select_texts(input, "h1 + :is(h2, h3) a"), ["b"]);
+        assert_eq!(select_texts(input, "a:is( :not(h1 a) )"), ["b", "c", "d"]);
+        assert!(crate::parse_query_selector(":is()").is_none());
+        assert!(crate::parse_query_selector(":is(a").is_none());
+    }
+
+    #[test]
+    fn has_pseudo_class() {
+        let input = "<article><h2>a</h2></article><article><p>b</p></article><article><div><h2>c</h2></div></article>";
+        assert_eq!(select_texts(input, "article:has(h2)"), ["a", "c"]);
+        assert_eq!(select_texts(input, "article:has(> h2)"), ["a"]);
+        assert_eq!(select_texts(input, "article:has(div h2, p)"), ["b", "c"]);
+        assert_eq!(select_texts(input, "article:not(:has(h2))"), ["b"]);
+
+        let input =
+            r#"<ul><li><a href="/">a</a></li><li><span><a>b</a></span></li><li>c</li></ul>"#;
+        assert_eq!(select_texts(input, "li:has(> a)"), ["a"]);
+        assert_eq!(select_texts(input, "li:has(a)"), ["a", "b"]);
+        assert_eq!(select_texts(input, "li:has(> span > a)"), ["b"]);
+        assert_eq!(select_texts(input, "li:has(+ li:has(span))"), ["a"]);
+        assert_eq!(
+            select_texts(input, "li:has(~ li > a)"),
+            Vec::<String>::new()
+        );
+        assert_eq!(select_texts(input, "ul:has(li:has(> a[href]))").len(), 1);
+
+        assert!(crate::parse_query_selector("li:has()").is_none());
+        assert!(crate::parse_query_selector("li:has(> a").is_none());
+    }
+
+    #[test]
+    fn contains_pseudo_class() {
+        let input =
+            r#"<a href="/a">Download now</a><a href="/b">Read <b>more</b></a><p>Download</p>"#;
+        assert_eq!(
+            select_texts(input, r#"a:contains("Download")"#),
+            ["Download now"]
+        );
+        assert_eq!(select_texts(input, "a:contains('d more')"), ["Read more"]);
+        assert_eq!(
+            select_texts(input, "a:contains(Download)"),
+            ["Download now"]
+        );
+        assert_eq!(select_texts(input, ":contains( Download )").len(), 2);
+        assert_eq!(select_texts(input, r#":contains("download")"#).len(), 0);
+        assert_eq!(select_texts(input, r#"a:contains("a)b")"#).len(), 0);
+        assert!(crate::parse_query_selector(r#"a:contains("x)"#).is_none());
+    }
+
+    #[test]
+    fn of_type_pseudo_classes() {
+        let input =
+            "<div><span>a</span><p>b</p><span>c</span><p>d</p> text <p>e</p><span>f</span></div>";
+        assert_eq!(select_texts(input, "p:first-of-type"), ["b"]);
+        assert_eq!(select_texts(input, "p:last-of-type"), ["e"]);
+        assert_eq!(select_texts(input, "span:first-of-type"), ["a"]);
+        assert_eq!(select_texts(input, "span:last-of-type"), ["f"]);
+        assert_eq!(select_texts(input, "div > :first-of-type"), ["a", "b"]);
+        assert_eq!(select_texts(input, "p:not(:first-of-type)"), ["d", "e"]);
+    }
+
+    #[test]
+    fn nth_of_type_pseudo_class() {
+        let input =
+            "<table><tr><th>h</th><td>1</td><td>2</td> <td>3</td><td>4</td><td>5</td></tr></table>";
+        assert_eq!(select_texts(input, "tr td:nth-of-type(2)"), ["2"]);
+        assert_eq!(select_texts(input, "tr :nth-child(2)"), ["1"]);
+        assert_eq!(select_texts(input, "td:nth-of-type(odd)"), ["1", "3", "5"]);
+        assert_eq!(select_texts(input, "td:nth-of-type(even)"), ["2", "4"]);
+        assert_eq!(select_texts(input, "td:nth-of-type(-n+2)"), ["1", "2"]);
+        assert_eq!(select_texts(input, "td:nth-of-type( 2n + 3 )"), ["3", "5"]);
+        assert_eq!(select_texts(input, "th:nth-of-type(1)"), ["h"]);
+        assert!(crate::parse_query_selector("td:nth-of-type(x)").is_none());
+    }
+
+    #[test]
+    fn only_pseudo_classes() {
+        let input = "<div id=one>a <p>b</p> <!-- c --></div><div id=two><p>c</p><p>d</p></div><div id=three><p>e</p><span>f</span></div>";
+        assert_eq!(select_texts(input, "p:only-child"), ["b"]);
+        assert_eq!(select_texts(input, "p:only-of-type"), ["b", "e"]);
+        assert_eq!(select_texts(input, "div > :only-of-type"), ["b", "e", "f"]);
+        assert_eq!(select_texts(input, "div:only-child").len(), 0);
+        assert_eq!(select_texts(input, "div:has(> p:only-child)").len(), 1);
+    }
+
+    #[test]
+    fn no_duplicate_matches() {
+        let input = r#"<div class="content"><div><p>a</p></div><p>b</p></div><span class="content">c</span>"#;
+        let dom = parse(input, ParserOptions::default()).unwrap();
+        let handles = |selector| {
+            dom.query_selector(selector)
+                .unwrap()
+                .map(|h| h.get_inner())
+                .collect::<Vec<_>>()
+        };
+        let assert_unique_and_ordered = |handles: &[u32]| {
+            assert!(handles.windows(2).all(|w| w[0] < w[1]), "{handles:?}");
+        };
+
+        let list = handles("div, .content");
+        assert_unique_and_ordered(&list);
+        assert_eq!(list.len(), 3);
+        assert_eq!(list, handles(".content, div"));
+
+        for selector in [
+            "div p",
+            "div div p, div p",
+            ":is(div, .content) p",
+            "div:has(p)",
+            "div:has(p), :has(div p)",
+            "div ~ span, .content",
+        ] {
+            assert_unique_and_ordered(&handles(selector));
+        }
+        assert_eq!(handles("div p").len(), 2);
+        assert_eq!(handles("div:has(p)").len(), 2);
+        assert_eq!(select_texts(input, "div div p, div p"), ["a", "b"]);
+    }
+
+    #[test]
+    fn combinators_in_tag_scope() {
+        let input = "<div id=outer><section><div><a>1</a><span><a>2</a></span></div><a>3</a></section></div><div><a>4</a></div>";
+        let dom = parse(input, ParserOptions::default()).unwrap();
+        let parser = dom.parser();
+        let texts = |id, selector| {
+            let scope = dom
+                .get_element_by_id(id)
+                .unwrap()
+                .get(parser)
+                .unwrap()
+                .as_tag()
+                .unwrap();
+            scope
+                .query_selector(parser, selector)
+                .unwrap()
+                .map(|h| h.get(parser).unwrap().inner_text(parser).into_owned())
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(texts("outer", "div > a"), ["1"]);
+        assert_eq!(texts("outer", "div a"), ["1", "2", "3"]);
+        assert_eq!(texts("outer", "section > a"), ["3"]);
+        assert_eq!(texts("outer", "div + a"), ["3"]);
+        assert_eq!(texts("outer", "a:first-child"), ["1", "2"]);
+
+        // Like in browsers, the ancestors of a matched node may lie outside of the scope
+        let inner = dom.query_selector_first("section > div").unwrap();
+        let inner = inner.get(parser).unwrap().as_tag().unwrap();
+        let matches = inner
+            .query_selector(parser, "#outer section a")
+            .unwrap()
+            .count();
+        assert_eq!(matches, 2);
+    }
+
+    #[test]
+    fn query_iterable_parent_and_siblings() {
+        use crate::queryselector::iterable::QueryIterable;
+
+        let input = "<div id=outer><section><div><a>1</a><span><a>2</a></span></div><a>3</a></section></div><div><a>4</a></div>";
+        let mut dom = parse(input, ParserOptions::default()).unwrap();
+        let handle = NodeHandle::new;
+        let outer = dom.get_element_by_id("outer").unwrap();
+
+        {
+            let parser = dom.parser();
+            let scope = outer.get(parser).unwrap().as_tag().unwrap();
+
+            // The children of the tag are the top level nodes of its scope
+            assert_eq!(scope.parent(parser, handle(1)), None);
+            assert_eq!(scope.siblings(parser, handle(1)), Some(&[handle(1)][..]));
+            assert_eq!(scope.parent(parser, handle(6)), Some(handle(5)));
+            assert_eq!(scope.parent(parser, handle(9)), Some(handle(8)));
+            assert_eq!(
+                scope.siblings(parser, handle(3)),
+                Some(&[handle(3), handle(5)][..])
+            );
+            assert_eq!(scope.parent(parser, handle(0)), None);
+            assert_eq!(scope.siblings(parser, handle(0)), None);
+            assert_eq!(scope.siblings(parser, handle(11)), None);
+
+            assert_eq!(dom.parent(parser, handle(1)), Some(handle(0)));
+            assert_eq!(dom.parent(parser, handle(11)), Some(handle(10)));
+            assert_eq!(dom.parent(parser, handle(0)), None);
+            assert_eq!(
+                dom.siblings(parser, handle(10)),
+                Some(&[handle(0), handle(10)][..])
+            );
+            assert_eq!(dom.siblings(parser, handle(100)), None);
+        }
+
+        // Nodes added after parsing are not stored in tree order
+        let parser = dom.parser_mut();
+        let span = parser.push_node(Node::Tag(HTMLTag::create("span")));
+        outer
+            .get_mut(parser)
+            .unwrap()
+            .as_tag_mut()
+            .unwrap()
+            .children_mut()
+            .append(span);
+
+        let parser = dom.parser();
+        assert_eq!(dom.parent(parser, span), Some(outer));
+        assert_eq!(dom.siblings(parser, span).map(<[_]>::len), Some(2));
+        assert_eq!(
+            dom.query_selector("#outer > span")
+                .unwrap()
+                .collect::<Vec<_>>(),
+            [span]
+        );
+    }
+
+    #[test]
+    fn root_pseudo_class() {
+        let input = "<!DOCTYPE html><html><head></head><body><p>a</p></body></html>";
+        let dom = parse(input, ParserOptions::default()).unwrap();
+        let body = dom
+            .query_selector(":root > body")
+            .unwrap()
+            .collect::<Vec<_>>();
+        assert_eq!(body.len(), 1);
+        assert_eq!(
+            body[0].get(dom.parser()).unwrap().as_tag().unwrap().name(),
+            "body"
+        );
+        assert_eq!(dom.query_selector("body:root").unwrap().count(), 0);
+
+        // Without an `<html>` element, every top level element is a root
+        assert_eq!(select_texts("<p>a</p><div><p>b</p></div>", "p:root"), ["a"]);
+    }
+
+    #[test]
+    fn attribute_case_insensitive_flag() {
+        let input = r#"<input type="TEXT" value="a"><a href="HTTPS://x/A" class="B c">b</a>"#;
+        let count = |selector| {
+            let dom = parse(input, ParserOptions::default()).unwrap();
+            let count = dom.query_selector(selector).unwrap().count();
+            count
+        };
+
+        assert_eq!(count(r#"[type="text"]"#), 0);
+        assert_eq!(count(r#"[type="text" i]"#), 1);
+        assert_eq!(count(r#"[type=text I]"#), 1);
+        assert_eq!(count(r#"[type="text" s]"#), 0);
+        assert_eq!(count(r#"[href^="https" i]"#), 1);
+        assert_eq!(count(r#"[href$='/a' i]"#), 1);
+        assert_eq!(count(r#"[href*="//X/"i]"#), 1);
+        assert_eq!(count(r#"[class~="b" i]"#), 1);
+        assert_eq!(count(r#"[class~="b"]"#), 0);
+    }
+
+    #[test]
+    fn attribute_dash_match() {
+        let input = r#"<p lang="en">a</p><p lang="en-US">b</p><p lang="english">c</p><p lang="EN-gb">d</p><p>e</p>"#;
+        assert_eq!(select_texts(input, "[lang|=en]"), ["a", "b"]);
+        assert_eq!(select_texts(input, r#"p[lang|="en" i]"#), ["a", "b", "d"]);
+        assert_eq!(select_texts(input, "[lang|=en-US]"), ["b"]);
+    }
+
+    #[test]
+    fn query_selector_first() {
+        let dom = parse(
+            r#"<p>a</p><div><p class="x">b</p><p class="x">c</p></div>"#,
+            ParserOptions::default(),
+        )
+        .unwrap();
+        let parser = dom.parser();
+        let first = dom.query_selector_first(".x").unwrap();
+        assert_eq!(first.get(parser).unwrap().inner_text(parser), "b");
+        assert_eq!(dom.query_selector_first(".y"), None);
+        assert_eq!(dom.query_selector_first("p:unknown"), None);
+
+        let div = dom
+            .query_selector_first("div")
+            .unwrap()
+            .get(parser)
+            .unwrap()
+            .as_tag()
+            .unwrap();
+        let first = div.query_selector_first(parser, "p").unwrap();
+        assert_eq!(first.get(parser).unwrap().inner_text(parser), "b");
+        assert_eq!(div.query_selector_first(parser, "span"), None);
+    }
+
+    #[test]
+    fn node_handle_matches() {
+        let dom = parse(
+            r#"<ul><li class="a" data-x>1</li><li>2</li></ul>"#,
+            ParserOptions::default(),
+        )
+        .unwrap();
+        let parser = dom.parser();
+        let first = dom.query_selector_first("li").unwrap();
+
+        assert!(first.matches(parser, ".a[data-x]"));
+        assert!(first.matches(parser, "ul > li:first-child"));
+        assert!(!first.matches(parser, "li + li"));
+        assert!(!first.matches(parser, "li:"));
+        assert!(!NodeHandle::new(100).matches(parser, "*"));
+
+        let selector = crate::parse_query_selector("li + li").unwrap();
+        let matching = dom
+            .nodes()
+            .iter()
+            .enumerate()
+            .filter(|&(id, _)| NodeHandle::new(id as u32).matches_selector(parser, &selector))
+            .count();
+        assert_eq!(matching, 1);
+    }
+
+    #[test]
+    fn tag_matches_selector() {
+        let dom = parse(
+            r#"<ul><li class="a">1</li><li>2</li></ul><li class="a">3</li>"#,
+            ParserOptions::default(),
+        )
+        .unwrap();
+        let parser = dom.parser();
+
+        for (selector, count) in [("li.a", 2), ("ul > li", 2), ("li + li", 1), ("p", 0)] {
+            let selector = crate::parse_query_selector(selector).unwrap();
+            let tags = dom
+                .nodes()
+                .iter()
+                .enumerate()
+                .filter_map(|(id, node)| Some((NodeHandle::new(id as u32), node.as_tag()?)))
+                .filter(|(handle, tag)| tag.matches_selector(parser, *handle, &selector))
+                .count();
+
+            assert_eq!(tags, count);
+            assert_eq!(dom.query_selector_parsed(&selector).count(), count);
+        }
+
+        // the handle must point to the tag itself
+        let selector = crate::parse_query_selector("li").unwrap();
+        let li = HTMLTag::create("li");
+        let first = dom.query_selector_first("li").unwrap();
+        let first_tag = first.get(parser).unwrap().as_tag().unwrap();
+        assert!(first_tag.matches_selector(parser, first, &selector));
+        assert!(!li.matches_selector(parser, first, &selector));
+        assert!(!first_tag.matches_selector(parser, NodeHandle::new(0), &selector));
+    }
+
+    #[test]
+    fn closest() {
+        let dom = parse(
+            r#"<div class="menu"><ul><li><ul><li><a>x</a></li></ul></li></ul></div>"#,
+            ParserOptions::default(),
+        )
+        .unwrap();
+        let link = dom.query_selector_first("a").unwrap();
+        let outer_li = dom.query_selector_first("li").unwrap();
+        let inner_li = dom.query_selector_first("li li").unwrap();
+        let outer_ul = dom.query_selector_first("ul").unwrap();
+
+        assert_eq!(dom.closest(link, "li"), Some(inner_li));
+        assert_eq!(dom.closest(link, "a"), Some(link));
+        assert_eq!(dom.closest(link, ".menu > ul"), Some(outer_ul));
+        assert_eq!(dom.closest(inner_li, "ul > li:not(li li)"), Some(outer_li));
+        assert_eq!(dom.closest(link, "span"), None);
+        assert_eq!(dom.closest(link, "li:"), None);
+    }
+
+    #[test]
+    fn adjacent_sibling_combinator_skips_text() {
+        let input = "<section><h1>a</h1> text <!-- comment --><p>b</p></section>";
+        assert_eq!(select_texts(input, "h1 + p"), ["b"]);
+        assert_eq!(select_texts(input, "section > h1 + p"), ["b"]);
+    }
+
+    #[test]
+    fn selector_parse_errors() {
+        use crate::errors::{SelectorParseError, SelectorParseErrorKind::*};
+
+        let error = |selector| crate::parse_query_selector_checked(selector).unwrap_err();
+        let at = |offset, kind| SelectorParseError { offset, kind };
+
+        assert_eq!(error(""), at(0, UnexpectedEof));
+        assert_eq!(error("div >"), at(5, UnexpectedEof));
+        assert_eq!(error("[href"), at(5, Expected(']')));
+        assert_eq!(error("[href='x]"), at(9, Expected('\'')));
+        assert_eq!(error("[href~x]"), at(6, Expected('=')));
+        assert_eq!(error(":not(.a"), at(7, Expected(')')));
+        assert_eq!(error("div)"), at(3, UnexpectedCharacter(')')));
+        assert_eq!(error("a, ?"), at(3, UnexpectedCharacter('?')));
+        assert_eq!(error("a:hover"), at(2, UnknownPseudoClass));
+        assert_eq!(error(":nth-child(x)"), at(11, InvalidNth));
+
+        assert!(crate::parse_query_selector_checked("div > p.a, [href]").is_ok());
+        assert!(crate::parse_query_selector("div)").is_none());
+    }
 }
 
 #[test]
@@ -648,6 +1423,38 @@ fn comment() {
     );
 }
 
+#[test]
+fn comment_text() {
+    let dom = parse("<!--\n  hello  \n--><!---->text", Default::default()).unwrap();
+    let nodes = dom.nodes();
+    assert_eq!(nodes[0].comment_text(false).unwrap(), "\n  hello  \n");
+    assert_eq!(nodes[0].comment_text(true).unwrap(), "hello");
+    assert_eq!(nodes[1].comment_text(true).unwrap(), "");
+    assert_eq!(nodes[2].comment_text(true), None);
+
+    let unclosed = Node::Comment("<!-- a -".into());
+    assert_eq!(unclosed.comment_text(true).unwrap(), "a -");
+    let bare = Node::Comment("a".into());
+    assert_eq!(bare.comment_text(false).unwrap(), "a");
+}
+
+#[test]
+fn comments_iterator() {
+    let input = "<!-- a --><div><!--b--><p>x</p><!----></div><![CDATA[<!-- c -->]]>";
+    let dom = parse(input, Default::default()).unwrap();
+    assert_eq!(dom.comments().count(), 3);
+    assert_eq!(dom.comments().nth(1).unwrap(), "<!--b-->");
+    assert_eq!(dom.comment_contents().collect::<Vec<_>>(), [" a ", "b", ""]);
+
+    let dom = parse("<!-- a ---><p>x</p>", Default::default()).unwrap();
+    assert_eq!(dom.comment_contents().collect::<Vec<_>>(), [" a -"]);
+    assert_eq!(dom.outer_html(), "<!-- a ---><p>x</p>");
+
+    let options = ParserOptions::default().preserve_comments(false);
+    let dom = parse(input, options).unwrap();
+    assert_eq!(dom.comments().count(), 0);
+}
+
 #[test]
 fn tag_all_children() {
     fn assert_len(input: &str, len: usize) {
@@ -707,7 +1514,27 @@ fn self_closing_no_child() {
 }
 
 #[test]
-fn insert_attribute_owned() {
+fn self_closing_non_void() {
+    let dom = parse("<div/><span>x</span><p />", Default::default()).unwrap();
+    assert_eq!(dom.children().len(), 3);
+    assert_eq!(dom.outer_html(), "<div></div><span>x</span><p></p>");
+
+    let div = dom.nodes()[0].as_tag().unwrap();
+    assert_eq!(div.name(), "div");
+    assert_eq!(div.children().top().len(), 0);
+
+    let dom = parse(r#"<input disabled/><a href="/">a</a>"#, Default::default()).unwrap();
+    assert_eq!(dom.children().len(), 2);
+    let input = dom.nodes()[0].as_tag().unwrap();
+    assert!(input.attributes().contains("disabled"));
+
+    // without the slash, non-void tags still need an end tag
+    let dom = parse("<div><span>x</span>", Default::default()).unwrap();
+    assert_eq!(dom.children().len(), 1);
+}
+
+#[test]
+fn insert_attribute_owned() {
     // https://github.com/y21/tl/issues/27
     let mut attr = Attributes::new();
     let style = "some style".to_string();
@@ -715,6 +1542,165 @@ fn insert_attribute_owned() {
     assert_eq!(attr.get("style"), Some(Some(&"some style".into())));
 }
 
+#[test]
+fn clone_subtree() {
+    let mut dom = parse(
+        r#"<div><ul class="list"><li>a</li></ul></div>"#,
+        Default::default(),
+    )
+    .unwrap();
+    let div = dom.children()[0];
+    let ul = dom.query_selector_first("ul").unwrap();
+    let li = dom.query_selector_first("li").unwrap();
+    let nodes = dom.nodes().len();
+
+    let copy = dom.clone_subtree(ul).unwrap();
+    assert_eq!(dom.nodes().len(), nodes + 3);
+
+    let parser = dom.parser_mut();
+    let copy_li = copy.get(parser).unwrap().as_tag().unwrap().children().top()[0];
+    assert_ne!(copy_li, li);
+
+    let tag = copy.get_mut(parser).unwrap().as_tag_mut().unwrap();
+    tag.set_attribute("class", "copy");
+    assert!(copy_li.set_inner_html(parser, "b"));
+    let tag = div.get_mut(parser).unwrap().as_tag_mut().unwrap();
+    tag.children_mut().append(copy);
+
+    assert_eq!(
+        dom.outer_html(),
+        r#"<div><ul class="list"><li>a</li></ul><ul class="copy"><li>b</li></ul></div>"#
+    );
+    assert_eq!(dom.clone_subtree(NodeHandle::new(1000)), None);
+}
+
+#[test]
+fn wrap_node() {
+    let mut dom = parse(
+        r#"<img src="a.png"><p><img src="b.png"></p>"#,
+        Default::default(),
+    )
+    .unwrap();
+    let images = dom.query_selector("img").unwrap().collect::<Vec<_>>();
+
+    let top = dom.wrap(images[0], "a").unwrap();
+    let nested = dom.wrap(images[1], "a").unwrap();
+    assert_eq!(dom.children()[0], top);
+    assert_eq!(images[1].parent(dom.parser()), Some(nested));
+
+    let link = nested
+        .get_mut(dom.parser_mut())
+        .unwrap()
+        .as_tag_mut()
+        .unwrap();
+    link.set_attribute("href", "/b");
+
+    assert_eq!(
+        dom.outer_html(),
+        r#"<a><img src="a.png"></a><p><a href="/b"><img src="b.png"></a></p>"#
+    );
+    let p = dom.query_selector_first("p").unwrap();
+    let p = p.get(dom.parser()).unwrap().as_tag().unwrap();
+    assert_eq!(
+        p.outer_html_cow(dom.parser()),
+        r#"<p><a href="/b"><img src="b.png"></a></p>"#
+    );
+    assert_eq!(dom.wrap(NodeHandle::new(1000), "a"), None);
+}
+
+#[test]
+fn replace_with() {
+    let mut dom = parse(
+        "<script>a</script><div><b>1</b><script>b</script><i>2</i></div>",
+        Default::default(),
+    )
+    .unwrap();
+    let scripts = dom.query_selector("script").unwrap().collect::<Vec<_>>();
+    let div = dom.query_selector_first("div").unwrap();
+
+    let parser = dom.parser_mut();
+    let top = parser.push_node(Node::Raw("[top]".into()));
+    let nested = parser.push_node(Node::Tag(HTMLTag::create("span")));
+
+    assert!(dom.replace_with(scripts[0], top));
+    assert!(dom.replace_with(scripts[1], nested));
+    assert!(!dom.replace_with(scripts[1], top));
+
+    let parser = dom.parser();
+    let div = div.get(parser).unwrap().as_tag().unwrap();
+    assert_eq!(div.inner_html(parser), "<b>1</b><span></span><i>2</i>");
+    assert_eq!(dom.children()[0], top);
+    assert_eq!(
+        dom.outer_html(),
+        "[top]<div><b>1</b><span></span><i>2</i></div>"
+    );
+}
+
+#[test]
+fn structural_eq() {
+    fn eq(a: &str, b: &str, whitespace: WhitespaceMode) -> bool {
+        let a = parse(a, Default::default()).unwrap();
+        let b = parse(b, Default::default()).unwrap();
+        let node_a = a.children()[0].get(a.parser()).unwrap();
+        let node_b = b.children()[0].get(b.parser()).unwrap();
+        node_a.structural_eq_with(node_b, a.parser(), b.parser(), whitespace)
+    }
+    use WhitespaceMode::{Collapse, Exact};
+
+    assert!(eq(
+        r#"<a href="/" title="t">x</a>"#,
+        r#"<a title="t" href="/">x</a>"#,
+        Exact
+    ));
+    assert!(eq(
+        "<div>\n  <p>a\n b</p>\n</div>",
+        "<div><p>a b</p></div>",
+        Collapse
+    ));
+    assert!(!eq(
+        "<div>\n  <p>a\n b</p>\n</div>",
+        "<div><p>a b</p></div>",
+        Exact
+    ));
+    assert!(eq("<p><!-- x --></p>", "<p><!--  x --></p>", Collapse));
+    assert!(!eq("<p><!-- x --></p>", "<p></p>", Collapse));
+    assert!(!eq(
+        r#"<a href="/">x</a>"#,
+        r#"<a href="/" hidden>x</a>"#,
+        Collapse
+    ));
+    assert!(!eq("<a hidden>x</a>", r#"<a hidden="">x</a>"#, Collapse));
+    assert!(!eq("<p>a</p>", "<span>a</span>", Collapse));
+    assert!(!eq("<p>a<b></b></p>", "<p>a</p>", Collapse));
+    assert!(!eq("<p>a</p>", "<p><![CDATA[a]]></p>", Collapse));
+
+    // A transformed DOM compares equal to the re-parsed output
+    let mut dom = parse("<ul><li>a</li><li>b</li></ul>", Default::default()).unwrap();
+    let first = dom.query_selector_first("li").unwrap();
+    dom.detach(first);
+    let html = dom.outer_html();
+    let reparsed = parse(&html, Default::default()).unwrap();
+    let node = dom.children()[0].get(dom.parser()).unwrap();
+    let other = reparsed.children()[0].get(reparsed.parser()).unwrap();
+    assert!(node.structural_eq(other, dom.parser(), reparsed.parser()));
+}
+
+#[test]
+fn owned_bytes_from_string_and_vec() {
+    let from_string = Bytes::try_from(format!("{}-{}", "data", 1)).unwrap();
+    assert_eq!(from_string, "data-1");
+    assert!(from_string.as_bytes_borrowed().is_none());
+
+    let from_vec = Bytes::try_from(vec![b'a', 0xff]).unwrap();
+    assert_eq!(from_vec, &b"a\xff"[..]);
+    assert!(from_vec.as_bytes_borrowed().is_none());
+
+    let mut attr = Attributes::new();
+    attr.insert("title", Some(from_string.clone()));
+    drop(from_string);
+    assert_eq!(attr.get("title"), Some(Some(&"data-1".into())));
+}
+
 #[test]
 fn boundaries() {
     // https://github.com/y21/tl/issues/25
@@ -778,3 +1764,2059 @@ fn tag_raw_abrupt_stop() {
     let from_raw = first_tag.raw().try_as_utf8_str().unwrap();
     assert_eq!(from_raw, "<p>abcd</p");
 }
+
+#[test]
+fn inner_text_decoded() {
+    fn decoded(input: &str) -> String {
+        let dom = parse(input, Default::default()).unwrap();
+        dom.nodes()[0].inner_text_decoded(dom.parser()).into_owned()
+    }
+
+    assert_eq!(decoded("<p>a &amp; b</p>"), "a & b");
+    assert_eq!(decoded("<p>&lt;&gt;&quot;&apos;&nbsp;</p>"), "<>\"'\u{a0}");
+    assert_eq!(
+        decoded("<p>&#169; &#x2764; &#X41;</p>"),
+        "\u{a9} \u{2764} A"
+    );
+    assert_eq!(
+        decoded("<p>&#0; &#xD800; &#x110000;</p>"),
+        "\u{fffd} \u{fffd} \u{fffd}"
+    );
+    assert_eq!(
+        decoded("<p>&notareal; &amp &copy 2023</p>"),
+        "&notareal; & \u{a9} 2023"
+    );
+    assert_eq!(
+        decoded("<p>&apos &hellip &euro;</p>"),
+        "&apos &hellip \u{20ac}"
+    );
+    // Legacy references are matched as a prefix of a longer name, like browsers do
+    assert_eq!(
+        decoded("<p>&copyright &copy2023 &ampx; &notareal</p>"),
+        "\u{a9}right \u{a9}2023 &x; &notareal"
+    );
+    assert_eq!(decoded("<p>&#65 &#; &#x; & &</p>"), "A &#; &#x; & &");
+    assert_eq!(decoded("<p>a<b>&amp;</b>&lt;</p>"), "a&<");
+    assert_eq!(decoded("<p>&am<b>p;</b></p>"), "&amp;");
+
+    // Without references, the text is borrowed from the source
+    let dom = parse("<p>plain text</p>", Default::default()).unwrap();
+    let text = dom.nodes()[0].inner_text_decoded(dom.parser());
+    assert!(matches!(text, std::borrow::Cow::Borrowed("plain text")));
+
+    // inner_text is unchanged
+    let dom = parse("<p>&amp;</p>", Default::default()).unwrap();
+    assert_eq!(dom.nodes()[0].inner_text(dom.parser()), "&amp;");
+}
+
+#[test]
+fn cdata() {
+    let dom = parse("<svg><![CDATA[ x < y & z ]]></svg>", Default::default()).unwrap();
+    let parser = dom.parser();
+    let svg = dom.nodes()[0].as_tag().unwrap();
+    let cdata = svg.children().top()[0].get(parser).unwrap();
+
+    assert_eq!(cdata.as_cdata().unwrap().as_utf8_str(), " x < y & z ");
+    assert_eq!(svg.inner_text(parser), " x < y & z ");
+    assert_eq!(cdata.outer_html(parser), "<![CDATA[ x < y & z ]]>");
+    assert_eq!(svg.outer_html(parser), "<svg><![CDATA[ x < y & z ]]></svg>");
+    assert_eq!(dom.nodes().len(), 2);
+
+    // A `]` directly before the terminator belongs to the section
+    let dom = parse("<![CDATA[a]]]><p>b</p>", Default::default()).unwrap();
+    assert_eq!(dom.nodes()[0].as_cdata().unwrap().as_utf8_str(), "a]");
+    assert_eq!(dom.children().len(), 2);
+
+    let dom = parse("<![CDATA[]]>", Default::default()).unwrap();
+    assert_eq!(dom.nodes()[0].as_cdata().unwrap().as_utf8_str(), "");
+
+    // Unterminated sections extend to the end of the input
+    let dom = parse("<![CDATA[a<p>b", Default::default()).unwrap();
+    assert_eq!(dom.nodes().len(), 1);
+    assert_eq!(dom.nodes()[0].as_cdata().unwrap().as_utf8_str(), "a<p>b");
+}
+
+#[test]
+fn skip_whitespace_text_nodes() {
+    let input = r#"
+    <div id="a">
+        <div id="b">
+            <span>Hello</span> <span>World</span>
+            <pre> <b> x </b> </pre>
+        </div>
+    </div>
+    "#;
+
+    let dom = parse(input, ParserOptions::default()).unwrap();
+    let a = dom
+        .get_element_by_id("a")
+        .unwrap()
+        .get(dom.parser())
+        .unwrap();
+    assert_eq!(a.children().unwrap().top().len(), 3);
+    assert_eq!(dom.children().len(), 3);
+
+    let options = ParserOptions::default().skip_whitespace_text_nodes();
+    let dom = parse(input, options).unwrap();
+    let parser = dom.parser();
+    let a = dom.get_element_by_id("a").unwrap().get(parser).unwrap();
+    assert_eq!(a.children().unwrap().top().len(), 1);
+    assert_eq!(dom.children().len(), 1);
+
+    let b = dom.get_element_by_id("b").unwrap().get(parser).unwrap();
+    assert_eq!(b.children().unwrap().top().len(), 3);
+
+    // Whitespace inside of <pre> is kept
+    let pre = dom
+        .query_selector_first("pre")
+        .unwrap()
+        .get(parser)
+        .unwrap();
+    assert_eq!(pre.inner_html(parser), " <b> x </b> ");
+
+    // Tag names are compared case-insensitively, and whitespace is skipped again once the tag is closed
+    let dom = parse("<PRE> <b> x </b> </PRE> <div> </div>", options).unwrap();
+    let parser = dom.parser();
+    assert_eq!(dom.nodes()[0].inner_html(parser), " <b> x </b> ");
+    assert_eq!(dom.children().len(), 2);
+    let div = dom.children()[1].get(parser).unwrap();
+    assert_eq!(div.children().unwrap().top().len(), 0);
+
+    let dom = parse("<textarea><b> </textarea> <p> </p>", options).unwrap();
+    assert_eq!(dom.children().len(), 2);
+    assert_eq!(dom.nodes()[1].inner_html(dom.parser()), " ");
+
+    // Text that is not only whitespace is kept as is
+    let dom = parse("<p> a </p>", options).unwrap();
+    assert_eq!(dom.nodes()[0].inner_text(dom.parser()), " a ");
+
+    assert!(!options.is_tracking());
+    assert!(options.track_ids().is_tracking());
+    assert_eq!(
+        ParserOptions::from_raw_checked(options.to_raw()),
+        Some(options)
+    );
+}
+
+#[test]
+fn get_elements_by_tag_name() {
+    let input = r#"<div><P id="a">a</P><br><span><p id="b">b</p></span><img /><p id="c">c</div>"#;
+
+    for options in [
+        ParserOptions::default(),
+        ParserOptions::default().track_tags(),
+        ParserOptions::default().intern_tag_names(),
+    ] {
+        let dom = parse(input, options).unwrap();
+        let parser = dom.parser();
+        let ids = |name| {
+            dom.get_elements_by_tag_name(name)
+                .map(|handle| {
+                    let tag = handle.get(parser).unwrap().as_tag().unwrap();
+                    tag.attributes().id().unwrap().as_utf8_str().into_owned()
+                })
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(ids("p"), ["a", "b", "c"]);
+        assert_eq!(ids("P"), ["a", "b", "c"]);
+        assert_eq!(dom.get_elements_by_tag_name("br").count(), 1);
+        assert_eq!(dom.get_elements_by_tag_name("img").count(), 1);
+        assert_eq!(dom.get_elements_by_tag_name("DIV").count(), 1);
+        assert_eq!(dom.get_elements_by_tag_name("table").count(), 0);
+    }
+}
+
+#[test]
+fn interned_tag_names() {
+    let input = r#"<div><p>a</p><P>b</P><section><p>c</p><span>d</span></section></div>"#;
+    let options = ParserOptions::default().intern_tag_names();
+    let mut dom = parse(input, options).unwrap();
+
+    fn texts(dom: &VDom<'_>, selector: &str) -> Vec<String> {
+        dom.query_selector(selector)
+            .unwrap()
+            .map(|h| {
+                h.get(dom.parser())
+                    .unwrap()
+                    .inner_text(dom.parser())
+                    .into_owned()
+            })
+            .collect()
+    }
+
+    assert_eq!(texts(&dom, "p"), ["a", "c"]);
+    assert_eq!(texts(&dom, "P"), ["b"]);
+    assert_eq!(texts(&dom, "section > p, span"), ["c", "d"]);
+    assert_eq!(texts(&dom, "p:first-of-type"), ["a", "c"]);
+    assert!(texts(&dom, "table").is_empty());
+
+    // renaming a tag drops its id, so it is compared by name again
+    let span = dom.query_selector_first("span").unwrap();
+    let tag = span
+        .get_mut(dom.parser_mut())
+        .unwrap()
+        .as_tag_mut()
+        .unwrap();
+    *tag.name_mut() = "p".into();
+    assert_eq!(texts(&dom, "section > p"), ["c", "d"]);
+    assert_eq!(dom.get_elements_by_tag_name("span").count(), 0);
+
+    // nodes from another document get an id from this one
+    let section = dom.query_selector_first("section").unwrap();
+    assert!(section.set_inner_html(dom.parser_mut(), "<em>x</em><p>y</p>"));
+    assert_eq!(texts(&dom, "section > p"), ["y"]);
+    assert_eq!(texts(&dom, "em + p"), ["y"]);
+
+    // names beyond the size of the table are not interned
+    let input: String = (0..300).map(|i| format!("<x-{i}>{i}</x-{i}>")).collect();
+    let dom = parse(&input, options).unwrap();
+    assert_eq!(texts(&dom, "x-0"), ["0"]);
+    assert_eq!(texts(&dom, "x-299"), ["299"]);
+    assert_eq!(dom.get_elements_by_tag_name("X-299").count(), 1);
+}
+
+#[test]
+fn node_handle_parent() {
+    let dom = parse(
+        "<div><p>a<b>b</b></p><!-- c --></div><span>d</span>",
+        Default::default(),
+    )
+    .unwrap();
+    let parser = dom.parser();
+    let handle = |id| NodeHandle::new(id);
+
+    // div, p, "a", b, "b", comment, span, "d"
+    assert_eq!(handle(0).parent(parser), None);
+    assert_eq!(handle(1).parent(parser), Some(handle(0)));
+    assert_eq!(handle(2).parent(parser), Some(handle(1)));
+    assert_eq!(handle(3).parent(parser), Some(handle(1)));
+    assert_eq!(handle(4).parent(parser), Some(handle(3)));
+    assert_eq!(handle(5).parent(parser), Some(handle(0)));
+    assert_eq!(handle(6).parent(parser), None);
+    assert_eq!(handle(7).parent(parser), Some(handle(6)));
+    assert_eq!(handle(100).parent(parser), None);
+}
+
+#[test]
+fn node_handle_siblings() {
+    let dom = parse(
+        "<ul><li>a</li> x <!-- y --><li>b</li></ul><p>c</p>",
+        Default::default(),
+    )
+    .unwrap();
+    let parser = dom.parser();
+    let handle = |id| NodeHandle::new(id);
+
+    // ul, li, "a", " x ", comment, li, "b", p, "c"
+    let (first, text, comment, second) = (handle(1), handle(3), handle(4), handle(5));
+
+    assert_eq!(first.next_sibling(parser), Some(text));
+    assert_eq!(text.next_sibling(parser), Some(comment));
+    assert_eq!(second.next_sibling(parser), None);
+    assert_eq!(second.previous_sibling(parser), Some(comment));
+    assert_eq!(first.previous_sibling(parser), None);
+
+    assert_eq!(first.next_element_sibling(parser), Some(second));
+    assert_eq!(second.previous_element_sibling(parser), Some(first));
+    assert_eq!(text.previous_element_sibling(parser), Some(first));
+    assert_eq!(second.next_element_sibling(parser), None);
+
+    // Top level nodes are siblings of each other
+    assert_eq!(handle(0).next_sibling(parser), Some(handle(7)));
+    assert_eq!(handle(7).previous_element_sibling(parser), Some(handle(0)));
+    assert_eq!(handle(2).next_sibling(parser), None);
+    assert_eq!(handle(100).next_sibling(parser), None);
+}
+
+#[test]
+fn tag_descendants() {
+    let dom = parse(
+        "<div><p>a<b>b</b></p><!-- c --><br><span>d</span></div><i>e</i>",
+        Default::default(),
+    )
+    .unwrap();
+    let parser = dom.parser();
+    let div = dom.nodes()[0].as_tag().unwrap();
+
+    let handles = div.descendants(parser).collect::<Vec<_>>();
+    let expected = (1..=8).map(NodeHandle::new).collect::<Vec<_>>();
+    assert_eq!(handles, expected);
+    assert_eq!(handles.len(), div.children().all(parser).len());
+
+    let depths = div
+        .descendants_with_depth(parser)
+        .map(|(_, depth)| depth)
+        .collect::<Vec<_>>();
+    assert_eq!(depths, [1, 2, 2, 3, 1, 1, 1, 2]);
+
+    let br = dom.nodes()[6].as_tag().unwrap();
+    assert_eq!(br.descendants(parser).count(), 0);
+}
+
+#[test]
+fn node_handle_ancestors() {
+    let dom = parse(
+        "<html><body><div><p>a</p></div></body></html><p>b</p>",
+        Default::default(),
+    )
+    .unwrap();
+    let parser = dom.parser();
+    let handle = |id| NodeHandle::new(id);
+
+    // html, body, div, p, "a", p, "b"
+    assert_eq!(
+        handle(4).ancestors(parser).collect::<Vec<_>>(),
+        [handle(3), handle(2), handle(1), handle(0)]
+    );
+    assert_eq!(handle(0).ancestors(parser).count(), 0);
+    assert_eq!(handle(6).ancestors(parser).collect::<Vec<_>>(), [handle(5)]);
+    assert!(handle(4).ancestors(parser).any(|h| h == handle(2)));
+}
+
+#[test]
+fn create_and_append_nodes() {
+    let input = String::from("<body></body>");
+    let mut dom = parse(&input, Default::default()).unwrap();
+    let parser = dom.parser_mut();
+
+    let text = parser.push_node(Node::Raw("hi".into()));
+
+    let mut span = HTMLTag::create("span");
+    span.children_mut().append(text);
+    let span = parser.push_node(Node::Tag(span));
+
+    let mut div = HTMLTag::create("div");
+    div.attributes_mut().insert("class", Some("x"));
+    div.children_mut().append(span);
+    let div = parser.push_node(Node::Tag(div));
+
+    assert_eq!(
+        div.get(parser).unwrap().outer_html(parser),
+        r#"<div class="x"><span>hi</span></div>"#
+    );
+
+    let body = NodeHandle::new(0)
+        .get_mut(parser)
+        .unwrap()
+        .as_tag_mut()
+        .unwrap();
+    body.children_mut().append(div);
+
+    assert_eq!(
+        dom.outer_html(),
+        r#"<body><div class="x"><span>hi</span></div></body>"#
+    );
+    assert_eq!(
+        dom.query_selector("body > div.x > span").unwrap().count(),
+        1
+    );
+}
+
+#[test]
+fn detach_nodes() {
+    let mut dom = parse(
+        "<div><p>a</p><p>b</p>c</div><span>d</span>",
+        Default::default(),
+    )
+    .unwrap();
+
+    let first = dom.query_selector_first("p").unwrap();
+    assert!(dom.detach(first));
+    assert!(!dom.detach(first));
+    assert_eq!(dom.outer_html(), "<div><p>b</p>c</div><span>d</span>");
+
+    let span = dom.query_selector_first("span").unwrap();
+    assert!(dom.detach(span));
+    assert_eq!(dom.children().len(), 1);
+    assert_eq!(dom.outer_html(), "<div><p>b</p>c</div>");
+
+    // Detached nodes are still in the nodes table
+    assert_eq!(dom.nodes().len(), 8);
+
+    let parser = dom.parser_mut();
+    let text = NodeHandle::new(5);
+    let div = NodeHandle::new(0)
+        .get_mut(parser)
+        .unwrap()
+        .as_tag_mut()
+        .unwrap();
+    assert!(div.children_mut().remove(text));
+    assert!(!div.children_mut().remove(text));
+    assert_eq!(dom.outer_html(), "<div><p>b</p></div>");
+}
+
+#[test]
+fn insert_before_and_after() {
+    let mut dom = parse("<div><p>a</p><p>b</p></div>", Default::default()).unwrap();
+    let parser = dom.parser_mut();
+    let (first, second) = (NodeHandle::new(1), NodeHandle::new(3));
+    let mut create = |name| parser.push_node(Node::Tag(HTMLTag::create(name)));
+    let (hr, br, img, wbr) = (create("hr"), create("br"), create("img"), create("wbr"));
+
+    let div = NodeHandle::new(0)
+        .get_mut(parser)
+        .unwrap()
+        .as_tag_mut()
+        .unwrap();
+    let mut children = div.children_mut();
+
+    assert!(children.insert_after(first, hr));
+    assert!(children.insert_before(first, br));
+    assert!(children.insert_after(second, img));
+    assert!(!children.insert_before(NodeHandle::new(2), wbr));
+    assert!(!children.insert_after(NodeHandle::new(100), wbr));
+
+    let div = dom.nodes()[0].as_tag().unwrap();
+    assert_eq!(
+        div.inner_html(dom.parser()),
+        "<br><p>a</p><hr><p>b</p><img>"
+    );
+}
+
+#[test]
+fn set_inner_html() {
+    let mut dom = parse(
+        r#"<ul id="list"><li>old</li></ul><p>after</p>"#,
+        Default::default(),
+    )
+    .unwrap();
+    let list = dom.get_element_by_id("list").unwrap();
+
+    {
+        let fragment = String::from(r#"<li class="new">a &amp; b</li><!-- c --><li>d<br></li>"#);
+        assert!(list.set_inner_html(dom.parser_mut(), &fragment));
+    }
+
+    assert_eq!(
+        dom.outer_html(),
+        r#"<ul id="list"><li class="new">a &amp; b</li><!-- c --><li>d<br></li></ul><p>after</p>"#
+    );
+
+    let parser = dom.parser();
+    let items = dom
+        .query_selector("#list > li")
+        .unwrap()
+        .collect::<Vec<_>>();
+    assert_eq!(items.len(), 2);
+    assert_eq!(
+        items[0].get(parser).unwrap().inner_text_decoded(parser),
+        "a & b"
+    );
+    assert_eq!(items[1].parent(parser), Some(list));
+
+    // Replacing with an empty fragment removes all children
+    assert!(list.set_inner_html(dom.parser_mut(), ""));
+    assert_eq!(dom.outer_html(), r#"<ul id="list"></ul><p>after</p>"#);
+
+    // Text nodes cannot have children
+    let text = dom
+        .query_selector_first("p")
+        .unwrap()
+        .get(dom.parser())
+        .unwrap();
+    let text = text.children().unwrap().top()[0];
+    assert!(!text.set_inner_html(dom.parser_mut(), "<b>x</b>"));
+}
+
+#[test]
+fn outer_html_to_writer() {
+    let input =
+        r#"<div id="a"><p class="b">x<br>y</p><!-- c --><![CDATA[d]]><img src="e"></div>text"#;
+    let dom = parse(input, Default::default()).unwrap();
+    let parser = dom.parser();
+    let div = dom.nodes()[0].as_tag().unwrap();
+
+    let mut outer = String::new();
+    div.outer_html_to(parser, &mut outer).unwrap();
+    assert_eq!(outer, div.outer_html(parser));
+
+    let mut inner = String::new();
+    div.inner_html_to(parser, &mut inner).unwrap();
+    assert_eq!(inner, div.inner_html(parser));
+
+    let mut all = String::new();
+    for handle in dom.children() {
+        handle
+            .get(parser)
+            .unwrap()
+            .outer_html_to(parser, &mut all)
+            .unwrap();
+    }
+    assert_eq!(all, dom.outer_html());
+    assert_eq!(all, format!("{}text", outer));
+}
+
+#[test]
+fn outer_html_cow() {
+    use std::borrow::Cow;
+
+    let input = r#"<div id=a><p>x<br>y</p><!-- c --><img src="e"></div>"#;
+    let mut dom = parse(input, Default::default()).unwrap();
+    let div = dom.children()[0];
+    let p = dom.query_selector_first("p").unwrap();
+    let html = |dom: &VDom<'_>| {
+        let div = div.get(dom.parser()).unwrap().as_tag().unwrap();
+        let html = div.outer_html_cow(dom.parser());
+        (matches!(html, Cow::Borrowed(_)), html.into_owned())
+    };
+
+    assert_eq!(html(&dom), (true, input.to_string()));
+
+    // Changing a text node makes it owned
+    let text = p.get(dom.parser()).unwrap().children().unwrap().top()[0];
+    if let Some(Node::Raw(text)) = text.get_mut(dom.parser_mut()) {
+        text.set("z").unwrap();
+    }
+    assert_eq!(
+        html(&dom),
+        (
+            false,
+            r#"<div id="a"><p>z<br>y</p><!-- c --><img src="e"></div>"#.to_string()
+        )
+    );
+
+    // Replacing a text node with a borrowed string from outside of the tag is detected as well
+    let mut dom = parse(input, Default::default()).unwrap();
+    if let Some(Node::Raw(text)) = text.get_mut(dom.parser_mut()) {
+        *text = "z".into();
+    }
+    assert!(!html(&dom).0);
+
+    // Removing all children does not leave any owned data behind
+    let mut dom = parse(input, Default::default()).unwrap();
+    assert!(p.set_inner_html(dom.parser_mut(), ""));
+    assert_eq!(
+        html(&dom),
+        (
+            false,
+            r#"<div id="a"><p></p><!-- c --><img src="e"></div>"#.to_string()
+        )
+    );
+}
+
+#[test]
+fn escape_attribute_quotes() {
+    let dom = parse(
+        r#"<a title='he said "hi"' href="/?a=1&amp;b=2">x</a>"#,
+        Default::default(),
+    )
+    .unwrap();
+    let html = dom.outer_html();
+    assert_eq!(
+        html,
+        r#"<a title="he said &quot;hi&quot;" href="/?a=1&amp;b=2">x</a>"#
+    );
+
+    // The output parses back into the same attribute
+    let dom = parse(&html, Default::default()).unwrap();
+    let tag = dom.nodes()[0].as_tag().unwrap();
+    let title = tag.attributes().get("title").flatten().unwrap();
+    assert_eq!(title.as_utf8_str(), "he said &quot;hi&quot;");
+    assert_eq!(tag.attributes().len(), 2);
+}
+
+#[test]
+fn outer_html_escaped() {
+    let input = r#"<p class="a&amp;b">1 &lt; 2 &amp; &copy;</p><script>if (a && b) {}</script>"#;
+    let dom = parse(input, Default::default()).unwrap();
+    let parser = dom.parser();
+
+    // Text that was already escaped stays the same, apart from references that do not need to be escaped
+    let p = dom.nodes()[0].as_tag().unwrap();
+    assert_eq!(
+        p.outer_html_escaped(parser),
+        "<p class=\"a&amp;b\">1 &lt; 2 &amp; \u{a9}</p>"
+    );
+
+    let script = dom.nodes()[2].as_tag().unwrap();
+    assert_eq!(
+        script.outer_html_escaped(parser),
+        "<script>if (a && b) {}</script>"
+    );
+
+    // In attribute values, legacy references followed by an alphanumeric or `=` are not decoded
+    let dom = parse(
+        r#"<a href="?a=1&copy=2&copyx&copy;&amp">"#,
+        Default::default(),
+    )
+    .unwrap();
+    assert_eq!(
+        dom.nodes()[0]
+            .as_tag()
+            .unwrap()
+            .outer_html_escaped(dom.parser()),
+        "<a href=\"?a=1&amp;copy=2&amp;copyx\u{a9}&amp;\"></a>"
+    );
+
+    // Text and attributes that were set programmatically are escaped
+    let mut dom = parse("<div></div>", Default::default()).unwrap();
+    let parser = dom.parser_mut();
+    let text = parser.push_node(Node::Raw("a < b & c > d".into()));
+    let div = NodeHandle::new(0)
+        .get_mut(parser)
+        .unwrap()
+        .as_tag_mut()
+        .unwrap();
+    div.children_mut().append(text);
+    div.attributes_mut().insert("title", Some(r#"say "a & b""#));
+
+    let div = dom.nodes()[0].as_tag().unwrap();
+    assert_eq!(
+        div.inner_html_escaped(dom.parser()),
+        "a &lt; b &amp; c &gt; d"
+    );
+    assert_eq!(
+        div.outer_html_escaped(dom.parser()),
+        r#"<div title="say &quot;a &amp; b&quot;">a &lt; b &amp; c &gt; d</div>"#
+    );
+    assert_eq!(
+        div.outer_html(dom.parser()),
+        r#"<div title="say &quot;a & b&quot;">a < b & c > d</div>"#
+    );
+}
+
+#[test]
+fn pretty_html() {
+    let input = r#"<!DOCTYPE html><html><head><title>Example</title><meta charset="utf-8" /></head>
+    <body><div class="a"><h1>Title</h1>
+    <p>Some <a href="/">link</a> text</p><pre> keep
+  this </pre><!-- comment --></div></body></html>"#;
+    let dom = parse(input, Default::default()).unwrap();
+    let pretty = dom.to_pretty_html("    ");
+
+    assert_eq!(
+        pretty,
+        r#"<html>
+    <head>
+        <title>Example</title>
+        <meta charset="utf-8">
+    </head>
+    <body>
+        <div class="a">
+            <h1>Title</h1>
+            <p>
+                Some
+                <a href="/">link</a>
+                text
+            </p>
+            <pre> keep
+  this </pre>
+            <!-- comment -->
+        </div>
+    </body>
+</html>
+"#
+    );
+
+    // Parsing the output again yields the same tags and text
+    fn summary(dom: &VDom) -> Vec<String> {
+        dom.nodes()
+            .iter()
+            .filter_map(|node| match node {
+                Node::Tag(tag) => Some(tag.name().as_utf8_str().into_owned()),
+                Node::Raw(text) => Some(text.as_utf8_str().trim().to_string()),
+                _ => None,
+            })
+            .filter(|s| !s.is_empty())
+            .collect()
+    }
+
+    let reparsed = parse(&pretty, Default::default()).unwrap();
+    assert_eq!(summary(&reparsed), summary(&dom));
+    assert_eq!(reparsed.to_pretty_html("    "), pretty);
+}
+
+#[test]
+fn vdom_text() {
+    let input = r#"
+<!doctype html>
+<html>
+<head>
+    <title>Example Domain</title>
+
+    <meta charset="utf-8" />
+    <meta http-equiv="Content-type" content="text/html; charset=utf-8" />
+    <meta name="viewport" content="width=device-width, initial-scale=1" />
+    <style>body { color: red; }</style>
+</head>
+
+<body>
+<div>
+    <h1>Example Domain</h1>
+    <p>This domain is for use in illustrative examples in documents. You may use this
+    domain in literature without prior coordination or asking for permission.</p>
+    <p><a href="https://www.iana.org/domains/example">More information...</a></p>
+</div>
+<!-- comment -->
+</body>
+</html>
+"#;
+    let dom = parse(input, Default::default()).unwrap();
+    let text = dom.text();
+
+    assert!(!text.contains('<'));
+    assert_eq!(
+        text.split_whitespace().collect::<Vec<_>>().join(" "),
+        "Example Domain Example Domain This domain is for use in illustrative examples in documents. \
+        You may use this domain in literature without prior coordination or asking for permission. \
+        More information..."
+    );
+
+    let dom = parse(
+        "<p>a</p><p>b</p><div>c<span>d</span></div>e<br>f",
+        Default::default(),
+    )
+    .unwrap();
+    assert_eq!(dom.text(), "a b cd e f");
+
+    // tag names are compared case-insensitively
+    let dom = parse(
+        "<DIV>a</DIV><DIV>b</DIV><SCRIPT>c</SCRIPT>",
+        Default::default(),
+    )
+    .unwrap();
+    assert_eq!(dom.text(), "a b");
+}
+
+#[test]
+fn vdom_text_decoded() {
+    let dom = parse(
+        "<p>a&amp;amp;b</p><p>&#8212;&#x2014;&mdash;</p><p>&lt;b&gt;</p><![CDATA[&amp;]]>",
+        Default::default(),
+    )
+    .unwrap();
+
+    assert_eq!(
+        dom.text_decoded(),
+        "a&amp;b \u{2014}\u{2014}\u{2014} <b> &amp;"
+    );
+    assert_eq!(
+        dom.text(),
+        "a&amp;amp;b &#8212;&#x2014;&mdash; &lt;b&gt; &amp;"
+    );
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn serialize_dom() {
+    use serde_json::json;
+
+    let dom = parse(
+        "<div id=\"a\" hidden><!-- c --><p>Hello</p>world<![CDATA[x]]></div>",
+        Default::default(),
+    )
+    .unwrap();
+
+    let value = serde_json::to_value(&dom).unwrap();
+    let expected = json!([{
+        "type": "tag",
+        "name": "div",
+        "attributes": { "id": "a", "hidden": null },
+        "children": [
+            { "type": "comment", "content": "<!-- c -->" },
+            {
+                "type": "tag",
+                "name": "p",
+                "attributes": {},
+                "children": [{ "type": "text", "content": "Hello" }]
+            },
+            { "type": "text", "content": "world" },
+            { "type": "cdata", "content": "x" }
+        ]
+    }]);
+    assert_eq!(value, expected);
+
+    let json = serde_json::to_string(&value).unwrap();
+    let roundtrip: serde_json::Value = serde_json::from_str(&json).unwrap();
+    assert_eq!(roundtrip, expected);
+
+    let p = dom
+        .query_selector_first("p")
+        .unwrap()
+        .get(dom.parser())
+        .unwrap();
+    assert_eq!(
+        serde_json::to_value(crate::SerializableNode::new(p, dom.parser())).unwrap(),
+        expected[0]["children"][1]
+    );
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn serde_bytes() {
+    let json = serde_json::to_string(&Bytes::from("a \"quoted\" value")).unwrap();
+    assert_eq!(json, r#""a \"quoted\" value""#);
+
+    let bytes: Bytes<'static> = serde_json::from_str(&json).unwrap();
+    assert_eq!(bytes, "a \"quoted\" value");
+    assert!(bytes.as_bytes_borrowed().is_none());
+
+    let invalid = Bytes::from(&b"a\xffb"[..]);
+    assert_eq!(serde_json::to_string(&invalid).unwrap(), "\"a\u{fffd}b\"");
+
+    let bytes: Bytes<'static> = serde_json::from_str(r#""a\nb""#).unwrap();
+    assert_eq!(bytes, "a\nb");
+    assert!(serde_json::from_str::<Bytes<'static>>("1").is_err());
+}
+
+#[test]
+fn parse_bytes() {
+    let input = b"<div class=\"a\" title=\"\xff\"><p>\xe4\xb8\xad\xe6\x96\x87 \xc3</p></div>";
+    let dom = crate::parse_bytes(input, Default::default()).unwrap();
+    let parser = dom.parser();
+
+    let div = dom.query_selector_first(".a").unwrap().get(parser).unwrap();
+    let div = div.as_tag().unwrap();
+    assert_eq!(
+        div.attributes().get("title").flatten().unwrap().as_bytes(),
+        b"\xff"
+    );
+
+    let p = dom.query_selector_first("p").unwrap().get(parser).unwrap();
+    assert_eq!(p.inner_text(parser), "中文 \u{fffd}");
+}
+
+#[test]
+fn meta_charset() {
+    let charset = |input| parse(input, Default::default()).unwrap().meta_charset();
+
+    assert_eq!(charset("<meta charset=utf-8>").as_deref(), Some("utf-8"));
+    assert_eq!(
+        charset(r#"<meta name="x" content="y"><META CHARSET=" Shift_JIS ">"#).as_deref(),
+        Some("Shift_JIS")
+    );
+    assert_eq!(
+        charset(r#"<meta http-equiv="Content-Type" content="text/html; charset=windows-1252">"#)
+            .as_deref(),
+        Some("windows-1252")
+    );
+    assert_eq!(
+        charset(r#"<meta http-equiv="content-type" content="text/html;Charset = 'koi8-r'">"#)
+            .as_deref(),
+        Some("koi8-r")
+    );
+    assert_eq!(
+        charset(r#"<meta http-equiv="refresh" content="charset=utf-8"><meta charset="">"#),
+        None
+    );
+    assert_eq!(charset("<p>charset=utf-8</p>"), None);
+}
+
+#[test]
+fn data_attributes() {
+    let dom = parse(
+        r#"<div id="x" data-user-id="42" Data-Flag datax="no" data-Name="Ab"></div>"#,
+        Default::default(),
+    )
+    .unwrap();
+    let attributes = dom.nodes()[0].as_tag().unwrap().attributes();
+
+    assert_eq!(attributes.data("user-id"), Some(Some(&"42".into())));
+    // Attribute names are lowercased when parsing
+    assert_eq!(attributes.data("flag"), Some(None));
+    assert_eq!(attributes.data("Flag"), None);
+    assert_eq!(attributes.data("name"), Some(Some(&"Ab".into())));
+    assert_eq!(attributes.data("Name"), None);
+    assert_eq!(attributes.data("x"), None);
+
+    let mut data = attributes.data_iter().collect::<Vec<_>>();
+    data.sort();
+    assert_eq!(
+        data,
+        [
+            ("flag".into(), None),
+            ("name".into(), Some("Ab".into())),
+            ("user-id".into(), Some("42".into())),
+        ]
+    );
+}
+
+#[test]
+fn class_list_mutation() {
+    let mut dom = parse(r#"<div class="a b"></div><p></p>"#, Default::default()).unwrap();
+    let attributes = dom.nodes_mut()[0].as_tag_mut().unwrap().attributes_mut();
+
+    assert!(!attributes.toggle_class("b"));
+    assert!(attributes.toggle_class("c"));
+    assert_eq!(attributes.class(), Some(&"a c".into()));
+    assert!(attributes.has_class("c"));
+    assert!(!attributes.has_class("b"));
+
+    assert!(!attributes.add_class("a"));
+    assert!(!attributes.remove_class("b"));
+    assert!(attributes.remove_class("a"));
+    assert!(attributes.remove_class("c"));
+    assert_eq!(attributes.class(), Some(&"".into()));
+    assert!(attributes.add_class("d"));
+    assert_eq!(attributes.class(), Some(&"d".into()));
+
+    let attributes = dom.nodes_mut()[1].as_tag_mut().unwrap().attributes_mut();
+    assert!(attributes.add_class("e"));
+    assert_eq!(
+        dom.outer_html(),
+        r#"<div class="d"></div><p class="e"></p>"#
+    );
+}
+
+#[test]
+fn attributes_iter_mut() {
+    let mut dom = parse(
+        r#"<a id="x" href="http://a.com" class="c" rel="http:no" download></a>"#,
+        Default::default(),
+    )
+    .unwrap();
+    let attributes = dom.nodes_mut()[0].as_tag_mut().unwrap().attributes_mut();
+
+    let mut keys = Vec::new();
+    for (key, value) in attributes.iter_mut() {
+        keys.push(key.as_utf8_str().into_owned());
+
+        match (key.as_bytes(), value) {
+            (b"href", Some(value)) => {
+                let https = value.as_utf8_str().replacen("http:", "https:", 1);
+                value.set(https).unwrap();
+            }
+            (b"id", value) => *value = Some("y".into()),
+            (b"download", value) => *value = Some("file".into()),
+            _ => {}
+        }
+    }
+
+    assert_eq!(keys, ["id", "href", "class", "rel", "download"]);
+    assert_eq!(attributes.get("href"), Some(Some(&"https://a.com".into())));
+    assert_eq!(attributes.get("rel"), Some(Some(&"http:no".into())));
+    assert_eq!(attributes.id(), Some(&"y".into()));
+    assert_eq!(attributes.get("download"), Some(Some(&"file".into())));
+
+    let iter_keys = attributes
+        .iter()
+        .map(|(k, _)| k.into_owned())
+        .collect::<Vec<_>>();
+    assert_eq!(iter_keys, keys);
+}
+
+#[test]
+fn rename_attributes() {
+    let mut dom = parse(
+        r#"<div klass="a b" id="x" data-x="1" hidden></div>"#,
+        Default::default(),
+    )
+    .unwrap();
+    let attributes = dom.nodes_mut()[0].as_tag_mut().unwrap().attributes_mut();
+
+    assert!(attributes.rename("klass", "class"));
+    assert!(attributes.is_class_member("b"));
+    assert_eq!(attributes.get("klass"), None);
+
+    assert!(attributes.rename("id", "data-id"));
+    assert_eq!(attributes.id(), None);
+    assert_eq!(attributes.get("data-id"), Some(Some(&"x".into())));
+
+    assert!(attributes.rename("hidden", "aria-hidden"));
+    assert_eq!(attributes.get("aria-hidden"), Some(None));
+
+    assert!(attributes.rename("data-x", "data-id"));
+    assert_eq!(attributes.get("data-id"), Some(Some(&"1".into())));
+    assert_eq!(attributes.len(), 3);
+
+    assert!(!attributes.rename("missing", "id"));
+    assert_eq!(attributes.id(), None);
+}
+
+#[test]
+fn attribute_order() {
+    let input = r#"<a z="1" a="2" x="3" b="" c="4" m></a>"#;
+    let dom = parse(input, Default::default()).unwrap();
+    assert_eq!(dom.outer_html(), input);
+
+    let mut dom = parse(
+        r#"<a z="1" id="i" a="2" class="c"></a>"#,
+        Default::default(),
+    )
+    .unwrap();
+    assert_eq!(dom.outer_html(), r#"<a z="1" id="i" a="2" class="c"></a>"#);
+
+    let attributes = dom.nodes_mut()[0].as_tag_mut().unwrap().attributes_mut();
+    attributes.remove("z");
+    attributes.insert("b", Some("3"));
+    attributes.insert("a", Some("4"));
+    assert_eq!(dom.outer_html(), r#"<a id="i" a="4" class="c" b="3"></a>"#);
+
+    // `id` and `class` are kept in source order, between the other attributes
+    let input = r#"<a z="1" id="x" b="2" class="c" y="3"></a>"#;
+    let dom = parse(input, Default::default()).unwrap();
+    assert_eq!(dom.outer_html(), input);
+    let keys = dom.nodes()[0]
+        .as_tag()
+        .unwrap()
+        .attributes()
+        .iter()
+        .map(|(k, _)| k.into_owned())
+        .collect::<Vec<_>>();
+    assert_eq!(keys, ["z", "id", "b", "class", "y"]);
+
+    let input = r#"<a class="c" id="x" z="1"></a>"#;
+    let mut dom = parse(input, Default::default()).unwrap();
+    assert_eq!(dom.outer_html(), input);
+
+    // new attributes are appended, and removing one keeps the others in place
+    let attributes = dom.nodes_mut()[0].as_tag_mut().unwrap().attributes_mut();
+    attributes.remove("id");
+    attributes.insert("id", Some("y"));
+    attributes.remove("z");
+    attributes.insert("a", None::<&str>);
+    assert_eq!(dom.outer_html(), r#"<a class="c" id="y" a></a>"#);
+}
+
+#[test]
+fn duplicate_attributes() {
+    let input =
+        r#"<input value="a" id="x" value="b" class="c" id="y" class="d" value="c" name="n">"#;
+
+    for _ in 0..2 {
+        let dom = parse(input, Default::default()).unwrap();
+        let attributes = dom.nodes()[0].as_tag().unwrap().attributes();
+
+        assert_eq!(attributes.get("value"), Some(Some(&"a".into())));
+        assert_eq!(attributes.id(), Some(&"x".into()));
+        assert_eq!(attributes.class(), Some(&"c".into()));
+        assert_eq!(attributes.len(), 4);
+        assert_eq!(
+            dom.outer_html(),
+            r#"<input value="a" id="x" class="c" name="n">"#
+        );
+    }
+}
+
+#[test]
+fn boolean_attributes() {
+    let dom = parse(
+        r#"<input disabled><input disabled="disabled" checked readonly=""><input Disabled="false" type="checkbox">"#,
+        Default::default(),
+    )
+    .unwrap();
+    let attributes = |index: usize| dom.nodes()[index].as_tag().unwrap().attributes();
+
+    assert!(attributes(0).has_bool("disabled"));
+    assert!(attributes(0).is_truthy("disabled"));
+    assert!(!attributes(0).has_bool("checked"));
+
+    assert!(attributes(1).has_bool("disabled"));
+    assert!(attributes(1).is_truthy("disabled"));
+    assert!(attributes(1).is_truthy("checked"));
+    assert!(attributes(1).is_truthy("readonly"));
+
+    assert!(attributes(2).has_bool("disabled"));
+    assert!(attributes(2).contains("disabled"));
+    assert!(!attributes(2).is_truthy("disabled"));
+    assert_eq!(attributes(2).get("type"), Some(Some(&"checkbox".into())));
+}
+
+#[test]
+fn attribute_names_are_case_insensitive() {
+    let mut dom = parse(
+        r#"<a HREF="/" Target=_blank ID="link" CLASS="x y" href="/ignored">a</a>"#,
+        Default::default(),
+    )
+    .unwrap();
+    let tag = dom.nodes()[0].as_tag().unwrap();
+    let attributes = tag.attributes();
+
+    assert_eq!(attributes.get("href"), Some(Some(&"/".into())));
+    assert_eq!(attributes.get("HREF"), Some(Some(&"/".into())));
+    assert!(attributes.contains("TARGET"));
+    assert_eq!(attributes.id(), Some(&"link".into()));
+    assert!(attributes.is_class_member("y"));
+    assert_eq!(attributes.len(), 4);
+    assert_eq!(dom.get_element_by_id("link"), Some(NodeHandle::new(0)));
+    assert_eq!(dom.query_selector("[href='/']").unwrap().count(), 1);
+    assert_eq!(
+        dom.outer_html(),
+        r#"<a href="/" target="_blank" id="link" class="x y">a</a>"#
+    );
+
+    let attributes = dom.nodes_mut()[0].as_tag_mut().unwrap().attributes_mut();
+    attributes.insert("Title", Some("t"));
+    assert_eq!(attributes.get("title"), Some(Some(&"t".into())));
+    assert_eq!(attributes.remove("TARGET"), Some(Some("_blank".into())));
+}
+
+#[test]
+fn attribute_names() {
+    use std::borrow::Cow;
+
+    let input = "<a @click=\"x\" :href=y [value]='z' (c)=\"d\"\tdata-a\r\nhidden/>";
+    let dom = parse(input, Default::default()).unwrap();
+    let attributes = dom.nodes()[0].as_tag().unwrap().attributes();
+    let attributes = attributes.iter().collect::<Vec<_>>();
+
+    let attr = |k: &'static str, v: Option<&'static str>| (Cow::Borrowed(k), v.map(Cow::Borrowed));
+    assert_eq!(
+        attributes,
+        [
+            attr("@click", Some("x")),
+            attr(":href", Some("y")),
+            attr("[value]", Some("z")),
+            attr("(c)", Some("d")),
+            attr("data-a", None),
+            attr("hidden", None),
+        ]
+    );
+}
+
+#[test]
+fn unquoted_attribute_values() {
+    let attributes = |input| {
+        let dom = parse(input, Default::default()).unwrap();
+        let tag = dom.nodes()[0].as_tag().unwrap();
+        let attributes = tag
+            .attributes()
+            .iter()
+            .map(|(k, v)| format!("{k}={}", v.unwrap_or_default()))
+            .collect::<Vec<_>>();
+
+        (attributes, tag.raw().as_utf8_str().into_owned())
+    };
+
+    assert_eq!(
+        attributes("<a href=/about>x</a>"),
+        (vec!["href=/about".into()], "<a href=/about>x</a>".into())
+    );
+    assert_eq!(
+        attributes("<img src=a.png/><p>"),
+        (vec!["src=a.png".into()], "<img src=a.png/>".into())
+    );
+    assert_eq!(
+        attributes("<a href=x/ title=y>t</a>").0,
+        ["href=x/", "title=y"]
+    );
+    assert_eq!(
+        attributes("<a href=x\tlang=y\r\ntitle=z>t</a>").0,
+        ["href=x", "lang=y", "title=z"]
+    );
+    assert_eq!(attributes(r#"<a / href="x">t</a>"#).0, ["href=x"]);
+    assert_eq!(attributes("<a/b>t</a>").0, ["b="]);
+    assert_eq!(attributes("<br //>").1, "<br //>");
+}
+
+#[test]
+fn quoted_attribute_values_containing_gt() {
+    use crate::{HTMLTag, TagHandler};
+    use std::ops::ControlFlow;
+
+    let input = r#"<a title="x > y" href="z">link</a><b data-a='>' data-b="/>">bold</b>"#;
+    let dom = parse(input, ParserOptions::default().strict()).unwrap();
+    let parser = dom.parser();
+
+    let a = dom.nodes()[0].as_tag().unwrap();
+    assert_eq!(a.attributes().get("title"), Some(Some(&"x > y".into())));
+    assert_eq!(a.attributes().get("href"), Some(Some(&"z".into())));
+    assert_eq!(a.inner_text(parser), "link");
+    assert_eq!(
+        a.attributes().value_span("href", parser),
+        Some((input.find('z').unwrap(), input.find('z').unwrap() + 1))
+    );
+
+    let b = dom.query_selector_first(r#"b[data-b="/>"]"#).unwrap();
+    let b = b.get(parser).unwrap().as_tag().unwrap();
+    assert_eq!(b.attributes().get("data-a"), Some(Some(&">".into())));
+    assert_eq!(b.inner_text(parser), "bold");
+    assert_eq!(
+        dom.query_selector(r#"[title="x > y"][href=z]"#)
+            .unwrap()
+            .count(),
+        1
+    );
+    assert_eq!(
+        dom.outer_html(),
+        r#"<a title="x > y" href="z">link</a><b data-a=">" data-b="/>">bold</b>"#
+    );
+
+    struct Hrefs(Vec<String>);
+    impl<'a> TagHandler<'a> for Hrefs {
+        fn on_open_tag(&mut self, tag: &HTMLTag<'a>) -> ControlFlow<()> {
+            if let Some(Some(href)) = tag.attributes().get("href") {
+                self.0.push(href.as_utf8_str().into_owned());
+            }
+            ControlFlow::Continue(())
+        }
+    }
+
+    let mut hrefs = Hrefs(Vec::new());
+    crate::parse_streaming(input, Default::default(), &mut hrefs).unwrap();
+    assert_eq!(hrefs.0, ["z"]);
+}
+
+#[test]
+fn truncated_attributes() {
+    let inputs = [
+        r#"<a href="x" title='y > z' data-a=b/c hidden>text</a>"#,
+        r#"<img src=/a.png alt=x/><p class=p id='q'>y</p>"#,
+        "<div a=\"1\"b='2'c=3\td\r\n/e>",
+    ];
+
+    fn tags(dom: &VDom<'_>) -> Vec<String> {
+        dom.nodes()
+            .iter()
+            .filter_map(Node::as_tag)
+            .map(|tag| format!("{:?} {:?}", tag.name(), tag.attributes()))
+            .collect()
+    }
+
+    // a start tag is only parsed once its `>` has been read, so every prefix of the input
+    // must produce the same tags with the same attributes as the full input, up to the point where it was cut off
+    for input in inputs {
+        let expected = tags(&parse(input, Default::default()).unwrap());
+
+        for end in 0..=input.len() {
+            let prefix = &input[..end];
+
+            for options in [ParserOptions::default(), ParserOptions::default().strict()] {
+                let Ok(dom) = parse(prefix, options) else {
+                    continue;
+                };
+
+                let tags = tags(&dom);
+                assert_eq!(tags, expected[..tags.len()], "{prefix:?}");
+            }
+        }
+    }
+
+    // a tag that is cut off in the middle of an attribute is not parsed
+    for input in [
+        r#"<a href="#,
+        r#"<a href=""#,
+        r#"<a href="x"#,
+        r#"<a href=x"#,
+        "<a b",
+        "<a /",
+    ] {
+        let dom = parse(input, Default::default()).unwrap();
+        assert!(
+            dom.nodes().iter().all(|node| node.as_tag().is_none()),
+            "{input:?}"
+        );
+        assert_eq!(
+            parse(input, ParserOptions::default().strict()).unwrap_err(),
+            ParseError::UnexpectedEof { offset: 0 }
+        );
+    }
+}
+
+#[test]
+fn attribute_value_span() {
+    let source = r#"<img src=a.png alt="x" ID='y' hidden>"#;
+    let mut dom = parse(source, Default::default()).unwrap();
+    let span = |dom: &VDom<'_>, key| {
+        let attributes = dom.nodes()[0].as_tag().unwrap().attributes();
+        attributes
+            .value_span(key, dom.parser())
+            .map(|(start, end)| &source[start..end])
+    };
+
+    assert_eq!(span(&dom, "src"), Some("a.png"));
+    assert_eq!(span(&dom, "alt"), Some("x"));
+    assert_eq!(span(&dom, "id"), Some("y"));
+    assert_eq!(span(&dom, "hidden"), None);
+    assert_eq!(span(&dom, "title"), None);
+
+    let tag = dom.nodes_mut()[0].as_tag_mut().unwrap();
+    tag.set_attribute("src", "b.png");
+    tag.set_attribute("alt", "static");
+    assert_eq!(span(&dom, "src"), None);
+    assert_eq!(span(&dom, "alt"), None);
+}
+
+#[test]
+fn document_sections() {
+    let name = |dom: &VDom<'_>, handle: Option<NodeHandle>| {
+        handle.map(|h| {
+            h.get(dom.parser())
+                .unwrap()
+                .as_tag()
+                .unwrap()
+                .name()
+                .as_utf8_str()
+                .into_owned()
+        })
+    };
+
+    let dom = parse(
+        "<!DOCTYPE html>\n<!-- x -->\n<HTML><HEAD></HEAD>\n<BODY></BODY></HTML>",
+        Default::default(),
+    )
+    .unwrap();
+    assert_eq!(name(&dom, dom.root_element()).as_deref(), Some("HTML"));
+    assert_eq!(name(&dom, dom.head()).as_deref(), Some("HEAD"));
+    assert_eq!(name(&dom, dom.body()).as_deref(), Some("BODY"));
+
+    // Documents without an `<html>` element
+    let dom = parse(" <head></head><body><p></p></body>", Default::default()).unwrap();
+    assert_eq!(name(&dom, dom.root_element()).as_deref(), Some("head"));
+    assert_eq!(name(&dom, dom.body()).as_deref(), Some("body"));
+
+    let dom = parse("text <!-- only -->", Default::default()).unwrap();
+    assert_eq!(dom.root_element(), None);
+    assert_eq!(dom.head(), None);
+}
+
+#[test]
+fn text_content() {
+    let dom = parse(
+        "<div>a &lt; b<!-- c --><p>d<![CDATA[&amp;]]></p><script>x = 1 &amp;&amp; 2</script></div><p>only</p>",
+        Default::default(),
+    )
+    .unwrap();
+    let parser = dom.parser();
+    let div = dom.nodes()[0].as_tag().unwrap();
+
+    assert_eq!(div.text_content(parser), "a < bd&amp;x = 1 &amp;&amp; 2");
+    assert_eq!(div.inner_text_decoded(parser), "a < bd&amp;x = 1 && 2");
+
+    let p = dom.children()[1].get(parser).unwrap().as_tag().unwrap();
+    assert!(matches!(
+        p.text_content(parser),
+        std::borrow::Cow::Borrowed("only")
+    ));
+}
+
+#[test]
+fn parse_fragment() {
+    let input = "a <i>b</i> &amp; c";
+
+    for context in ["title", "TEXTAREA", "script", "style"] {
+        let dom = crate::parse_fragment(input, context, Default::default()).unwrap();
+        assert_eq!(dom.nodes().len(), 1);
+        assert_eq!(dom.children().len(), 1);
+        assert_eq!(dom.nodes()[0].as_raw().unwrap(), input);
+    }
+
+    let dom = crate::parse_fragment(input, "div", Default::default()).unwrap();
+    assert_eq!(dom.children().len(), 3);
+    assert_eq!(dom.outer_html(), input);
+
+    let dom = crate::parse_fragment("", "title", Default::default()).unwrap();
+    assert!(dom.nodes().is_empty());
+}
+
+#[test]
+fn minified_html() {
+    // The input of the benchmark
+    let input = r#"
+<!doctype html>
+<html>
+<head>
+    <title>Example Domain</title>
+
+    <meta charset="utf-8" />
+    <meta http-equiv="Content-type" content="text/html; charset=utf-8" />
+    <meta name="viewport" content="width=device-width, initial-scale=1" />
+</head>
+
+<body>
+<div>
+    <h1>Example Domain</h1>
+    <p>This domain is for use in illustrative examples in documents. You may use this
+    domain in literature without prior coordination or asking for permission.</p>
+    <p><a href="https://www.iana.org/domains/example">More information...</a></p>
+</div>
+</body>
+</html>
+"#;
+    let dom = parse(input, Default::default()).unwrap();
+    let minified = dom.to_minified_html();
+    assert!(minified.len() < input.len() * 9 / 10, "{minified}");
+    assert!(minified.starts_with("<html><head><title>Example Domain</title><meta charset"));
+    assert!(minified.contains(
+        "<p>This domain is for use in illustrative examples in documents. You may use this domain"
+    ));
+
+    // The minified document has the same elements and the same visible text
+    let reparsed = parse(&minified, Default::default()).unwrap();
+    let names = |dom: &VDom<'_>| {
+        dom.nodes()
+            .iter()
+            .filter_map(Node::as_tag)
+            .map(|tag| tag.name().as_utf8_str().into_owned())
+            .collect::<Vec<_>>()
+    };
+    let words = |dom: &VDom<'_>| {
+        dom.nodes()
+            .iter()
+            .filter_map(Node::as_raw)
+            .map(|text| {
+                text.as_utf8_str()
+                    .split_whitespace()
+                    .map(String::from)
+                    .collect::<Vec<_>>()
+            })
+            .filter(|words| !words.is_empty())
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(names(&dom), names(&reparsed));
+    assert_eq!(words(&dom), words(&reparsed));
+    assert_eq!(reparsed.to_minified_html(), minified);
+
+    let dom = parse(
+        "<p> a <b>b</b> <!-- c --> <i> c </i></p>\n<ul>\n <li>x</li>\n</ul> <textarea>\n t  </textarea>",
+        Default::default(),
+    )
+    .unwrap();
+    assert_eq!(
+        dom.to_minified_html(),
+        "<p>a <b>b</b> <!-- c --> <i> c </i></p><ul><li>x</li></ul><textarea>\n t  </textarea>"
+    );
+}
+
+#[test]
+fn style_declarations() {
+    let dom = parse(
+        r#"<p style=" color : red;;background:url(a.png) ; font-weight:bold;: x; nocolon ;"></p><p></p>"#,
+        Default::default(),
+    )
+    .unwrap();
+    let declarations = |index: usize| {
+        dom.nodes()[index]
+            .as_tag()
+            .unwrap()
+            .attributes()
+            .style_declarations()
+            .collect::<Vec<_>>()
+    };
+
+    assert_eq!(
+        declarations(0),
+        [
+            ("color", "red"),
+            ("background", "url(a.png)"),
+            ("font-weight", "bold")
+        ]
+    );
+    assert!(declarations(1).is_empty());
+}
+
+#[test]
+fn parse_streaming() {
+    use crate::{Bytes, HTMLTag, TagHandler};
+    use std::ops::ControlFlow;
+
+    #[derive(Default)]
+    struct Counter {
+        links: usize,
+        open: usize,
+        close: usize,
+        text: String,
+        comments: usize,
+        stop_after: Option<usize>,
+    }
+
+    impl<'a> TagHandler<'a> for Counter {
+        fn on_open_tag(&mut self, tag: &HTMLTag<'a>) -> ControlFlow<()> {
+            self.open += 1;
+            if tag.name() == "a" {
+                self.links += 1;
+                if Some(self.links) == self.stop_after {
+                    return ControlFlow::Break(());
+                }
+            }
+            ControlFlow::Continue(())
+        }
+
+        fn on_close_tag(&mut self, _: &Bytes<'a>) -> ControlFlow<()> {
+            self.close += 1;
+            ControlFlow::Continue(())
+        }
+
+        fn on_text(&mut self, text: &Bytes<'a>) -> ControlFlow<()> {
+            self.text.push_str(&text.as_utf8_str());
+            ControlFlow::Continue(())
+        }
+
+        fn on_comment(&mut self, _: &Bytes<'a>) -> ControlFlow<()> {
+            self.comments += 1;
+            ControlFlow::Continue(())
+        }
+    }
+
+    let input = "<div><a href=\"#\">x</a><br><!-- c --><img /></div>".repeat(10_000);
+
+    let mut counter = Counter::default();
+    crate::parse_streaming(&input, Default::default(), &mut counter).unwrap();
+    assert_eq!(counter.links, 10_000);
+    assert_eq!(counter.open, 40_000);
+    assert_eq!(counter.close, 40_000);
+    assert_eq!(counter.comments, 10_000);
+    assert_eq!(counter.text.len(), 10_000);
+
+    let mut counter = Counter {
+        stop_after: Some(3),
+        ..Default::default()
+    };
+    crate::parse_streaming(&input, Default::default(), &mut counter).unwrap();
+    assert_eq!(counter.links, 3);
+    assert_eq!(counter.close, 2 * 4);
+
+    let mut counter = Counter::default();
+    crate::parse_streaming(
+        "<p>a<![CDATA[b]]>\n<pre>\n</pre>\n</p >",
+        ParserOptions::default().skip_whitespace_text_nodes(),
+        &mut counter,
+    )
+    .unwrap();
+    assert_eq!(counter.text, "ab\n");
+    assert_eq!(counter.close, 2);
+}
+
+#[test]
+fn parse_chunked() {
+    use crate::{Bytes, ChunkedParser, HTMLTag, TagHandler};
+    use std::ops::ControlFlow;
+
+    #[derive(Default)]
+    struct Events(Vec<String>, Option<usize>);
+
+    impl<'a> TagHandler<'a> for Events {
+        fn on_open_tag(&mut self, tag: &HTMLTag<'a>) -> ControlFlow<()> {
+            self.0.push(format!("open {}", tag.raw().as_utf8_str()));
+            match self.1 {
+                Some(limit) if self.0.len() >= limit => ControlFlow::Break(()),
+                _ => ControlFlow::Continue(()),
+            }
+        }
+
+        fn on_close_tag(&mut self, name: &Bytes<'a>) -> ControlFlow<()> {
+            self.0.push(format!("close {}", name.as_utf8_str()));
+            ControlFlow::Continue(())
+        }
+
+        fn on_text(&mut self, text: &Bytes<'a>) -> ControlFlow<()> {
+            self.0.push(format!("text {}", text.as_utf8_str()));
+            ControlFlow::Continue(())
+        }
+
+        fn on_comment(&mut self, comment: &Bytes<'a>) -> ControlFlow<()> {
+            self.0.push(format!("comment {}", comment.as_utf8_str()));
+            ControlFlow::Continue(())
+        }
+    }
+
+    let input = "<!DOCTYPE html><div class=\"a > b\" id=x>Grüße <br/><!-- c --->\
+        <![CDATA[<x>]]><pre>\n  </pre>  </div ><p>trailing text";
+
+    for options in [
+        ParserOptions::default(),
+        ParserOptions::default().skip_whitespace_text_nodes(),
+    ] {
+        let mut expected = Events::default();
+        crate::parse_streaming(input, options, &mut expected).unwrap();
+        assert!(expected.0.len() > 10);
+
+        for size in 1..=input.len() {
+            let mut parser = ChunkedParser::new(options, Events::default());
+            for chunk in input.as_bytes().chunks(size) {
+                parser.feed(chunk).unwrap();
+            }
+            assert_eq!(parser.finish().unwrap().0, expected.0, "chunk size {size}");
+        }
+    }
+
+    // Tokens are reported as soon as they are complete
+    let mut parser = ChunkedParser::new(Default::default(), Events::default());
+    parser.feed(b"<p>a</p").unwrap();
+    assert_eq!(parser.handler().0, ["open <p>", "text a"]);
+    parser.feed(b"><b").unwrap();
+    assert_eq!(parser.handler().0.last().unwrap(), "close p");
+
+    // Chunks after the handler stopped parsing are ignored
+    let mut parser = ChunkedParser::new(Default::default(), Events(Vec::new(), Some(2)));
+    parser.feed(b"<a>x<b>y").unwrap();
+    parser.feed(b"<c>z").unwrap();
+    assert_eq!(
+        parser.finish().unwrap().0,
+        ["open <a>", "text x", "open <b>"]
+    );
+}
+
+#[test]
+fn max_depth() {
+    let options = ParserOptions::default().max_depth(2);
+
+    let dom = parse("<a><b><c><d>x</d></c><br></b>y</a>", options).unwrap();
+    assert!(dom.depth_limit_exceeded());
+    assert_eq!(
+        dom.nodes().iter().filter(|n| n.as_tag().is_some()).count(),
+        2
+    );
+    assert_eq!(dom.outer_html(), "<a><b><c><d>x</d></c><br></b>y</a>");
+    let b = dom.nodes()[1].as_tag().unwrap();
+    assert_eq!(b.name(), "b");
+    assert_eq!(b.children().top().len(), 6);
+    assert_eq!(
+        dom.nodes()[0].inner_text(dom.parser()),
+        "<c><d>x</d></c><br>y"
+    );
+
+    let dom = parse("<a><b></b></a>", options).unwrap();
+    assert!(!dom.depth_limit_exceeded());
+
+    let input = "<div>".repeat(100_000);
+    let dom = parse(&input, ParserOptions::default().max_depth(64)).unwrap();
+    assert!(dom.depth_limit_exceeded());
+    assert_eq!(dom.outer_html().len(), input.len() + 64 * "</div>".len());
+}
+
+#[test]
+fn serialize_deep_tree() {
+    const DEPTH: usize = 50_000;
+
+    let input = format!("{}x{}", "<div>".repeat(DEPTH), "</div>".repeat(DEPTH));
+    let dom = parse(&input, Default::default()).unwrap();
+
+    assert_eq!(dom.outer_html(), input);
+    let root = dom.nodes()[0].as_tag().unwrap();
+    assert_eq!(
+        root.inner_html(dom.parser()).len(),
+        input.len() - "<div></div>".len()
+    );
+    assert_eq!(root.outer_html_escaped(dom.parser()), input);
+
+    let pretty = format!(
+        "{}<div>x</div>\n{}",
+        "<div>\n".repeat(DEPTH - 1),
+        "</div>\n".repeat(DEPTH - 1)
+    );
+    assert_eq!(dom.to_pretty_html(""), pretty);
+    assert_eq!(dom.text(), "x");
+    assert_eq!(dom.to_minified_html(), input);
+}
+
+#[test]
+fn element_children() {
+    let mut dom = parse(
+        "<div> <p>a</p> <!-- c --> <span><b>b</b></span> text </div>",
+        Default::default(),
+    )
+    .unwrap();
+    let parser = dom.parser();
+    let div = dom.nodes()[0].as_tag().unwrap();
+
+    let names = div
+        .element_children(parser)
+        .map(|h| {
+            h.get(parser)
+                .unwrap()
+                .as_tag()
+                .unwrap()
+                .name()
+                .as_utf8_str()
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(names, ["p", "span"]);
+    assert!(div
+        .children()
+        .elements(parser)
+        .eq(div.element_children(parser)));
+    assert_eq!(
+        div.children().nth_element(parser, 1),
+        div.children().top().get(5).copied()
+    );
+    assert_eq!(div.children().nth_element(parser, 2), None);
+    assert_eq!(div.descendant_element_count(parser), 3);
+
+    let p = dom.query_selector_first("p").unwrap();
+    assert!(dom.detach(p));
+    let div = dom.nodes()[0].as_tag().unwrap();
+    assert_eq!(div.element_children(dom.parser()).count(), 1);
+    assert_eq!(div.descendant_element_count(dom.parser()), 2);
+}
+
+#[test]
+fn node_handle_index_and_depth() {
+    let dom = parse(
+        "<!-- c --><div>a<p>b</p><!-- c --><span><b>c</b></span></div>",
+        Default::default(),
+    )
+    .unwrap();
+    let parser = dom.parser();
+    let find = |selector| dom.query_selector_first(selector).unwrap();
+
+    let div = find("div");
+    assert_eq!(div.index_in_parent(parser), Some(1));
+    assert_eq!(div.element_index_in_parent(parser), Some(0));
+    assert_eq!(div.depth(parser), 0);
+
+    let span = find("span");
+    assert_eq!(span.index_in_parent(parser), Some(3));
+    assert_eq!(span.element_index_in_parent(parser), Some(1));
+    assert_eq!(span.depth(parser), 1);
+
+    assert_eq!(find("b").depth(parser), 2);
+    assert_eq!(find("b").element_index_in_parent(parser), Some(0));
+
+    let text = dom.children()[0];
+    assert_eq!(text.index_in_parent(parser), Some(0));
+    assert_eq!(text.element_index_in_parent(parser), None);
+
+    assert_eq!(NodeHandle::new(1000).index_in_parent(parser), None);
+}
+
+#[test]
+fn node_display() {
+    let dom = parse(
+        r#"<div title='a "b"'>x<!-- c --><![CDATA[y]]><br></div>"#,
+        Default::default(),
+    )
+    .unwrap();
+    let parser = dom.parser();
+
+    for node in dom.nodes() {
+        assert_eq!(node.display(parser).to_string(), node.outer_html(parser));
+    }
+}
+
+#[test]
+fn strict_mode() {
+    let options = ParserOptions::default().strict();
+    assert!(options.is_strict());
+
+    assert!(parse("<div><br><img/><p>x</p><!-- c --></div>", options).is_ok());
+
+    assert_eq!(
+        parse("<div><p>x</p>", options).unwrap_err(),
+        ParseError::UnclosedTag { offset: 0 }
+    );
+    assert_eq!(
+        parse("<div><p>x</div>", options).unwrap_err(),
+        ParseError::MismatchedEndTag { offset: 9 }
+    );
+    assert_eq!(
+        parse("<div></div><p", options).unwrap_err(),
+        ParseError::UnexpectedEof { offset: 11 }
+    );
+    assert_eq!(
+        parse("<div></div", options).unwrap_err(),
+        ParseError::UnexpectedEof { offset: 5 }
+    );
+    assert_eq!(
+        parse("<div /x></div>", options).unwrap_err(),
+        ParseError::MalformedTag { offset: 6 }
+    );
+
+    // the default is still lenient
+    assert!(parse("<div><p>x</div><p", ParserOptions::default()).is_ok());
+}
+
+#[test]
+fn misnested_end_tags() {
+    // `</b>` closes `<b>` and the `<i>` inside of it, and the stray `</i>` is ignored
+    let dom = parse("<b><i>x</b>y</i>z", ParserOptions::default()).unwrap();
+    let parser = dom.parser();
+    assert_eq!(dom.children().len(), 3);
+    let b = dom.children()[0].get(parser).unwrap().as_tag().unwrap();
+    assert_eq!(b.raw(), "<b><i>x</b>");
+    let i = b.children().top()[0].get(parser).unwrap().as_tag().unwrap();
+    assert_eq!(i.raw(), "<i>x");
+    assert_eq!(
+        dom.children()[1].get(parser).unwrap().as_raw().unwrap(),
+        "y"
+    );
+
+    // an unclosed inline tag does not swallow the rest of the document
+    let dom = parse("<div><span>a</div><p>b</p>", ParserOptions::default()).unwrap();
+    assert_eq!(dom.children().len(), 2);
+    assert_eq!(dom.outer_html(), "<div><span>a</span></div><p>b</p>");
+
+    // end tags without a matching start tag are ignored
+    let dom = parse("<div>a</span>b</div>", ParserOptions::default()).unwrap();
+    assert_eq!(dom.children().len(), 1);
+    assert_eq!(dom.nodes()[0].inner_text(dom.parser()), "ab");
+
+    // implicitly closed tags are tracked as well
+    let dom = parse(
+        "<ul><li id=\"a\" class=\"x\">a</ul>",
+        ParserOptions::default().track_ids().track_classes(),
+    )
+    .unwrap();
+    assert!(dom.get_element_by_id("a").is_some());
+    assert_eq!(dom.get_elements_by_class_name("x").count(), 1);
+}
+
+#[test]
+fn implied_end_tags() {
+    let dom = parse("<ul><li>a<li>b</ul>", ParserOptions::default()).unwrap();
+    assert_eq!(dom.outer_html(), "<ul><li>a</li><li>b</li></ul>");
+    let li = dom.nodes()[1].as_tag().unwrap();
+    assert_eq!(li.raw(), "<li>a");
+
+    let dom = parse("<p>a<p>b<div>c</div>", ParserOptions::default()).unwrap();
+    assert_eq!(dom.outer_html(), "<p>a</p><p>b</p><div>c</div>");
+
+    // inline tags do not close a paragraph
+    let dom = parse("<p>a<span>b</span></p>", ParserOptions::default()).unwrap();
+    assert_eq!(dom.children().len(), 1);
+
+    let dom = parse("<dl><dt>a<dd>b<dt>c</dl>", ParserOptions::default()).unwrap();
+    assert_eq!(dom.outer_html(), "<dl><dt>a</dt><dd>b</dd><dt>c</dt></dl>");
+
+    let dom = parse(
+        "<select><option>a<option>b<optgroup><option>c</select>",
+        ParserOptions::default(),
+    )
+    .unwrap();
+    assert_eq!(
+        dom.outer_html(),
+        "<select><option>a</option><option>b</option><optgroup><option>c</option></optgroup></select>"
+    );
+
+    let dom = parse(
+        "<table><thead><tr><th>a<th>b<tbody><tr><td>c<td>d<tr><td>e</table>",
+        ParserOptions::default(),
+    )
+    .unwrap();
+    assert_eq!(
+        dom.outer_html(),
+        "<table><thead><tr><th>a</th><th>b</th></tr></thead>\
+         <tbody><tr><td>c</td><td>d</td></tr><tr><td>e</td></tr></tbody></table>"
+    );
+
+    // nested lists are not affected
+    let dom = parse(
+        "<ul><li>a<ul><li>b</li></ul></li><li>c</li></ul>",
+        ParserOptions::default(),
+    )
+    .unwrap();
+    assert_eq!(
+        dom.outer_html(),
+        "<ul><li>a<ul><li>b</li></ul></li><li>c</li></ul>"
+    );
+}
+
+#[test]
+fn preserve_comments() {
+    let input = "<!-- a --><div><!-- b -->x<p>y</p><!-- c --></div>";
+    let count_comments = |dom: &VDom| {
+        dom.nodes()
+            .iter()
+            .filter(|node| matches!(node, Node::Comment(_)))
+            .count()
+    };
+
+    let options = ParserOptions::default();
+    assert!(options.is_preserving_comments());
+    let dom = parse(input, options).unwrap();
+    assert_eq!(count_comments(&dom), 3);
+
+    let options = options.preserve_comments(false);
+    assert!(!options.is_preserving_comments());
+    let dom = parse(input, options).unwrap();
+    assert_eq!(count_comments(&dom), 0);
+    assert_eq!(dom.nodes().len(), 4);
+    assert_eq!(dom.outer_html(), "<div>x<p>y</p></div>");
+
+    assert!(options.preserve_comments(true).is_preserving_comments());
+}
+
+#[test]
+fn void_tags() {
+    // parsing and serialization agree on the standard void elements
+    let dom = parse("<div><command>x<keygen>y</div>", ParserOptions::default()).unwrap();
+    assert_eq!(dom.outer_html(), "<div><command>x<keygen>y</div>");
+
+    let options = ParserOptions::default().extra_void_tags(&["my-icon", "spacer"]);
+    assert!(options.is_void_tag(b"spacer"));
+    assert!(options.is_void_tag(b"br"));
+    assert!(!options.is_void_tag(b"div"));
+    assert_eq!(options.get_extra_void_tags(), ["my-icon", "spacer"]);
+
+    let dom = parse("<p><my-icon>a<spacer/>b</p>", options).unwrap();
+    let parser = dom.parser();
+    assert_eq!(dom.children().len(), 1);
+    let p = dom.nodes()[0].as_tag().unwrap();
+    assert_eq!(p.children().top().len(), 4);
+    assert_eq!(dom.outer_html(), "<p><my-icon>a<spacer>b</p>");
+    assert_eq!(dom.nodes()[1].outer_html(parser), "<my-icon>");
+
+    // without the option, custom elements need an end tag
+    let dom = parse("<p><my-icon>a</p>", ParserOptions::default()).unwrap();
+    assert_eq!(dom.outer_html(), "<p><my-icon>a</my-icon></p>");
+}
+
+#[test]
+fn append_input() {
+    let options = ParserOptions::default().track_ids().track_classes();
+    let mut dom =
+        unsafe { parse_owned("<div id=\"a\" class=\"x\">a</div><p>".into(), options) }.unwrap();
+    let first = dom.get_ref().children()[0];
+
+    dom.append_input(String::from("<span id=\"b\" class=\"x\">b</span>"))
+        .unwrap();
+
+    let dom = dom.get_ref();
+    let parser = dom.parser();
+    assert_eq!(dom.children().len(), 3);
+    assert_eq!(dom.children()[0], first);
+    assert_eq!(
+        dom.outer_html(),
+        "<div id=\"a\" class=\"x\">a</div><p></p><span id=\"b\" class=\"x\">b</span>"
+    );
+
+    assert!(dom.get_element_by_id("a").is_some());
+    assert!(dom.get_element_by_id("b").is_some());
+    assert_eq!(dom.get_elements_by_class_name("x").count(), 2);
+    assert_eq!(dom.query_selector(".x").unwrap().count(), 2);
+
+    let span = dom.get_element_by_id("b").unwrap().get(parser).unwrap();
+    assert_eq!(span.as_tag().unwrap().boundaries(parser), (0, 30));
+}
+
+#[test]
+fn walk_dom() {
+    #[derive(Default)]
+    struct Events(Vec<String>);
+
+    impl Visitor<'_> for Events {
+        fn visit_tag(&mut self, tag: &HTMLTag<'_>) {
+            self.0.push(format!("<{}>", tag.name().as_utf8_str()));
+        }
+
+        fn visit_text(&mut self, text: &Bytes<'_>) {
+            self.0.push(text.as_utf8_str().into_owned());
+        }
+
+        fn visit_comment(&mut self, comment: &Bytes<'_>) {
+            self.0.push(comment.as_utf8_str().into_owned());
+        }
+    }
+
+    let mut dom = parse(
+        "<div>a<p>b<br>c</p><!--d--></div>e<![CDATA[f]]>",
+        ParserOptions::default(),
+    )
+    .unwrap();
+
+    let mut events = Events::default();
+    dom.walk(&mut events);
+    assert_eq!(
+        events.0,
+        ["<div>", "a", "<p>", "b", "<br>", "c", "<!--d-->", "e"]
+    );
+
+    // detached nodes are not visited
+    let p = dom.nodes()[2].as_tag().unwrap().children().top()[0];
+    assert!(dom.detach(p));
+    let mut events = Events::default();
+    dom.walk(&mut events);
+    assert_eq!(
+        events.0,
+        ["<div>", "a", "<p>", "<br>", "c", "<!--d-->", "e"]
+    );
+
+    struct RenameParagraphs;
+
+    impl<'a> VisitorMut<'a> for RenameParagraphs {
+        fn visit_tag(&mut self, tag: &mut HTMLTag<'a>) {
+            if tag.name() == "p" {
+                *tag.name_mut() = "section".into();
+            }
+        }
+    }
+
+    dom.walk_mut(&mut RenameParagraphs);
+    assert_eq!(
+        dom.outer_html(),
+        "<div>a<section><br>c</section><!--d--></div>e<![CDATA[f]]>"
+    );
+}
+
+#[test]
+fn find_nodes() {
+    let dom = parse(
+        "<div><p>a</p><span><p>b</p></span>c<p>d</p></div><p>e</p>",
+        ParserOptions::default(),
+    )
+    .unwrap();
+    let parser = dom.parser();
+    let div = &dom.nodes()[0];
+    let is_p = |node: &Node| node.as_tag().is_some_and(|tag| tag.name() == "p");
+
+    let found = div.find_nodes(parser, &mut { is_p });
+    let texts = found
+        .iter()
+        .map(|handle| handle.get(parser).unwrap().inner_text(parser))
+        .collect::<Vec<_>>();
+    assert_eq!(texts, ["a", "b", "d"]);
+
+    assert_eq!(
+        div.as_tag()
+            .unwrap()
+            .find_nodes_iter(parser, is_p)
+            .collect::<Vec<_>>(),
+        found
+    );
+    let first_text = div
+        .find_nodes_iter(parser, |node| node.as_raw().is_some())
+        .next()
+        .unwrap();
+    assert_eq!(first_text.get(parser).unwrap().as_raw().unwrap(), "a");
+
+    // only tags have descendants
+    let text = dom.nodes().last().unwrap();
+    assert!(text.find_nodes(parser, &mut |_| true).is_empty());
+}
+
+#[test]
+fn tracked_lookups_match_untracked() {
+    let input = r#"
+        <div id="outer" class="a">
+            <section class="b a">
+                <p id="deep" class="c c">x<span id="deeper" class="a">y</span></p>
+                <div id="outer" class="b">duplicate id</div>
+            </section>
+            <ul><li class="a">1<li class="a" id="li">2</ul>
+            <p id="unclosed" class="b">
+    "#;
+
+    let untracked = parse(input, ParserOptions::default()).unwrap();
+    let tracked = parse(input, ParserOptions::default().track_ids().track_classes()).unwrap();
+
+    for id in ["outer", "deep", "deeper", "li", "unclosed", "missing"] {
+        assert_eq!(
+            tracked.get_element_by_id(id),
+            untracked.get_element_by_id(id),
+            "id {id}"
+        );
+    }
+    assert_eq!(
+        tracked.get_element_by_id("outer"),
+        Some(untracked.children()[1])
+    );
+
+    for class in ["a", "b", "c", "missing"] {
+        assert_eq!(
+            tracked
+                .get_elements_by_class_name(class)
+                .collect::<Vec<_>>(),
+            untracked
+                .get_elements_by_class_name(class)
+                .collect::<Vec<_>>(),
+            "class {class}"
+        );
+    }
+    assert_eq!(tracked.get_elements_by_class_name("a").count(), 5);
+}
+
+#[test]
+fn track_void_and_self_closing_tags() {
+    let dom = parse(
+        r#"<p><img id="x" class="icon"><br class="icon"/><span id="y" class="icon" /></p>"#,
+        ParserOptions::default().track_ids().track_classes(),
+    )
+    .unwrap();
+    let parser = dom.parser();
+
+    let img = dom.get_element_by_id("x").unwrap();
+    assert_eq!(img.get(parser).unwrap().as_tag().unwrap().name(), "img");
+    assert!(dom.get_element_by_id("y").is_some());
+    assert_eq!(dom.get_elements_by_class_name("icon").count(), 3);
+}
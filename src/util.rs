@@ -1,8 +1,10 @@
+use crate::Bytes;
+
 #[inline(never)]
 pub fn is_ident(c: u8) -> bool {
-    (b'0'..=b'9').contains(&c)
-        || (b'A'..=b'Z').contains(&c)
-        || (b'a'..=b'z').contains(&c)
+    c.is_ascii_digit()
+        || c.is_ascii_uppercase()
+        || c.is_ascii_lowercase()
         || c == b'-'
         || c == b'_'
         || c == b':'
@@ -10,9 +12,38 @@ pub fn is_ident(c: u8) -> bool {
         || c == b'/'
 }
 
+/// Checks whether the given byte can be part of a tag name, ID or class name in a query selector
+///
+/// Unlike [`is_ident`], this does not accept characters that have a special meaning in selectors (`+`, `:` and `/`).
+#[inline]
+pub fn is_selector_ident(c: u8) -> bool {
+    c.is_ascii_alphanumeric() || c == b'-' || c == b'_' || c >= 0x80
+}
+
 #[inline(always)]
 pub fn to_lower(byte: u8) -> u8 {
-    let is_upper = (byte >= b'A' && byte <= b'Z') as u8;
+    let is_upper = byte.is_ascii_uppercase() as u8;
     let lower = is_upper * 0x20;
     byte + lower
 }
+
+/// Checks whether a tag name is one of the given lowercase names, ignoring ASCII case
+pub fn is_tag_in(name: &[u8], tags: &[&[u8]]) -> bool {
+    tags.iter().any(|tag| name.eq_ignore_ascii_case(tag))
+}
+
+/// ASCII-lowercases the given bytes, only allocating if they contain uppercase characters
+///
+/// Bytes that are already lowercase are returned as they are, so borrowed input stays borrowed.
+pub fn to_lower_bytes<'b, B: Into<Bytes<'b>>>(bytes: B) -> Bytes<'b> {
+    let bytes = bytes.into();
+    if !bytes.as_bytes().iter().any(u8::is_ascii_uppercase) {
+        return bytes;
+    }
+
+    let mut lower = Bytes::new();
+    lower
+        .set(bytes.as_bytes().to_ascii_lowercase())
+        .expect("lowercasing does not change the length, which already fits in a Bytes");
+    lower
+}
@@ -1,20 +1,27 @@
 use crate::errors::ParseError;
-use crate::parser::HTMLVersion;
+use crate::inline::vec::InlineVec;
 use crate::parser::NodeHandle;
+use crate::parser::{comment_content, constants, HTMLVersion};
 use crate::queryselector;
-use crate::queryselector::QuerySelectorIterator;
+use crate::queryselector::context::MatchContext;
+use crate::queryselector::{QuerySelectorIterator, Selector};
+use crate::util;
 use crate::Bytes;
 use crate::InnerNodeHandle;
 use crate::ParserOptions;
-use crate::{Node, Parser};
+use crate::{visitor, Visitor, VisitorMut};
+use crate::{HTMLTag, Node, Parser};
+use std::borrow::Cow;
+use std::fmt::{self, Write};
 use std::marker::PhantomData;
+use std::sync::Arc;
 
 /// VDom represents a [Document Object Model](https://developer.mozilla.org/en/docs/Web/API/Document_Object_Model)
 ///
 /// It is the result of parsing an HTML document.
 /// Internally it is only a wrapper around the [`Parser`] struct, in which all of the HTML tags are stored.
 /// Many functions of the public API take a reference to a [`Parser`] as a parameter to resolve [`NodeHandle`]s to [`Node`]s.
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct VDom<'a> {
     /// Internal parser
     parser: Parser<'a>,
@@ -54,14 +61,63 @@ impl<'a> VDom<'a> {
                 .iter()
                 .enumerate()
                 .find(|(_, node)| {
-                    node.as_tag().map_or(false, |tag| {
-                        tag._attributes.id.as_ref().map_or(false, |x| x.eq(&bytes))
+                    node.as_tag().is_some_and(|tag| {
+                        tag._attributes.id.as_ref().is_some_and(|x| x.eq(&bytes))
                     })
                 })
                 .map(|(id, _)| NodeHandle::new(id as InnerNodeHandle))
         }
     }
 
+    /// Returns a list of elements with the given tag name, in document order.
+    ///
+    /// Tag names are compared ASCII case-insensitively.
+    /// This is a linear scan over all nodes, unless tag name tracking is enabled with [`ParserOptions::track_tags`].
+    /// If tag names are interned with [`ParserOptions::intern_tag_names`], the scan compares ids instead of names.
+    ///
+    /// # Example
+    /// ```
+    /// let dom = tl::parse("<div><P>a</P><br><p>b</p></div>", Default::default()).unwrap();
+    /// assert_eq!(dom.get_elements_by_tag_name("p").count(), 2);
+    /// assert_eq!(dom.get_elements_by_tag_name("BR").count(), 1);
+    /// ```
+    pub fn get_elements_by_tag_name<'b>(
+        &'b self,
+        name: &'b str,
+    ) -> Box<dyn Iterator<Item = NodeHandle> + 'b> {
+        let parser = self.parser();
+
+        if parser.options.is_tracking_tags() {
+            parser
+                .tag_names
+                .get(&crate::util::to_lower_bytes(name.as_bytes()))
+                .map(|x| Box::new(x.iter().copied()) as Box<dyn Iterator<Item = NodeHandle>>)
+                .unwrap_or_else(|| Box::new(std::iter::empty()))
+        } else {
+            // names are interned case-sensitively, so there can be more than one id for the name
+            let name_ids: Vec<_> = parser
+                .interned_tag_name_ids(|n| n.eq_ignore_ascii_case(name.as_bytes()))
+                .collect();
+
+            let iter = self
+                .nodes()
+                .iter()
+                .enumerate()
+                .filter_map(move |(id, node)| {
+                    node.as_tag().and_then(|tag| {
+                        let matches = match tag._name_id {
+                            Some(name_id) => name_ids.contains(&name_id),
+                            None => tag.name().as_bytes().eq_ignore_ascii_case(name.as_bytes()),
+                        };
+
+                        matches.then(|| NodeHandle::new(id as InnerNodeHandle))
+                    })
+                });
+
+            Box::new(iter)
+        }
+    }
+
     /// Returns a list of elements that match a given class name.
     pub fn get_elements_by_class_name<'b>(
         &'b self,
@@ -101,6 +157,7 @@ impl<'a> VDom<'a> {
     ///
     /// # Order
     /// The order of the returned nodes is the same as the order of the nodes in the HTML document.
+    /// Nodes that have been added or detached after parsing do not follow this order, and detached nodes are still included.
     pub fn nodes(&self) -> &[Node<'a>] {
         &self.parser.tags
     }
@@ -123,12 +180,310 @@ impl<'a> VDom<'a> {
         &mut self.parser.ast
     }
 
+    /// Returns an iterator over all comments in the document, in the order in which they were parsed
+    ///
+    /// The comments include the `<!--` and `-->` delimiters. Use [`VDom::comment_contents`] to get the text between them.
+    /// Like [`VDom::nodes`], this includes comments that have been detached from the tree.
+    /// Comments are only kept if [`ParserOptions::preserve_comments`] is enabled, which is the default.
+    ///
+    /// # Example
+    /// ```
+    /// let dom = tl::parse("<!-- a --><p>x<!--b--></p>", Default::default()).unwrap();
+    /// let comments = dom.comments().map(|c| c.as_utf8_str()).collect::<Vec<_>>();
+    /// assert_eq!(comments, ["<!-- a -->", "<!--b-->"]);
+    /// ```
+    pub fn comments(&self) -> impl Iterator<Item = &Bytes<'a>> + '_ {
+        self.parser.tags.iter().filter_map(Node::as_comment)
+    }
+
+    /// Returns an iterator over the text of all comments in the document, without the `<!--` and `-->` delimiters
+    ///
+    /// Whitespace around the text is kept. See [`VDom::comments`] for which comments are included.
+    ///
+    /// # Example
+    /// ```
+    /// let dom = tl::parse("<!--[if IE]><p>old</p><![endif]--><!-- x -->", Default::default()).unwrap();
+    /// let contents = dom.comment_contents().collect::<Vec<_>>();
+    /// assert_eq!(contents, ["[if IE]><p>old</p><![endif]", " x "]);
+    /// ```
+    pub fn comment_contents(&self) -> impl Iterator<Item = Cow<'_, str>> + '_ {
+        self.comments()
+            .map(|c| String::from_utf8_lossy(comment_content(c.as_bytes())))
+    }
+
+    /// Returns the first topmost tag, which is usually the `<html>` element
+    ///
+    /// Text and comment nodes before it, such as whitespace after the `<!DOCTYPE>`, are skipped.
+    ///
+    /// # Example
+    /// ```
+    /// let dom = tl::parse("<!DOCTYPE html>\n<!-- c --><html><body></body></html>", Default::default()).unwrap();
+    /// let html = dom.root_element().unwrap();
+    /// assert_eq!(html.get(dom.parser()).unwrap().as_tag().unwrap().name(), "html");
+    /// ```
+    pub fn root_element(&self) -> Option<NodeHandle> {
+        self.parser
+            .ast
+            .iter()
+            .copied()
+            .find(|h| h.get(&self.parser).is_some_and(|n| n.as_tag().is_some()))
+    }
+
+    /// Returns the `<head>` element
+    ///
+    /// See [`VDom::body`] for where it is searched.
+    pub fn head(&self) -> Option<NodeHandle> {
+        self.document_section(b"head")
+    }
+
+    /// Returns the `<body>` element
+    ///
+    /// It is searched among the children of the `<html>` root element, and among the topmost nodes
+    /// for documents without an `<html>` tag. Like the rest of the parser, this does not create missing elements.
+    ///
+    /// # Example
+    /// ```
+    /// let dom = tl::parse("<html><head><title>x</title></head><body><p>y</p></body></html>", Default::default()).unwrap();
+    /// let parser = dom.parser();
+    /// let body = dom.body().unwrap().get(parser).unwrap();
+    /// assert_eq!(body.inner_text(parser), "y");
+    /// assert_eq!(dom.head().unwrap().get(parser).unwrap().inner_text(parser), "x");
+    ///
+    /// let fragment = tl::parse("<p>no body</p>", Default::default()).unwrap();
+    /// assert_eq!(fragment.body(), None);
+    /// ```
+    pub fn body(&self) -> Option<NodeHandle> {
+        self.document_section(b"body")
+    }
+
+    /// Finds a child of the `<html>` element or a topmost tag with the given name
+    fn document_section(&self, name: &[u8]) -> Option<NodeHandle> {
+        let parser = &self.parser;
+        let tag = |h: &NodeHandle| h.get(parser).and_then(Node::as_tag);
+
+        let html_children = self
+            .root_element()
+            .as_ref()
+            .and_then(tag)
+            .filter(|t| t.name().eq_ignore_ascii_case(b"html"))
+            .map_or(&[][..], |t| t._children.as_slice());
+
+        html_children
+            .iter()
+            .chain(parser.ast.iter())
+            .copied()
+            .find(|h| tag(h).is_some_and(|t| t.name().eq_ignore_ascii_case(name)))
+    }
+
+    /// Walks the DOM depth-first in document order, calling the visitor for every node
+    ///
+    /// Only nodes that are part of the tree are visited, detached nodes are skipped.
+    /// See [`Visitor`] for an example.
+    pub fn walk<V: Visitor<'a> + ?Sized>(&self, visitor: &mut V) {
+        visitor::walk(&self.parser, &self.parser.ast, visitor)
+    }
+
+    /// Walks the DOM depth-first in document order, allowing the visitor to modify every node
+    ///
+    /// See [`VisitorMut`] for an example.
+    pub fn walk_mut<V: VisitorMut<'a> + ?Sized>(&mut self, visitor: &mut V) {
+        let roots = self.parser.ast.clone();
+        visitor::walk_mut(&mut self.parser, &roots, visitor)
+    }
+
+    /// Removes a node from the tree by removing it from the children of its parent, or from the topmost nodes.
+    ///
+    /// Returns `false` if the node was not attached to the tree.
+    ///
+    /// The node and its descendants are not removed from the nodes table, they are simply no longer referenced.
+    /// This means that [`VDom::nodes`] still includes detached nodes, and so do lookups that scan all nodes,
+    /// such as [`VDom::query_selector`] or [`VDom::get_element_by_id`].
+    /// Serialization methods like [`VDom::outer_html`] only visit nodes that are part of the tree.
+    ///
+    /// # Example
+    /// ```
+    /// let mut dom = tl::parse("<ul><li>a</li><li>b</li></ul>", Default::default()).unwrap();
+    /// let first = dom.query_selector_first("li").unwrap();
+    /// assert!(dom.detach(first));
+    /// assert_eq!(dom.outer_html(), "<ul><li>b</li></ul>");
+    /// ```
+    pub fn detach(&mut self, handle: NodeHandle) -> bool {
+        let parser = &mut self.parser;
+
+        match handle.parent(parser) {
+            Some(parent) => parent
+                .get_mut(parser)
+                .and_then(Node::as_tag_mut)
+                .is_some_and(|tag| tag.children_mut().remove(handle)),
+            None => match parser.ast.iter().position(|&h| h == handle) {
+                Some(index) => {
+                    parser.ast.remove(index);
+                    true
+                }
+                None => false,
+            },
+        }
+    }
+
+    /// Wraps a node in a new element with the given name, and returns a handle to the new element
+    ///
+    /// The new element takes the place of `target` in the children of its parent (or in the topmost nodes),
+    /// and `target` becomes its only child. If `target` is not attached to the tree, neither is the wrapper.
+    ///
+    /// Returns `None` if the handle does not belong to this DOM.
+    ///
+    /// # Example
+    /// ```
+    /// let mut dom = tl::parse(r#"<p>see <img src="a.png"></p>"#, Default::default()).unwrap();
+    /// let img = dom.query_selector_first("img").unwrap();
+    /// let a = dom.wrap(img, "a").unwrap();
+    ///
+    /// let link = a.get_mut(dom.parser_mut()).unwrap().as_tag_mut().unwrap();
+    /// link.set_attribute("href", "a.png");
+    /// assert_eq!(dom.outer_html(), r#"<p>see <a href="a.png"><img src="a.png"></a></p>"#);
+    /// ```
+    pub fn wrap(&mut self, target: NodeHandle, wrapper_name: &str) -> Option<NodeHandle> {
+        let parser = &mut self.parser;
+        target.get(parser)?;
+
+        // The parent has to be found before the wrapper is added, since that is a parent of `target` too
+        let parent = target.parent(parser);
+
+        let mut wrapper = HTMLTag::create(wrapper_name);
+        wrapper.children_mut().append(target);
+        let wrapper = parser.push_node(Node::Tag(wrapper));
+
+        replace_child(parser, parent, target, wrapper);
+        Some(wrapper)
+    }
+
+    /// Replaces a node in the tree with another node, at the same position among its siblings
+    ///
+    /// Returns `false` and does nothing if `target` is not attached to the tree.
+    /// `target` and its descendants stay in the nodes table, like with [`VDom::detach`].
+    /// Replacing a node with one that is already part of the tree does not detach it from its current parent.
+    ///
+    /// # Example
+    /// ```
+    /// let mut dom = tl::parse("<div><script>alert(1)</script><p>a</p></div>", Default::default()).unwrap();
+    /// let script = dom.query_selector_first("script").unwrap();
+    /// let placeholder = dom.parser_mut().push_node(tl::Node::Comment("<!-- removed -->".into()));
+    ///
+    /// assert!(dom.replace_with(script, placeholder));
+    /// assert_eq!(dom.outer_html(), "<div><!-- removed --><p>a</p></div>");
+    /// ```
+    pub fn replace_with(&mut self, target: NodeHandle, replacement: NodeHandle) -> bool {
+        let parent = target.parent(&self.parser);
+        replace_child(&mut self.parser, parent, target, replacement)
+    }
+
+    /// Copies a node and all of its descendants, and returns a handle to the copy
+    ///
+    /// Every descendant is added to the nodes table as a new node, and the children of the copied tags
+    /// refer to the copies, so changing the copy does not affect the original subtree.
+    /// Like nodes added through [`Parser::push_node`], the copy is not attached to the tree
+    /// and is not registered in the ID, class or tag name lookup tables.
+    ///
+    /// Returns `None` if the handle does not belong to this DOM.
+    ///
+    /// # Example
+    /// ```
+    /// let mut dom = tl::parse("<div><p>a</p></div>", Default::default()).unwrap();
+    /// let p = dom.query_selector_first("p").unwrap();
+    /// let copy = dom.clone_subtree(p).unwrap();
+    ///
+    /// let div = dom.children()[0];
+    /// let parser = dom.parser_mut();
+    /// div.get_mut(parser).unwrap().as_tag_mut().unwrap().children_mut().append(copy);
+    /// assert_eq!(dom.outer_html(), "<div><p>a</p><p>a</p></div>");
+    /// ```
+    pub fn clone_subtree(&mut self, handle: NodeHandle) -> Option<NodeHandle> {
+        let parser = &mut self.parser;
+        let mut root = None;
+        // (node to copy, copy of its parent)
+        let mut stack: Vec<(NodeHandle, Option<NodeHandle>)> = vec![(handle, None)];
+
+        while let Some((original, parent)) = stack.pop() {
+            let mut node = original.get(parser)?.clone();
+            let children = match &mut node {
+                Node::Tag(tag) => std::mem::replace(&mut tag._children, InlineVec::new()),
+                _ => InlineVec::new(),
+            };
+            let copy = parser.push_node(node);
+
+            match parent {
+                Some(parent) => {
+                    if let Some(Node::Tag(tag)) = parent.get_mut(parser) {
+                        tag._children.push(copy);
+                    }
+                }
+                None => root = Some(copy),
+            }
+
+            // Children are pushed in reverse so that they are copied in document order
+            stack.extend(
+                children
+                    .as_slice()
+                    .iter()
+                    .rev()
+                    .map(|&child| (child, Some(copy))),
+            );
+        }
+
+        root
+    }
+
     /// Returns the HTML version.
     /// This is determined by the `<!DOCTYPE>` tag
     pub fn version(&self) -> Option<HTMLVersion> {
         self.parser.version
     }
 
+    /// Returns whether some tags were kept as text because they exceeded the depth limit set with [`ParserOptions::max_depth`].
+    pub fn depth_limit_exceeded(&self) -> bool {
+        self.parser.depth_limit_exceeded
+    }
+
+    /// Returns the character encoding declared by a `<meta>` tag, if any.
+    ///
+    /// Both the HTML5 form `<meta charset="utf-8">` and the legacy form
+    /// `<meta http-equiv="Content-Type" content="text/html; charset=utf-8">` are recognized.
+    /// The first declaration in document order wins. The encoding name is returned as written.
+    ///
+    /// The document is not transcoded. If it uses a different encoding,
+    /// the input can be decoded and parsed again.
+    ///
+    /// # Example
+    /// ```
+    /// let dom = tl::parse(r#"<head><meta charset="ISO-8859-1"></head>"#, Default::default()).unwrap();
+    /// assert_eq!(dom.meta_charset().as_deref(), Some("ISO-8859-1"));
+    /// ```
+    pub fn meta_charset(&self) -> Option<String> {
+        self.get_elements_by_tag_name("meta").find_map(|handle| {
+            let tag = handle.get(self.parser())?.as_tag()?;
+            let attribute = |name: &str| {
+                tag.attributes()
+                    .iter()
+                    .find(|(key, _)| key.eq_ignore_ascii_case(name))
+                    .and_then(|(_, value)| value)
+            };
+
+            let charset = match attribute("charset") {
+                Some(charset) => charset.trim().to_string(),
+                None => {
+                    let http_equiv = attribute("http-equiv")?;
+                    if !http_equiv.trim().eq_ignore_ascii_case("content-type") {
+                        return None;
+                    }
+
+                    charset_from_content_type(&attribute("content")?)?
+                }
+            };
+
+            (!charset.is_empty()).then_some(charset)
+        })
+    }
+
     /// Returns the contained markup of all of the elements in this DOM.
     ///
     /// Equivalent to [Element#outerHTML](https://developer.mozilla.org/en-US/docs/Web/API/Element/outerHTML) in browsers)
@@ -154,12 +509,106 @@ impl<'a> VDom<'a> {
 
         for node in self.children() {
             let node = node.get(&self.parser).unwrap();
-            inner_html.push_str(&node.outer_html(&self.parser));
+            node.outer_html_to(&self.parser, &mut inner_html)
+                .expect("writing to a String cannot fail");
         }
 
         inner_html
     }
 
+    /// Returns the text of the whole document, without any markup.
+    ///
+    /// This is similar to calling [`Node::inner_text`] on every top level node and concatenating the results,
+    /// with a few differences that make the result closer to what is visible in a browser:
+    /// - Comments and the contents of `<script>` and `<style>` tags are skipped
+    /// - A space is inserted around block-level elements such as `<p>` or `<div>`,
+    ///   unless there already is whitespace, so that words in adjacent blocks do not run together
+    ///
+    /// Character references are not decoded. Use [`VDom::text_decoded`] for that.
+    ///
+    /// # Example
+    /// ```
+    /// let dom = tl::parse("<h1>Title</h1><p>Hello <b>world</b></p><!-- hidden -->", Default::default()).unwrap();
+    /// assert_eq!(dom.text(), "Title Hello world");
+    /// ```
+    pub fn text(&self) -> String {
+        self.collect_text(false)
+    }
+
+    /// Returns the text of the whole document like [`VDom::text`], with HTML character references decoded.
+    ///
+    /// This is roughly what a user sees when viewing the page.
+    /// References are decoded in a single pass, so `&amp;amp;` becomes `&amp;`.
+    ///
+    /// # Example
+    /// ```
+    /// let dom = tl::parse("<p>Fish&nbsp;&amp;&nbsp;Chips &#8212; &amp;amp;</p>", Default::default()).unwrap();
+    /// assert_eq!(dom.text_decoded(), "Fish\u{a0}&\u{a0}Chips \u{2014} &amp;");
+    /// ```
+    pub fn text_decoded(&self) -> String {
+        self.collect_text(true)
+    }
+
+    fn collect_text(&self, decode: bool) -> String {
+        let mut out = String::new();
+
+        write_text(&self.parser, self.children(), decode, &mut out);
+
+        let trimmed = out.trim_end().len();
+        out.truncate(trimmed);
+        out
+    }
+
+    /// Returns the markup of this DOM, formatted with one node per line and children indented by `indent`.
+    ///
+    /// This is meant for making documents easier to read, e.g. when debugging.
+    /// Text nodes are trimmed and whitespace-only text is left out, so whitespace is not preserved exactly,
+    /// but parsing the output again yields an equivalent tree.
+    /// Elements that only contain text are kept on a single line, and the contents of
+    /// `<pre>`, `<textarea>`, `<script>` and `<style>` tags are written as they are.
+    ///
+    /// # Example
+    /// ```
+    /// let dom = tl::parse("<div><p>Hello <b>world</b></p><br><!-- x --></div>", Default::default()).unwrap();
+    /// assert_eq!(dom.to_pretty_html("  "), "\
+    /// <div>
+    ///   <p>
+    ///     Hello
+    ///     <b>world</b>
+    ///   </p>
+    ///   <br>
+    ///   <!-- x -->
+    /// </div>
+    /// ");
+    /// ```
+    pub fn to_pretty_html(&self, indent: &str) -> String {
+        let mut out = String::with_capacity(self.parser.stream.len());
+
+        write_pretty(&self.parser, self.children(), indent, &mut out)
+            .expect("writing to a String cannot fail");
+
+        out
+    }
+
+    /// Returns the markup of this DOM without insignificant whitespace
+    ///
+    /// Runs of whitespace in text are collapsed into a single space, and whitespace next to the start or end tag
+    /// of a block element (e.g. between `</p>` and `<p>`) is left out. Whitespace between inline content is kept,
+    /// so that `<b>a</b> <i>b</i>` still renders with a space. Comments are kept, and the contents of
+    /// `<pre>`, `<textarea>`, `<script>` and `<style>` tags are written as they are.
+    ///
+    /// # Example
+    /// ```
+    /// let dom = tl::parse("<div>\n  <p>Hello,\n    <b>world</b> !</p>\n  <pre> x  y </pre>\n</div>", Default::default()).unwrap();
+    /// assert_eq!(dom.to_minified_html(), "<div><p>Hello, <b>world</b> !</p><pre> x  y </pre></div>");
+    /// ```
+    pub fn to_minified_html(&self) -> String {
+        let mut out = String::with_capacity(self.parser.stream.len());
+        write_minified(&self.parser, self.children(), &mut out)
+            .expect("writing to a String cannot fail");
+        out
+    }
+
     /// Tries to parse the query selector and returns an iterator over elements that match the given query selector.
     ///
     /// # Example
@@ -177,6 +626,360 @@ impl<'a> VDom<'a> {
         let iter = queryselector::QuerySelectorIterator::new(selector, self.parser(), self);
         Some(iter)
     }
+
+    /// Returns an iterator over elements that match the given, already parsed query selector.
+    ///
+    /// This is useful if the same selector is used many times: parse it once with [`crate::parse_query_selector`]
+    /// and reuse the result, instead of parsing the string on every call to [`VDom::query_selector`].
+    ///
+    /// # Example
+    /// ```
+    /// let links = tl::parse_query_selector("a[href]").unwrap();
+    ///
+    /// for input in ["<a href=\"/a\">a</a>", "<a>b</a><a href=\"/c\">c</a>"] {
+    ///     let dom = tl::parse(input, Default::default()).unwrap();
+    ///     assert_eq!(dom.query_selector_parsed(&links).count(), 1);
+    /// }
+    /// ```
+    pub fn query_selector_parsed<'b>(
+        &'b self,
+        selector: &'b Selector<'b>,
+    ) -> QuerySelectorIterator<'a, 'b, Self> {
+        queryselector::QuerySelectorIterator::borrowed(selector, self.parser(), self)
+    }
+
+    /// Tries to parse the query selector and returns the first element that matches it.
+    ///
+    /// This is a shorthand for `query_selector(selector).and_then(|mut iter| iter.next())`.
+    /// Note that `None` is returned both if the selector is invalid and if no element matches it.
+    /// Use [`VDom::query_selector`] if you need to tell these cases apart.
+    ///
+    /// # Example
+    /// ```
+    /// let dom = tl::parse("<div><p class=\"foo\">bar</div>", tl::ParserOptions::default()).unwrap();
+    /// let handle = dom.query_selector_first("p.foo").unwrap();
+    /// let node = handle.get(dom.parser()).unwrap();
+    /// assert_eq!(node.inner_text(dom.parser()), "bar");
+    /// assert!(dom.query_selector_first("span").is_none());
+    /// ```
+    pub fn query_selector_first(&self, selector: &str) -> Option<NodeHandle> {
+        self.query_selector(selector)?.next()
+    }
+
+    /// Returns the closest ancestor of the given node that matches the query selector, starting with the node itself.
+    ///
+    /// `None` is returned if the selector is invalid or if neither the node nor any of its ancestors match it.
+    ///
+    /// # Example
+    /// ```
+    /// let dom = tl::parse(r#"<ul class="menu"><li><a>link</a></li></ul>"#, Default::default()).unwrap();
+    /// let link = dom.query_selector_first("a").unwrap();
+    /// assert_eq!(dom.closest(link, "li"), dom.query_selector_first("li"));
+    /// assert_eq!(dom.closest(link, "a"), Some(link));
+    /// assert_eq!(dom.closest(link, "div"), None);
+    /// ```
+    pub fn closest(&self, start: NodeHandle, selector: &str) -> Option<NodeHandle> {
+        let selector = crate::parse_query_selector(selector)?;
+        let context = MatchContext::new(&self.parser);
+
+        std::iter::successors(Some(start), |&handle| context.parent(handle))
+            .find(|&handle| selector.matches_in(&context, handle))
+    }
+}
+
+/// Extracts the charset parameter from a `Content-Type` value like `text/html; charset=utf-8`
+fn charset_from_content_type(content: &str) -> Option<String> {
+    let lower = content.to_ascii_lowercase();
+    let start = lower.find("charset")? + "charset".len();
+    let rest = content[start..]
+        .trim_start()
+        .strip_prefix('=')?
+        .trim_start();
+
+    let value = match rest.strip_prefix(['"', '\'']) {
+        Some(quoted) => quoted.split(['"', '\'']).next()?,
+        None => rest.split([';', ' ', '\t']).next()?,
+    };
+
+    Some(value.trim().to_string())
+}
+
+/// Writes the text of the given nodes and their children for [`VDom::text`]
+///
+/// The text of block elements is separated from the surrounding text.
+fn write_text(parser: &Parser<'_>, handles: &[NodeHandle], decode: bool, out: &mut String) {
+    fn separate(out: &mut String) {
+        if out.chars().next_back().is_some_and(|c| !c.is_whitespace()) {
+            out.push(' ');
+        }
+    }
+
+    let enter = |node: &Node<'_>, _: &Visit<'_, '_>, out: &mut String| {
+        match node {
+            // Each text node is decoded on its own, so references cannot span multiple nodes
+            Node::Raw(text) if decode => {
+                out.push_str(&crate::entities::decode(&text.as_utf8_str()))
+            }
+            Node::Raw(text) | Node::CData(text) => out.push_str(&text.as_utf8_str()),
+            Node::Tag(tag) => {
+                if util::is_tag_in(tag.name().as_bytes(), &[b"script", b"style"]) {
+                    return Ok(false);
+                }
+
+                if is_block_element(tag) {
+                    separate(out);
+                }
+                return Ok(true);
+            }
+            Node::Comment(_) => {}
+        }
+
+        Ok(false)
+    };
+
+    let leave = |tag: &HTMLTag<'_>, _, out: &mut String| {
+        if is_block_element(tag) {
+            separate(out);
+        }
+        Ok(())
+    };
+
+    walk(parser, handles, out, enter, leave).expect("writing to a String cannot fail");
+}
+
+/// Checks whether a tag is rendered as a block, which starts on a new line
+fn is_block_element(tag: &HTMLTag<'_>) -> bool {
+    util::is_tag_in(tag.name().as_bytes(), constants::BLOCK_TAGS)
+}
+
+/// Checks whether the first of the given sibling nodes that is rendered is a block element
+///
+/// `in_block` is returned if there is no such node, i.e. the siblings are at the start or end of their parent.
+/// Comments are skipped, since they are not rendered.
+fn is_next_to_block<'h>(
+    parser: &Parser<'_>,
+    siblings: impl Iterator<Item = &'h NodeHandle>,
+    in_block: bool,
+) -> bool {
+    for handle in siblings {
+        match handle.get(parser) {
+            Some(Node::Comment(_)) | None => continue,
+            Some(Node::Tag(tag)) => return is_block_element(tag),
+            Some(_) => return false,
+        }
+    }
+
+    in_block
+}
+
+/// Writes the given nodes and their children without insignificant whitespace, see [`VDom::to_minified_html`]
+fn write_minified(parser: &Parser<'_>, handles: &[NodeHandle], out: &mut String) -> fmt::Result {
+    let enter = |node: &Node<'_>, visit: &Visit<'_, '_>, out: &mut String| {
+        match node {
+            // Whitespace next to the start or end of a block is not rendered, including the start and end of the parent.
+            // The topmost nodes are treated like the contents of a block.
+            Node::Raw(text) => {
+                let in_block = visit.parent.is_none_or(is_block_element);
+                let (before, after) = visit.siblings.split_at(visit.index);
+                let trim_start = is_next_to_block(parser, before.iter().rev(), in_block);
+                let trim_end = is_next_to_block(parser, after[1..].iter(), in_block);
+                write_collapsed(&text.as_utf8_str(), trim_start, trim_end, out);
+            }
+            Node::Tag(tag) if tag.is_void_element(parser) => tag.write_start_tag(out, false)?,
+            Node::Tag(tag) if tag.preserves_whitespace() => tag.outer_html_to(parser, out)?,
+            Node::Tag(tag) => {
+                tag.write_start_tag(out, false)?;
+                return Ok(true);
+            }
+            node => node.outer_html_to(parser, out)?,
+        }
+
+        Ok(false)
+    };
+
+    let leave =
+        |tag: &HTMLTag<'_>, _, out: &mut String| write!(out, "</{}>", tag.name().as_utf8_str());
+
+    walk(parser, handles, out, enter, leave)
+}
+
+/// Writes text with runs of whitespace collapsed into a single space, optionally leaving out whitespace at either end
+fn write_collapsed(text: &str, trim_start: bool, trim_end: bool, out: &mut String) {
+    let mut words = text.split_ascii_whitespace();
+
+    let Some(first) = words.next() else {
+        // whitespace-only text separates inline content, unless it is next to a block
+        if !text.is_empty() && !trim_start && !trim_end {
+            out.push(' ');
+        }
+        return;
+    };
+
+    if !trim_start && text.starts_with(|c: char| c.is_ascii_whitespace()) {
+        out.push(' ');
+    }
+    out.push_str(first);
+    for word in words {
+        out.push(' ');
+        out.push_str(word);
+    }
+    if !trim_end && text.ends_with(|c: char| c.is_ascii_whitespace()) {
+        out.push(' ');
+    }
+}
+
+/// The position of a node visited by [`walk`]
+struct Visit<'p, 'a> {
+    /// The node and its siblings
+    siblings: &'p [NodeHandle],
+    /// The index of the node in `siblings`
+    index: usize,
+    /// The parent of the node, or `None` for the topmost nodes
+    parent: Option<&'p HTMLTag<'a>>,
+    /// The number of ancestors of the node
+    depth: usize,
+}
+
+/// Visits the given nodes and their descendants in document order
+///
+/// `enter` is called for every node and returns whether the children of a tag are visited as well,
+/// in which case `leave` is called with the tag and its depth after its children.
+/// Descendants are visited using a heap allocated stack instead of recursion,
+/// so that deeply nested trees cannot overflow the call stack.
+fn walk<'p, 'a>(
+    parser: &'p Parser<'a>,
+    handles: &'p [NodeHandle],
+    out: &mut String,
+    mut enter: impl FnMut(&'p Node<'a>, &Visit<'p, 'a>, &mut String) -> Result<bool, fmt::Error>,
+    mut leave: impl FnMut(&'p HTMLTag<'a>, usize, &mut String) -> fmt::Result,
+) -> fmt::Result {
+    // the tags whose children are being visited, with their children and the index of the next child
+    let mut stack: Vec<(Option<&'p HTMLTag<'a>>, &'p [NodeHandle], usize)> =
+        vec![(None, handles, 0)];
+
+    while let Some((parent, siblings, index)) = stack.last_mut() {
+        let (parent, siblings, position) = (*parent, *siblings, *index);
+        *index += 1;
+
+        let Some(handle) = siblings.get(position) else {
+            stack.pop();
+            if let Some(tag) = parent {
+                leave(tag, stack.len() - 1, out)?;
+            }
+            continue;
+        };
+
+        let visit = Visit {
+            siblings,
+            index: position,
+            parent,
+            depth: stack.len() - 1,
+        };
+
+        let Some(node) = handle.get(parser) else {
+            continue;
+        };
+
+        if let (true, Node::Tag(tag)) = (enter(node, &visit, out)?, node) {
+            stack.push((Some(tag), tag._children.as_slice(), 0));
+        }
+    }
+
+    Ok(())
+}
+
+/// Writes the given nodes and their children for [`VDom::to_pretty_html`]
+fn write_pretty(
+    parser: &Parser<'_>,
+    handles: &[NodeHandle],
+    indent: &str,
+    out: &mut String,
+) -> fmt::Result {
+    let write_indent = |out: &mut String, depth: usize| {
+        // looping up to the depth of every node adds up for deep trees, even if nothing is written
+        if indent.is_empty() {
+            return;
+        }
+
+        for _ in 0..depth {
+            out.push_str(indent);
+        }
+    };
+
+    let enter = |node: &Node<'_>, visit: &Visit<'_, '_>, out: &mut String| {
+        match node {
+            Node::Raw(text) => {
+                let text = text.as_utf8_str();
+                let text = text.trim();
+
+                if !text.is_empty() {
+                    write_indent(out, visit.depth);
+                    out.push_str(text);
+                    out.push('\n');
+                }
+            }
+            Node::Tag(tag) => {
+                write_indent(out, visit.depth);
+
+                let only_text = tag
+                    ._children
+                    .iter()
+                    .all(|child| child.get(parser).is_some_and(|n| n.as_raw().is_some()));
+
+                if tag.is_void_element(parser) {
+                    tag.write_start_tag(out, false)?;
+                } else if tag.preserves_whitespace() {
+                    tag.outer_html_to(parser, out)?;
+                } else if only_text {
+                    tag.write_start_tag(out, false)?;
+                    out.push_str(tag.inner_html(parser).trim());
+                    write!(out, "</{}>", tag.name().as_utf8_str())?;
+                } else {
+                    tag.write_start_tag(out, false)?;
+                    out.push('\n');
+                    return Ok(true);
+                }
+
+                out.push('\n');
+            }
+            _ => {
+                write_indent(out, visit.depth);
+                node.outer_html_to(parser, out)?;
+                out.push('\n');
+            }
+        }
+
+        Ok(false)
+    };
+
+    let leave = |tag: &HTMLTag<'_>, depth: usize, out: &mut String| {
+        write_indent(out, depth);
+        writeln!(out, "</{}>", tag.name().as_utf8_str())
+    };
+
+    walk(parser, handles, out, enter, leave)
+}
+
+/// Replaces `target` with `replacement` in the children of `parent`, or in the topmost nodes if there is no parent
+fn replace_child(
+    parser: &mut Parser<'_>,
+    parent: Option<NodeHandle>,
+    target: NodeHandle,
+    replacement: NodeHandle,
+) -> bool {
+    match parent {
+        Some(parent) => parent
+            .get_mut(parser)
+            .and_then(Node::as_tag_mut)
+            .is_some_and(|tag| tag.children_mut().replace(target, replacement)),
+        None => match parser.ast.iter_mut().find(|h| **h == target) {
+            Some(slot) => {
+                *slot = replacement;
+                true
+            }
+            None => false,
+        },
+    }
 }
 
 /// A RAII guarded version of VDom
@@ -187,8 +990,8 @@ impl<'a> VDom<'a> {
 pub struct VDomGuard {
     /// Wrapped VDom instance
     dom: VDom<'static>,
-    /// The leaked input string that is referenced by self.dom
-    _s: RawString,
+    /// The leaked input strings that are referenced by self.dom
+    _s: Vec<RawString>,
     /// PhantomData for self.dom
     _phantom: PhantomData<&'static str>,
 }
@@ -210,11 +1013,11 @@ impl VDomGuard {
         //    that, when dropped, will free the input string
         // b) fail, and we return a ParseError
         //    and `RawString`s destructor will run and deallocate the string properly
-        let mut parser = Parser::new(input_ref, options);
+        let mut parser = Parser::new(input_ref.as_bytes(), options);
         parser.parse()?;
 
         Ok(Self {
-            _s: input,
+            _s: vec![input],
             dom: VDom::from(parser),
             _phantom: PhantomData,
         })
@@ -222,6 +1025,36 @@ impl VDomGuard {
 }
 
 impl VDomGuard {
+    /// Parses another input string into this DOM
+    ///
+    /// The top level nodes of `input` are appended to the top level nodes of the DOM,
+    /// and all nodes share the same parser, so existing [`NodeHandle`](crate::NodeHandle)s stay valid.
+    /// Tags that were left open by a previous input are not continued.
+    ///
+    /// The ID, class and tag name lookup tables (if enabled) include the nodes of all inputs.
+    /// Note that [`HTMLTag::boundaries`](crate::HTMLTag::boundaries) is relative to the input that was parsed last,
+    /// and must not be used for tags of earlier inputs.
+    ///
+    /// If parsing fails, the nodes that were parsed up to the error are kept.
+    ///
+    /// # Example
+    /// ```
+    /// let mut dom = unsafe { tl::parse_owned("<header>Title</header>".into(), Default::default()) }.unwrap();
+    /// dom.append_input("<main><p>Hello</p></main>".into()).unwrap();
+    ///
+    /// let dom = dom.get_ref();
+    /// assert_eq!(dom.children().len(), 2);
+    /// assert_eq!(dom.outer_html(), "<header>Title</header><main><p>Hello</p></main>");
+    /// ```
+    pub fn append_input(&mut self, input: String) -> Result<(), ParseError> {
+        let input = RawString::new(input);
+        let input_ref: &'static str = unsafe { &*input.as_ptr() };
+
+        // The nodes of a failed parse may still reference the input, so it is kept either way
+        self._s.push(input);
+        self.dom.parser.parse_next(input_ref.as_bytes())
+    }
+
     /// Returns a reference to the inner DOM.
     ///
     /// The lifetime of the returned `VDom` is bound to self so that elements cannot outlive this `VDomGuard` struct.
@@ -237,6 +1070,77 @@ impl VDomGuard {
     }
 }
 
+/// An owned, cloneable version of VDom
+///
+/// The input string is kept in an [`Arc`] that is shared by all clones,
+/// while the parsed nodes are cloned, so changes made to one clone are not visible in the others.
+/// This makes it suitable for caching parsed documents and sharing them between threads.
+/// The only way to construct this is by calling `parse_shared()`.
+#[derive(Debug, Clone)]
+pub struct OwnedVDom {
+    /// Wrapped VDom instance
+    ///
+    /// This must be declared before `source` so that it is dropped first.
+    dom: VDom<'static>,
+    /// The input string that is referenced by self.dom
+    source: Arc<str>,
+}
+
+unsafe impl Send for OwnedVDom {}
+unsafe impl Sync for OwnedVDom {}
+
+impl OwnedVDom {
+    /// Parses the input string
+    pub(crate) fn parse(source: Arc<str>, options: ParserOptions) -> Result<OwnedVDom, ParseError> {
+        // SAFETY: the string data lives on the heap and is not moved or freed while `source` (or a clone of it) exists.
+        // Every `OwnedVDom` that holds nodes referencing it also holds a clone of the `Arc`,
+        // and the nodes are never handed out with a lifetime that outlives `self`.
+        let input_ref: &'static str = unsafe { &*(&*source as *const str) };
+
+        let mut parser = Parser::new(input_ref.as_bytes(), options);
+        parser.parse()?;
+
+        Ok(Self {
+            dom: VDom::from(parser),
+            source,
+        })
+    }
+
+    /// Returns the input string that this DOM was parsed from
+    #[inline]
+    pub fn source(&self) -> &Arc<str> {
+        &self.source
+    }
+
+    /// Returns a reference to the inner DOM.
+    ///
+    /// The lifetime of the returned `VDom` is bound to self so that elements cannot outlive this `OwnedVDom` struct.
+    #[inline]
+    pub fn get_ref<'a>(&'a self) -> &'a VDom<'a> {
+        &self.dom
+    }
+
+    /// Calls the given function with a mutable reference to the inner DOM and returns its result.
+    ///
+    /// The function must work with a DOM of any lifetime, so no borrowed data can be moved into
+    /// or out of the DOM, except for data that lives for `'static`.
+    ///
+    /// # Example
+    /// ```
+    /// let mut dom = tl::parse_shared("<p id=\"greeting\">Hello</p>", Default::default()).unwrap();
+    ///
+    /// dom.with_mut(|dom| {
+    ///     let tag = dom.get_element_by_id("greeting").unwrap().get_mut(dom.parser_mut()).unwrap();
+    ///     tag.as_tag_mut().unwrap().set_attribute("class", "big");
+    /// });
+    ///
+    /// assert_eq!(dom.get_ref().outer_html(), r#"<p id="greeting" class="big">Hello</p>"#);
+    /// ```
+    pub fn with_mut<R>(&mut self, f: impl for<'a> FnOnce(&mut VDom<'a>) -> R) -> R {
+        f(&mut self.dom)
+    }
+}
+
 #[derive(Debug)]
 struct RawString(*mut str);
 
@@ -0,0 +1,146 @@
+use crate::{Bytes, HTMLTag, Node, NodeHandle, Parser};
+
+/// Callbacks for walking the DOM with [`VDom::walk`](crate::VDom::walk)
+///
+/// Nodes are visited depth-first in document order, i.e. a tag is visited before its children.
+/// Every method has a default implementation that does nothing, so only the node types of interest need to be implemented.
+///
+/// # Example
+/// ```
+/// use tl::{HTMLTag, Visitor};
+///
+/// #[derive(Default)]
+/// struct Links(Vec<String>);
+///
+/// impl Visitor<'_> for Links {
+///     fn visit_tag(&mut self, tag: &HTMLTag<'_>) {
+///         if let Some(Some(href)) = tag.attributes().get("href") {
+///             self.0.push(href.as_utf8_str().into_owned());
+///         }
+///     }
+/// }
+///
+/// let dom = tl::parse(r#"<a href="/a">A</a><p><a href="/b">B</a></p>"#, Default::default()).unwrap();
+/// let mut links = Links::default();
+/// dom.walk(&mut links);
+/// assert_eq!(links.0, ["/a", "/b"]);
+/// ```
+pub trait Visitor<'a> {
+    /// Called for every tag, before its children are visited
+    fn visit_tag(&mut self, tag: &HTMLTag<'a>) {
+        let _ = tag;
+    }
+
+    /// Called for every text node
+    fn visit_text(&mut self, text: &Bytes<'a>) {
+        let _ = text;
+    }
+
+    /// Called for every comment, including the `<!--` and `-->`
+    fn visit_comment(&mut self, comment: &Bytes<'a>) {
+        let _ = comment;
+    }
+
+    /// Called for the contents of every CDATA section
+    fn visit_cdata(&mut self, cdata: &Bytes<'a>) {
+        let _ = cdata;
+    }
+}
+
+/// Callbacks for walking and modifying the DOM with [`VDom::walk_mut`](crate::VDom::walk_mut)
+///
+/// This is the mutable counterpart of [`Visitor`]. Nodes are visited in the same order.
+/// The children of a tag are read after [`VisitorMut::visit_tag`] returns,
+/// so changes to its children made by the visitor are taken into account.
+///
+/// # Example
+/// ```
+/// use tl::{Bytes, VisitorMut};
+///
+/// struct Uppercase;
+///
+/// impl<'a> VisitorMut<'a> for Uppercase {
+///     fn visit_text(&mut self, text: &mut Bytes<'a>) {
+///         let upper = text.as_utf8_str().to_uppercase();
+///         text.set(upper).unwrap();
+///     }
+/// }
+///
+/// let mut dom = tl::parse("<p>Hello <b>world</b></p>", Default::default()).unwrap();
+/// dom.walk_mut(&mut Uppercase);
+/// assert_eq!(dom.outer_html(), "<p>HELLO <b>WORLD</b></p>");
+/// ```
+pub trait VisitorMut<'a> {
+    /// Called for every tag, before its children are visited
+    fn visit_tag(&mut self, tag: &mut HTMLTag<'a>) {
+        let _ = tag;
+    }
+
+    /// Called for every text node
+    fn visit_text(&mut self, text: &mut Bytes<'a>) {
+        let _ = text;
+    }
+
+    /// Called for every comment, including the `<!--` and `-->`
+    fn visit_comment(&mut self, comment: &mut Bytes<'a>) {
+        let _ = comment;
+    }
+
+    /// Called for the contents of every CDATA section
+    fn visit_cdata(&mut self, cdata: &mut Bytes<'a>) {
+        let _ = cdata;
+    }
+}
+
+/// Pushes the handles in reverse order, so that popping them yields document order
+fn push_reversed(stack: &mut Vec<NodeHandle>, handles: &[NodeHandle]) {
+    stack.extend(handles.iter().rev().copied());
+}
+
+/// Visits the given nodes and their descendants
+///
+/// A heap allocated stack is used instead of recursion, so that deeply nested trees cannot overflow the call stack.
+pub(crate) fn walk<'a, V: Visitor<'a> + ?Sized>(
+    parser: &Parser<'a>,
+    roots: &[NodeHandle],
+    visitor: &mut V,
+) {
+    let mut stack = Vec::new();
+    push_reversed(&mut stack, roots);
+
+    while let Some(handle) = stack.pop() {
+        match handle.get(parser) {
+            Some(Node::Tag(tag)) => {
+                visitor.visit_tag(tag);
+                push_reversed(&mut stack, tag.children().top().as_slice());
+            }
+            Some(Node::Raw(text)) => visitor.visit_text(text),
+            Some(Node::Comment(comment)) => visitor.visit_comment(comment),
+            Some(Node::CData(cdata)) => visitor.visit_cdata(cdata),
+            None => {}
+        }
+    }
+}
+
+/// Visits the given nodes and their descendants, allowing the visitor to modify them
+pub(crate) fn walk_mut<'a, V: VisitorMut<'a> + ?Sized>(
+    parser: &mut Parser<'a>,
+    roots: &[NodeHandle],
+    visitor: &mut V,
+) {
+    let mut stack = Vec::new();
+    push_reversed(&mut stack, roots);
+
+    while let Some(handle) = stack.pop() {
+        match handle.get_mut(parser) {
+            Some(Node::Tag(tag)) => {
+                visitor.visit_tag(tag);
+                push_reversed(&mut stack, tag.children().top().as_slice());
+            }
+            Some(Node::Raw(text)) => visitor.visit_text(text),
+            Some(Node::Comment(comment)) => visitor.visit_comment(comment),
+            Some(Node::CData(cdata)) => visitor.visit_cdata(cdata),
+            None => {}
+        }
+    }
+}